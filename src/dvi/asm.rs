@@ -0,0 +1,940 @@
+use std::char::REPLACEMENT_CHARACTER;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+use crate::dvi::disassembler::disassemble;
+use crate::dvi::opcodes::OpCode;
+pub use crate::dvi::units::Unit;
+use crate::dvi::units::{TEX_DENOMINATOR, TEX_MAGNIFICATION, TEX_NUMERATOR};
+use crate::dvi::writer::DviWriter;
+use crate::errors::{DviousError, DviousResult};
+
+const DEFAULT_ID: u8 = 2;
+
+/// Parses the text format of dviasm and returns the complete DVI file. Like dviasm,
+/// the font definitions are written after the preamble and again in the postamble,
+/// and fonts are numbered in the order in which they are defined. The postamble is
+/// generated by the `DviWriter`, except for `maxv` and `maxh` if the text has them.
+pub fn assemble(text: &str) -> DviousResult<Vec<u8>> {
+    let mut parser = DviasmParser::new();
+    for (index, line) in text.lines().enumerate() {
+        parser.parse_line(line).map_err(|why| match why {
+            DviousError::AsmParseError(message) => {
+                DviousError::AsmParseError(format!("Line {}: {}", index + 1, message))
+            }
+            other => other,
+        })?;
+    }
+
+    let mut writer = DviWriter::new(Vec::new());
+    writer.set_max_extents(parser.max_v, parser.max_h);
+    for opcode in parser.into_opcodes() {
+        writer.write(&opcode)?;
+    }
+    writer.write_postamble()?;
+    writer.finish()
+}
+
+/// Parses the text format of dviasm into the opcodes of the DVI file that
+/// `assemble` returns
+pub fn parse(text: &str) -> DviousResult<Vec<OpCode>> {
+    disassemble(assemble(text)?)
+}
+
+struct Font {
+    name: String,
+    design_size: i32,
+    scaled_size: i32,
+}
+
+struct Page {
+    counts: [i32; 10],
+    content: Vec<OpCode>,
+}
+
+struct DviasmParser {
+    id: u8,
+    num: u32,
+    den: u32,
+    mag: u32,
+    comment: Vec<u8>,
+    max_v: Option<u32>,
+    max_h: Option<u32>,
+    fonts: Vec<Font>,
+    pages: Vec<Page>,
+}
+
+impl DviasmParser {
+    fn new() -> DviasmParser {
+        DviasmParser {
+            id: DEFAULT_ID,
+            num: TEX_NUMERATOR,
+            den: TEX_DENOMINATOR,
+            mag: TEX_MAGNIFICATION,
+            comment: Vec::new(),
+            max_v: None,
+            max_h: None,
+            fonts: Vec::new(),
+            pages: Vec::new(),
+        }
+    }
+
+    fn parse_line(&mut self, line: &str) -> DviousResult<()> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('%') {
+            return Ok(());
+        }
+
+        let (key, value) = match line.find(':') {
+            Some(colon) => (line[..colon].trim(), line[colon + 1..].trim()),
+            None => return self.parse_section(line),
+        };
+
+        match key {
+            // Preamble
+            "id" => {
+                self.id = u8::try_from(parse_int(value)?)
+                    .map_err(|_| DviousError::AsmParseError(format!("Invalid id [{}]", value)))?
+            }
+            "numerator" => self.num = parse_positive(value, "numerator")?,
+            "denominator" => self.den = parse_positive(value, "denominator")?,
+            "magnification" => self.mag = parse_positive(value, "magnification")?,
+            "comment" => self.comment = parse_bytes(value)?,
+
+            // Postamble, the number of pages and the stack depth are computed
+            "maxv" => self.max_v = Some(self.parse_extent(value)?),
+            "maxh" => self.max_h = Some(self.parse_extent(value)?),
+            "maxs" | "pages" => {}
+
+            // Font definitions
+            "fntdef" => {
+                let font = self.parse_font(value)?;
+                self.fonts.push(font);
+            }
+
+            // Pages
+            _ => {
+                let opcodes = self.parse_command(key, value)?;
+                match self.pages.last_mut() {
+                    Some(page) => page.content.extend(opcodes),
+                    None => {
+                        return Err(DviousError::AsmParseError(format!(
+                            "Command [{}] outside of a page",
+                            key
+                        )))
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Section headers like `[preamble]` are only informative, except for pages
+    fn parse_section(&mut self, line: &str) -> DviousResult<()> {
+        let line = line.trim_end_matches(']');
+        let mut words = line.split(' ').filter(|word| !word.is_empty());
+        if words.next() != Some("[page") {
+            return Ok(());
+        }
+
+        let mut counts = [0; 10];
+        for (count, word) in counts.iter_mut().zip(words) {
+            *count = word.parse::<i32>().map_err(|_| {
+                DviousError::AsmParseError(format!("Invalid page count [{}]", word))
+            })?;
+        }
+        self.pages.push(Page {
+            counts,
+            content: Vec::new(),
+        });
+        Ok(())
+    }
+
+    fn parse_command(&mut self, key: &str, value: &str) -> DviousResult<Vec<OpCode>> {
+        let opcode = match key {
+            "set" => {
+                let characters = parse_string(value)?;
+                return Ok(characters.into_iter().map(|c| OpCode::Set { c }).collect());
+            }
+            "put" => {
+                let characters = parse_string(value)?;
+                return Ok(characters.into_iter().map(|c| OpCode::Put { c }).collect());
+            }
+            "setrule" => {
+                let (a, b) = self.parse_rule(value)?;
+                OpCode::SetRule { a, b }
+            }
+            "putrule" => {
+                let (a, b) = self.parse_rule(value)?;
+                OpCode::PutRule { a, b }
+            }
+            "xxx" => {
+                let x = parse_bytes(value)?;
+                OpCode::Xxx {
+                    k: x.len() as u32,
+                    x,
+                }
+            }
+            "fnt" => {
+                let font = self.parse_font(value)?;
+                OpCode::Fnt {
+                    k: self.font_number(font),
+                }
+            }
+            "right" => OpCode::Right {
+                b: self.parse_length(value)?,
+            },
+            "down" => OpCode::Down {
+                a: self.parse_length(value)?,
+            },
+            "w" => OpCode::W {
+                b: self.parse_length(value)?,
+            },
+            "x" => OpCode::X {
+                b: self.parse_length(value)?,
+            },
+            "y" => OpCode::Y {
+                a: self.parse_length(value)?,
+            },
+            "z" => OpCode::Z {
+                a: self.parse_length(value)?,
+            },
+            "push" => OpCode::Push,
+            "pop" => OpCode::Pop,
+            "w0" => OpCode::W0,
+            "x0" => OpCode::X0,
+            "y0" => OpCode::Y0,
+            "z0" => OpCode::Z0,
+            _ => {
+                return Err(DviousError::AsmParseError(format!(
+                    "Invalid command [{}]",
+                    key
+                )))
+            }
+        };
+        Ok(vec![opcode])
+    }
+
+    fn parse_rule(&self, value: &str) -> DviousResult<(i32, i32)> {
+        let lengths: Vec<&str> = value.split_whitespace().collect();
+        if lengths.len() != 2 {
+            return Err(DviousError::AsmParseError(format!(
+                "Two values are required for a rule, but found [{}]",
+                value
+            )));
+        }
+        Ok((
+            self.parse_length(lengths[0])?,
+            self.parse_length(lengths[1])?,
+        ))
+    }
+
+    /// Fonts are given as `name at size` or `name (design size) at size`
+    fn parse_font(&self, value: &str) -> DviousResult<Font> {
+        let (name, design_size, size) = match (value.find('('), value.find(')')) {
+            (Some(open), Some(close)) if open < close => (
+                &value[..open],
+                Some(value[open + 1..close].trim()),
+                &value[close + 1..],
+            ),
+            _ => match value.find(' ') {
+                Some(space) => (&value[..space], None, &value[space..]),
+                None => {
+                    return Err(DviousError::AsmParseError(format!(
+                        "Missing size in font [{}]",
+                        value
+                    )))
+                }
+            },
+        };
+
+        let name = name.trim();
+        if name.starts_with('"') {
+            return Err(DviousError::AsmParseError(format!(
+                "Native fonts like [{}] are not supported",
+                name
+            )));
+        }
+
+        let size = size.trim();
+        let size = size.strip_prefix("at").unwrap_or(size).trim();
+        let scaled_size = self.parse_length(size)?;
+        let design_size = match design_size {
+            Some(design_size) => self.parse_length(design_size)?,
+            None => scaled_size,
+        };
+
+        Ok(Font {
+            name: name.to_string(),
+            design_size,
+            scaled_size,
+        })
+    }
+
+    /// Returns the number of an identical font, defining it if there is none yet
+    fn font_number(&mut self, font: Font) -> i32 {
+        let existing = self.fonts.iter().position(|f| {
+            f.name == font.name
+                && f.design_size == font.design_size
+                && f.scaled_size == font.scaled_size
+        });
+        match existing {
+            Some(k) => k as i32,
+            None => {
+                self.fonts.push(font);
+                self.fonts.len() as i32 - 1
+            }
+        }
+    }
+
+    /// Lengths are either plain DVI units or numbers followed by a unit
+    fn parse_length(&self, value: &str) -> DviousResult<i32> {
+        if let Ok(dvi_units) = value.parse::<i32>() {
+            return Ok(dvi_units);
+        }
+
+        let split = value.len().saturating_sub(2);
+        let (number, unit) = match (value.get(..split), value.get(split..)) {
+            (Some(number), Some(suffix)) => match Unit::from_suffix(suffix) {
+                Some(unit) => (number, unit),
+                None => (value, Unit::Pt),
+            },
+            _ => (value, Unit::Pt),
+        };
+
+        match number.trim().parse::<f64>() {
+            Ok(number) => Ok((number / unit.per_dvi_unit(self.num, self.den)).round() as i32),
+            Err(_) => Err(DviousError::AsmParseError(format!(
+                "Invalid length [{}]",
+                value
+            ))),
+        }
+    }
+
+    fn parse_extent(&self, value: &str) -> DviousResult<u32> {
+        let length = self.parse_length(value)?;
+        u32::try_from(length)
+            .map_err(|_| DviousError::AsmParseError(format!("Negative extent [{}]", value)))
+    }
+
+    /// The preamble, font definitions and pages; the postamble is left to the writer
+    fn into_opcodes(self) -> Vec<OpCode> {
+        let mut opcodes = Vec::new();
+        opcodes.push(OpCode::Pre {
+            i: self.id,
+            num: self.num,
+            den: self.den,
+            mag: self.mag,
+            k: self.comment.len() as u8,
+            x: self.comment.clone(),
+        });
+
+        opcodes.extend(
+            self.fonts
+                .iter()
+                .enumerate()
+                .map(|(k, font)| OpCode::FntDef {
+                    k: k as i32,
+                    c: 0,
+                    s: font.scaled_size as u32,
+                    d: font.design_size as u32,
+                    a: 0,
+                    l: font.name.len() as u8,
+                    n: font.name.as_bytes().to_vec(),
+                }),
+        );
+
+        for page in self.pages {
+            let c = page.counts;
+            opcodes.push(OpCode::Bop {
+                c0: c[0],
+                c1: c[1],
+                c2: c[2],
+                c3: c[3],
+                c4: c[4],
+                c5: c[5],
+                c6: c[6],
+                c7: c[7],
+                c8: c[8],
+                c9: c[9],
+                p: -1,
+            });
+            opcodes.extend(page.content);
+            opcodes.push(OpCode::Eop);
+        }
+        opcodes
+    }
+}
+
+/// Renders the opcodes of a complete DVI file in the text format of dviasm, with
+/// dimensions in the given unit. Like dviasm, font sizes are always given in points,
+/// consecutive characters are coalesced into one `set:` and `nop`s are dropped.
+pub fn dump(opcodes: &[OpCode], unit: Unit) -> DviousResult<String> {
+    let mut dumper = DviasmDumper::new(unit);
+    for opcode in opcodes {
+        dumper.read(opcode)?;
+    }
+    Ok(dumper.into_text())
+}
+
+struct DviasmDumper {
+    unit: Unit,
+    id: u8,
+    num: u32,
+    den: u32,
+    mag: u32,
+    comment: Vec<u8>,
+    max_v: i32,
+    max_h: i32,
+    max_s: u16,
+    total_pages: u16,
+    fonts: BTreeMap<i32, Font>,
+    pages: Vec<String>,
+    indent: usize,
+    characters: Vec<i32>,
+}
+
+impl DviasmDumper {
+    fn new(unit: Unit) -> DviasmDumper {
+        DviasmDumper {
+            unit,
+            id: DEFAULT_ID,
+            num: TEX_NUMERATOR,
+            den: TEX_DENOMINATOR,
+            mag: TEX_MAGNIFICATION,
+            comment: Vec::new(),
+            max_v: 0,
+            max_h: 0,
+            max_s: 0,
+            total_pages: 0,
+            fonts: BTreeMap::new(),
+            pages: Vec::new(),
+            indent: 0,
+            characters: Vec::new(),
+        }
+    }
+
+    fn read(&mut self, opcode: &OpCode) -> DviousResult<()> {
+        if let OpCode::Set { c } = *opcode {
+            self.characters.push(c);
+            return Ok(());
+        }
+        self.flush_characters();
+
+        match *opcode {
+            OpCode::Pre {
+                i,
+                num,
+                den,
+                mag,
+                ref x,
+                ..
+            } => {
+                self.id = i;
+                self.num = num;
+                self.den = den;
+                self.mag = mag;
+                self.comment = x.clone();
+            }
+            OpCode::Post { l, u, s, t, .. } => {
+                self.max_v = l as i32;
+                self.max_h = u as i32;
+                self.max_s = s;
+                self.total_pages = t;
+            }
+            OpCode::FntDef { k, s, d, ref n, .. } => {
+                // The first definition of a font number wins, as in dviasm
+                self.fonts.entry(k).or_insert_with(|| Font {
+                    name: String::from_utf8_lossy(n).into_owned(),
+                    design_size: d as i32,
+                    scaled_size: s as i32,
+                });
+            }
+            OpCode::Bop {
+                c0,
+                c1,
+                c2,
+                c3,
+                c4,
+                c5,
+                c6,
+                c7,
+                c8,
+                c9,
+                ..
+            } => {
+                let counts = [c0, c1, c2, c3, c4, c5, c6, c7, c8, c9];
+                let counts: Vec<String> = counts.iter().map(|c| c.to_string()).collect();
+                self.pages.push(format!("\n[page {}]\n", counts.join(" ")));
+                self.indent = 0;
+            }
+            OpCode::Push => {
+                self.write_command("push:");
+                self.indent += 2;
+            }
+            OpCode::Pop => {
+                self.indent = self.indent.saturating_sub(2);
+                self.write_command("pop:");
+            }
+            OpCode::Put { c } => {
+                let command = format!("put: {}", format_characters(&[c]));
+                self.write_command(&command);
+            }
+            OpCode::SetRule { a, b } => {
+                let command = format!("setrule: {} {}", self.length(a), self.length(b));
+                self.write_command(&command);
+            }
+            OpCode::PutRule { a, b } => {
+                let command = format!("putrule: {} {}", self.length(a), self.length(b));
+                self.write_command(&command);
+            }
+            OpCode::Xxx { ref x, .. } => {
+                let command = format!("xxx: {}", python_repr(x));
+                self.write_command(&command);
+            }
+            OpCode::Fnt { k } => {
+                let font = match self.fonts.get(&k) {
+                    Some(font) => font,
+                    None => {
+                        return Err(DviousError::DviParseError(format!(
+                            "Font [{}] selected, but never defined",
+                            k
+                        )))
+                    }
+                };
+                let mut command = format!("fnt: {} ", font.name);
+                if font.design_size != font.scaled_size {
+                    command += &format!("({}) ", self.points(font.design_size));
+                }
+                command += &format!("at {}", self.points(font.scaled_size));
+                self.write_command(&command);
+            }
+            OpCode::Right { b } => self.write_length("right", b),
+            OpCode::Down { a } => self.write_length("down", a),
+            OpCode::W { b } => self.write_length("w", b),
+            OpCode::X { b } => self.write_length("x", b),
+            OpCode::Y { a } => self.write_length("y", a),
+            OpCode::Z { a } => self.write_length("z", a),
+            OpCode::W0 => self.write_command("w0:"),
+            OpCode::X0 => self.write_command("x0:"),
+            OpCode::Y0 => self.write_command("y0:"),
+            OpCode::Z0 => self.write_command("z0:"),
+            OpCode::Set { .. } | OpCode::Nop | OpCode::Eop | OpCode::PostPost { .. } => {}
+        }
+        Ok(())
+    }
+
+    fn flush_characters(&mut self) {
+        if self.characters.is_empty() {
+            return;
+        }
+        let command = format!("set: {}", format_characters(&self.characters));
+        self.characters.clear();
+        self.write_command(&command);
+    }
+
+    fn write_length(&mut self, name: &str, value: i32) {
+        let command = format!("{}: {}", name, self.length(value));
+        self.write_command(&command);
+    }
+
+    fn write_command(&mut self, command: &str) {
+        if let Some(page) = self.pages.last_mut() {
+            page.push_str(&" ".repeat(self.indent));
+            page.push_str(command);
+            page.push('\n');
+        }
+    }
+
+    fn length(&self, value: i32) -> String {
+        format_length(value, self.unit, self.num, self.den)
+    }
+
+    fn points(&self, value: i32) -> String {
+        format_length(value, Unit::Pt, self.num, self.den)
+    }
+
+    fn into_text(mut self) -> String {
+        self.flush_characters();
+
+        let mut text = String::new();
+        text += "[preamble]\n";
+        text += &format!("id: {}\n", self.id);
+        text += &format!("numerator: {}\n", self.num);
+        text += &format!("denominator: {}\n", self.den);
+        text += &format!("magnification: {}\n", self.mag);
+        text += &format!("comment: {}\n", python_repr(&self.comment));
+
+        text += "\n[postamble]\n";
+        text += &format!("maxv: {}\n", self.length(self.max_v));
+        text += &format!("maxh: {}\n", self.length(self.max_h));
+        text += &format!("maxs: {}\n", self.max_s);
+        text += &format!("pages: {}\n", self.total_pages);
+
+        text += "\n[font definitions]\n";
+        for font in self.fonts.values() {
+            text += &format!("fntdef: {}", font.name);
+            if font.design_size != font.scaled_size {
+                text += &format!(" ({}) ", self.points(font.design_size));
+            }
+            text += &format!(" at {}\n", self.points(font.scaled_size));
+        }
+
+        for page in &self.pages {
+            text += page;
+        }
+        text
+    }
+}
+
+/// Formats a length like dviasm: scaled points are truncated, other units are given
+/// as integers if they are exact and with six decimals otherwise
+fn format_length(value: i32, unit: Unit, num: u32, den: u32) -> String {
+    let converted = unit.per_dvi_unit(num, den) * f64::from(value);
+    if unit == Unit::Sp || converted == converted.trunc() {
+        format!("{}{}", converted.trunc() as i64, unit.suffix())
+    } else {
+        format!("{:.6}{}", converted, unit.suffix())
+    }
+}
+
+/// Quotes character codes like dviasm does for UTF-8 output
+fn format_characters(characters: &[i32]) -> String {
+    let mut result = String::from("'");
+    for &c in characters {
+        match c {
+            92 => result.push_str("\\\\"),
+            32..=126 => result.push(c as u8 as char),
+            0..=127 => result.push_str(&format!("\\x{:02x}", c)),
+            _ => result.push(std::char::from_u32(c as u32).unwrap_or(REPLACEMENT_CHARACTER)),
+        }
+    }
+    result.push('\'');
+    result
+}
+
+/// Quotes bytes like `repr` of a Python 2 string
+fn python_repr(bytes: &[u8]) -> String {
+    let quote = if bytes.contains(&b'\'') && !bytes.contains(&b'"') {
+        '"'
+    } else {
+        '\''
+    };
+
+    let mut result = String::new();
+    result.push(quote);
+    for &byte in bytes {
+        match byte {
+            b'\\' => result.push_str("\\\\"),
+            b'\t' => result.push_str("\\t"),
+            b'\n' => result.push_str("\\n"),
+            b'\r' => result.push_str("\\r"),
+            _ if byte as char == quote => {
+                result.push('\\');
+                result.push(quote);
+            }
+            32..=126 => result.push(byte as char),
+            _ => result.push_str(&format!("\\x{:02x}", byte)),
+        }
+    }
+    result.push(quote);
+    result
+}
+
+fn parse_int(value: &str) -> DviousResult<i64> {
+    value
+        .parse::<i64>()
+        .map_err(|_| DviousError::AsmParseError(format!("Invalid number [{}]", value)))
+}
+
+fn parse_positive(value: &str, name: &str) -> DviousResult<u32> {
+    match parse_int(value)? {
+        n if n > 0 && n <= i64::from(i32::MAX) => Ok(n as u32),
+        n => Err(DviousError::AsmParseError(format!(
+            "The {} has to be positive, but is [{}]",
+            name, n
+        ))),
+    }
+}
+
+/// Parses a quoted string with Python escapes into character codes
+fn parse_string(value: &str) -> DviousResult<Vec<i32>> {
+    let quote = match value.chars().next() {
+        Some(quote @ '\'') | Some(quote @ '"') if value.len() > 1 && value.ends_with(quote) => {
+            quote
+        }
+        _ => {
+            return Err(DviousError::AsmParseError(format!(
+                "Expected a quoted string, but found [{}]",
+                value
+            )))
+        }
+    };
+    let inner = &value[quote.len_utf8()..value.len() - quote.len_utf8()];
+
+    let mut result = Vec::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c as i32);
+            continue;
+        }
+
+        let escaped = match chars.next() {
+            Some('n') => '\n' as i32,
+            Some('t') => '\t' as i32,
+            Some('r') => '\r' as i32,
+            Some('x') => parse_hex(&mut chars, 2)?,
+            Some('u') => parse_hex(&mut chars, 4)?,
+            Some(other) => other as i32,
+            None => '\\' as i32,
+        };
+        result.push(escaped);
+    }
+    Ok(result)
+}
+
+/// Parses the argument of `xxx`, where escaped and plain characters below 256 are
+/// taken as bytes and everything else is encoded as UTF-8
+fn parse_bytes(value: &str) -> DviousResult<Vec<u8>> {
+    let mut result = Vec::new();
+    for c in parse_string(value)? {
+        if (0..256).contains(&c) {
+            result.push(c as u8);
+        } else if let Some(c) = std::char::from_u32(c as u32) {
+            let mut buffer = [0; 4];
+            result.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+        }
+    }
+    Ok(result)
+}
+
+fn parse_hex(chars: &mut std::str::Chars, digits: usize) -> DviousResult<i32> {
+    let hex: String = chars.take(digits).collect();
+    i32::from_str_radix(&hex, 16)
+        .map_err(|_| DviousError::AsmParseError(format!("Invalid escape sequence [{}]", hex)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dvi::asm::*;
+
+    #[test]
+    fn test_parse_lengths() {
+        let parser = DviasmParser::new();
+
+        assert_eq!(parser.parse_length("1234").unwrap(), 1234);
+        assert_eq!(parser.parse_length("1pt").unwrap(), 65536);
+        assert_eq!(parser.parse_length("-3.5pt").unwrap(), -229_376);
+        assert_eq!(parser.parse_length("1in").unwrap(), 4_736_287);
+        assert_eq!(parser.parse_length("72bp").unwrap(), 4_736_287);
+        assert_eq!(parser.parse_length("2.54cm").unwrap(), 4_736_287);
+        assert_eq!(parser.parse_length("10sp").unwrap(), 10);
+        assert!(parser.parse_length("many").is_err(), "Expected Err, was Ok");
+    }
+
+    #[test]
+    fn test_parse_string() {
+        let result = parse_string(r"'a\'b\\\x80Ā'").unwrap();
+
+        assert_eq!(result, vec![0x61, 0x27, 0x62, 0x5C, 0x80, 0x100]);
+    }
+
+    #[test]
+    fn test_parse_font() {
+        let parser = DviasmParser::new();
+
+        let font = parser.parse_font("cmr10 (10pt) at 12pt").unwrap();
+
+        assert_eq!(font.name, "cmr10");
+        assert_eq!(font.design_size, 655_360);
+        assert_eq!(font.scaled_size, 786_432);
+    }
+
+    #[test]
+    fn test_parse_document() {
+        let text = "
+            [preamble]
+            id: 2
+            numerator: 25400000
+            denominator: 473628672
+            magnification: 1000
+            comment: ' TeX output'
+
+            [postamble]
+            maxv: 10pt
+            maxh: 20pt
+            maxs: 2
+            pages: 1
+
+            [font definitions]
+            fntdef: cmr10 at 10pt
+
+            [page 1 0 0 0 0 0 0 0 0 0]
+            push:
+              fnt: cmr10 at 10pt
+              set: 'Hi'
+              right: 3.5pt
+            pop:
+            xxx: 'color push'
+        ";
+
+        let opcodes = parse(text).unwrap();
+
+        assert_eq!(opcodes.len(), 14);
+        assert_eq!(opcodes[4], OpCode::Fnt { k: 0 });
+        assert_eq!(opcodes[5], OpCode::Set { c: 0x48 });
+        assert_eq!(opcodes[7], OpCode::Right { b: 229_376 });
+        match opcodes[11] {
+            OpCode::Post { l, u, s, t, .. } => {
+                assert_eq!((l, u, s, t), (655_360, 1_310_720, 1, 1));
+            }
+            ref other => panic!("Expected post, was {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_command_outside_page() {
+        match parse("set: 'a'") {
+            Err(DviousError::AsmParseError(message)) => {
+                assert_eq!(message, "Line 1: Command [set] outside of a page")
+            }
+            other => panic!("Expected AsmParseError, was {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_invalid_header_values() {
+        for text in &["id: 256", "[page 1 x 0 0 0 0 0 0 0 0]", "maxv: -1pt"] {
+            match parse(text) {
+                Err(DviousError::AsmParseError(_)) => {}
+                other => panic!("Expected AsmParseError for [{}], was {:?}", text, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_escaped_comment() {
+        let opcodes = parse("comment: \"it's \\x80\"").unwrap();
+
+        match opcodes[0] {
+            OpCode::Pre { ref x, .. } => assert_eq!(x, b"it's \x80"),
+            ref other => panic!("Expected pre, was {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_format_length() {
+        let (num, den) = (TEX_NUMERATOR, TEX_DENOMINATOR);
+
+        assert_eq!(format_length(655_360, Unit::Pt, num, den), "10pt");
+        assert_eq!(format_length(218_453, Unit::Pt, num, den), "3.333328pt");
+        assert_eq!(format_length(-18_205, Unit::Pt, num, den), "-0.277786pt");
+        assert_eq!(format_length(-18_205, Unit::Sp, num, den), "-18205sp");
+        assert_eq!(format_length(65_536, Unit::Bp, num, den), "0.996264bp");
+    }
+
+    #[test]
+    fn test_python_repr() {
+        assert_eq!(python_repr(b"color push"), "'color push'");
+        assert_eq!(python_repr(b"it's"), "\"it's\"");
+        assert_eq!(python_repr(b"'\"\\\n\x80"), "'\\'\"\\\\\\n\\x80'");
+    }
+
+    #[test]
+    fn test_dump_coalesces_characters() {
+        let opcodes = vec![
+            bop(),
+            OpCode::Set { c: 0x41 },
+            OpCode::Nop,
+            OpCode::Set { c: 0x5C },
+            OpCode::Set { c: 0x0B },
+            OpCode::Set { c: 0xE9 },
+            OpCode::Push,
+            OpCode::Put { c: 0x42 },
+            OpCode::SetRule {
+                a: 65536,
+                b: 131_072,
+            },
+            OpCode::Pop,
+            OpCode::W0,
+            OpCode::Eop,
+        ];
+
+        let text = dump(&opcodes, Unit::Pt).unwrap();
+
+        assert!(text.ends_with(
+            "\n[page 1 0 0 0 0 0 0 0 0 0]\n\
+             set: 'A'\n\
+             set: '\\\\\\x0bé'\n\
+             push:\n\
+             \x20 put: 'B'\n\
+             \x20 setrule: 1pt 2pt\n\
+             pop:\n\
+             w0:\n"
+        ));
+    }
+
+    #[test]
+    fn test_dump_writes_every_font_selection() {
+        let font_definition = |k, s| OpCode::FntDef {
+            k,
+            c: 0,
+            s,
+            d: 655_360,
+            a: 0,
+            l: 5,
+            n: b"cmr10".to_vec(),
+        };
+        let opcodes = vec![
+            font_definition(0, 655_360),
+            font_definition(1, 786_432),
+            font_definition(2, 655_360),
+            bop(),
+            OpCode::Fnt { k: 0 },
+            OpCode::Fnt { k: 2 },
+            OpCode::Fnt { k: 1 },
+            OpCode::Eop,
+            bop(),
+            OpCode::Fnt { k: 1 },
+            OpCode::Fnt { k: 0 },
+            OpCode::Eop,
+        ];
+
+        let text = dump(&opcodes, Unit::Pt).unwrap();
+
+        // Like dviasm, even fonts of the same name and size are selected again
+        assert!(text.ends_with(
+            "\n[page 1 0 0 0 0 0 0 0 0 0]\n\
+             fnt: cmr10 at 10pt\n\
+             fnt: cmr10 at 10pt\n\
+             fnt: cmr10 (10pt) at 12pt\n\
+             \n[page 1 0 0 0 0 0 0 0 0 0]\n\
+             fnt: cmr10 (10pt) at 12pt\n\
+             fnt: cmr10 at 10pt\n"
+        ));
+    }
+
+    #[test]
+    fn test_dump_undefined_font() {
+        let result = dump(&[bop(), OpCode::Fnt { k: 7 }], Unit::Pt);
+
+        assert!(matches!(result, Err(DviousError::DviParseError(_))));
+    }
+
+    fn bop() -> OpCode {
+        OpCode::Bop {
+            c0: 1,
+            c1: 0,
+            c2: 0,
+            c3: 0,
+            c4: 0,
+            c5: 0,
+            c6: 0,
+            c7: 0,
+            c8: 0,
+            c9: 0,
+            p: -1,
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use crate::dvi::page::{Glyph, Page, Rule};
+use crate::util::dimension::Dimension;
+
+/// A rectangle in DVI units, with `v` growing downwards like on a page
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BoundingBox {
+    pub left: Dimension,
+    pub top: Dimension,
+    pub right: Dimension,
+    pub bottom: Dimension,
+}
+
+impl BoundingBox {
+    /// The box spanned by two corners in any order
+    pub fn from_corners(h0: Dimension, v0: Dimension, h1: Dimension, v1: Dimension) -> BoundingBox {
+        BoundingBox {
+            left: h0.min(h1),
+            top: v0.min(v1),
+            right: h0.max(h1),
+            bottom: v0.max(v1),
+        }
+    }
+
+    /// The box of a glyph from the width, height and depth in its TFM file
+    pub fn of_glyph(glyph: &Glyph) -> BoundingBox {
+        BoundingBox::from_corners(
+            glyph.h,
+            glyph.v - glyph.height,
+            glyph.h + glyph.width,
+            glyph.v + glyph.depth,
+        )
+    }
+
+    pub fn of_rule(rule: &Rule) -> BoundingBox {
+        BoundingBox::from_corners(rule.h, rule.v - rule.height, rule.h + rule.width, rule.v)
+    }
+
+    pub fn width(&self) -> Dimension {
+        self.right - self.left
+    }
+
+    pub fn height(&self) -> Dimension {
+        self.bottom - self.top
+    }
+
+    /// The smallest box containing both boxes
+    pub fn union(self, other: BoundingBox) -> BoundingBox {
+        BoundingBox {
+            left: self.left.min(other.left),
+            top: self.top.min(other.top),
+            right: self.right.max(other.right),
+            bottom: self.bottom.max(other.bottom),
+        }
+    }
+}
+
+/// The smallest box containing the glyphs and rules of a page, or `None` if there
+/// are neither. Specials are not taken into account.
+pub fn bounding_box(page: &Page) -> Option<BoundingBox> {
+    let glyphs = page.glyphs.iter().map(BoundingBox::of_glyph);
+    let rules = page.rules.iter().map(BoundingBox::of_rule);
+    glyphs.chain(rules).fold(None, |result, bbox| match result {
+        Some(result) => Some(bbox.union(result)),
+        None => Some(bbox),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dvi::bbox::*;
+    use crate::dvi::page::*;
+
+    #[test]
+    fn test_bounding_box_of_glyphs_and_rules() {
+        let page = Page {
+            glyphs: vec![glyph(10, 100, 5, 7, 2), glyph(15, 100, 5, 9, 0)],
+            rules: vec![Rule {
+                h: sp(0),
+                v: sp(120),
+                width: sp(40),
+                height: sp(1),
+                pixels: None,
+            }],
+            ..Default::default()
+        };
+
+        let bbox = bounding_box(&page).unwrap();
+
+        assert_eq!(
+            bbox,
+            BoundingBox {
+                left: sp(0),
+                top: sp(91),
+                right: sp(40),
+                bottom: sp(120),
+            }
+        );
+        assert_eq!((bbox.width(), bbox.height()), (sp(40), sp(29)));
+    }
+
+    #[test]
+    fn test_bounding_box_with_negative_width() {
+        let page = Page {
+            glyphs: vec![glyph(10, 100, -5, 7, 2)],
+            ..Default::default()
+        };
+
+        let bbox = bounding_box(&page).unwrap();
+
+        assert_eq!((bbox.left, bbox.right), (sp(5), sp(10)));
+    }
+
+    #[test]
+    fn test_bounding_box_of_empty_page() {
+        assert_eq!(bounding_box(&Page::default()), None);
+    }
+
+    fn sp(raw: i64) -> Dimension {
+        Dimension::new(raw)
+    }
+
+    fn glyph(h: i64, v: i64, width: i64, height: i64, depth: i64) -> Glyph {
+        Glyph {
+            font: 1,
+            c: 0x42,
+            h: sp(h),
+            v: sp(v),
+            width: sp(width),
+            height: sp(height),
+            depth: sp(depth),
+            pixels: None,
+        }
+    }
+}
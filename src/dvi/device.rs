@@ -0,0 +1,59 @@
+use crate::dvi::page::{Glyph, Page, Rule, Special};
+use crate::util::dimension::Dimension;
+
+/// Receives the contents of the pages while the interpreter executes a DVI file.
+/// Positions and dimensions are in DVI units. All callbacks do nothing by default,
+/// so that devices only implement the ones they need.
+pub trait DviDevice {
+    /// Called for every `bop` with the counters `\count0` to `\count9`
+    fn begin_page(&mut self, _counts: &[i32; 10]) {}
+
+    /// Called for every character set or put on a page
+    fn glyph(&mut self, _glyph: &Glyph) {}
+
+    /// Called for every visible rule
+    fn rule(&mut self, _rule: &Rule) {}
+
+    /// Called for every `xxx` on a page
+    fn special(&mut self, _x: &[u8], _h: Dimension, _v: Dimension) {}
+
+    /// Called for every `eop`
+    fn end_page(&mut self) {}
+}
+
+/// The device behind `Interpreter::execute`, which collects the pages
+#[derive(Default)]
+pub(crate) struct PageCollector {
+    page: Page,
+    pub(crate) pages: Vec<Page>,
+}
+
+impl DviDevice for PageCollector {
+    fn begin_page(&mut self, counts: &[i32; 10]) {
+        self.page = Page {
+            counts: *counts,
+            ..Default::default()
+        };
+    }
+
+    fn glyph(&mut self, glyph: &Glyph) {
+        self.page.glyphs.push(glyph.clone());
+    }
+
+    fn rule(&mut self, rule: &Rule) {
+        self.page.rules.push(rule.clone());
+    }
+
+    fn special(&mut self, x: &[u8], h: Dimension, v: Dimension) {
+        self.page.specials.push(Special {
+            h,
+            v,
+            x: x.to_vec(),
+        });
+    }
+
+    fn end_page(&mut self) {
+        let page = std::mem::take(&mut self.page);
+        self.pages.push(page);
+    }
+}
@@ -1,43 +1,118 @@
-use crate::dvi::opcodes::OpCode;
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
+use crate::dvi::opcodes::{OpCode, SpannedOpCode};
 use crate::errors::{DviousError, DviousResult};
 use crate::util::byte_reader::ByteReader;
 use crate::util::num::{i24, u24};
 
 pub fn disassemble(bytes: Vec<u8>) -> DviousResult<Vec<OpCode>> {
-    let mut disassembler = Disassembler::new(bytes);
-    disassembler.disassemble()
+    OpCodeIter::new(Cursor::new(bytes)).collect()
+}
+
+pub fn disassemble_spanned(bytes: Vec<u8>) -> DviousResult<Vec<SpannedOpCode>> {
+    SpannedOpCodeIter::new(Cursor::new(bytes)).collect()
+}
+
+/// Lazily disassembles DVI instructions from any byte source, one opcode at a time.
+/// Iteration stops after the first error.
+pub struct OpCodeIter<R: Read> {
+    inner: SpannedOpCodeIter<R>,
+}
+
+impl<R: Read> OpCodeIter<R> {
+    pub fn new(source: R) -> OpCodeIter<R> {
+        OpCodeIter {
+            inner: SpannedOpCodeIter::new(source),
+        }
+    }
 }
 
-struct Disassembler {
-    reader: ByteReader,
-    last_bop: Option<usize>,
-    last_post: Option<usize>,
-    number_of_instructions: usize,
+impl<R: Read> Iterator for OpCodeIter<R> {
+    type Item = DviousResult<OpCode>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|result| result.map(|spanned| spanned.opcode))
+    }
 }
 
-impl Disassembler {
-    fn new(bytes: Vec<u8>) -> Disassembler {
-        Disassembler {
-            reader: ByteReader::new(bytes),
-            last_bop: Option::None,
-            last_post: Option::None,
-            number_of_instructions: 0,
+/// Like `OpCodeIter`, but yields every opcode together with its location in the source.
+pub struct SpannedOpCodeIter<R: Read> {
+    disassembler: Disassembler<R>,
+    failed: bool,
+}
+
+impl<R: Read> SpannedOpCodeIter<R> {
+    pub fn new(source: R) -> SpannedOpCodeIter<R> {
+        SpannedOpCodeIter {
+            disassembler: Disassembler::new(ByteReader::from_reader(source)),
+            failed: false,
+        }
+    }
+}
+
+impl<R: Read> Iterator for SpannedOpCodeIter<R> {
+    type Item = DviousResult<SpannedOpCode>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return Option::None;
         }
+
+        let result = match self.disassembler.has_more() {
+            Ok(false) => return Option::None,
+            Ok(true) => self.disassembler.disassemble_next_spanned(),
+            Err(why) => Err(why),
+        };
+
+        self.failed = result.is_err();
+        Option::Some(result)
+    }
+}
+
+pub(crate) struct Disassembler<R: Read> {
+    reader: ByteReader<R>,
+}
+
+impl<R: Read + Seek> Disassembler<R> {
+    pub(crate) fn seek(&mut self, target: SeekFrom) -> DviousResult<usize> {
+        self.reader.seek(target)
+    }
+
+    pub(crate) fn read_u8_at(&mut self, offset: usize) -> DviousResult<u8> {
+        self.seek(SeekFrom::Start(offset as u64))?;
+        self.reader.read_be::<u8>()
     }
+}
 
-    fn disassemble(&mut self) -> DviousResult<Vec<OpCode>> {
-        let mut opcodes = Vec::new();
-        self.last_bop = Option::None;
+impl<R: Read> Disassembler<R> {
+    pub(crate) fn new(reader: ByteReader<R>) -> Disassembler<R> {
+        Disassembler { reader }
+    }
 
-        while self.reader.has_more() {
-            let opcode = self.disassemble_next()?;
-            opcodes.push(opcode);
+    fn has_more(&mut self) -> DviousResult<bool> {
+        match self.reader.peek_be::<u8>() {
+            Ok(_) => Ok(true),
+            Err(DviousError::IndexOutOfBoundsError) => Ok(false),
+            Err(why) => Err(why),
         }
+    }
+
+    pub(crate) fn disassemble_next_spanned(&mut self) -> DviousResult<SpannedOpCode> {
+        let offset = self.reader.position();
+        let byte = self.reader.peek_be::<u8>()?;
+        let opcode = self.disassemble_next()?;
 
-        Ok(opcodes)
+        Ok(SpannedOpCode {
+            offset,
+            length: self.reader.position() - offset,
+            byte,
+            opcode,
+        })
     }
 
-    fn disassemble_next(&mut self) -> DviousResult<OpCode> {
+    pub(crate) fn disassemble_next(&mut self) -> DviousResult<OpCode> {
         let byte = self.reader.read_be::<u8>()?;
         let opcode = match byte {
             0..=127 => self.handle_set_char(i32::from(byte))?,
@@ -100,10 +175,12 @@ impl Disassembler {
             247 => self.handle_pre()?,
             248 => self.handle_post()?,
             249 => self.handle_post_post()?,
-            _ => return Err(DviousError::UnknownOpcodeError(byte)),
+            _ => {
+                let offset = self.reader.position() - 1;
+                return Err(DviousError::UnknownOpcodeError(byte, offset));
+            }
         };
 
-        self.number_of_instructions += 1;
         Ok(opcode)
     }
 
@@ -178,8 +255,6 @@ impl Disassembler {
     }
 
     fn handle_bop(&mut self) -> DviousResult<OpCode> {
-        self.last_bop = Some(self.number_of_instructions);
-
         Ok(OpCode::Bop {
             c0: self.reader.read_be::<i32>()?,
             c1: self.reader.read_be::<i32>()?,
@@ -386,7 +461,7 @@ impl Disassembler {
     }
 
     fn handle_fnt_num(&mut self, byte: u8) -> DviousResult<OpCode> {
-        self.handle_fnt(byte)
+        self.handle_fnt(byte - 171)
     }
 
     fn handle_fnt1(&mut self) -> DviousResult<OpCode> {
@@ -414,7 +489,7 @@ impl Disassembler {
     fn handle_xxx<T: Into<u32>>(&mut self, n: T) -> DviousResult<OpCode> {
         let k = n.into();
         let x = self.reader.read_vector_be::<u8>(k as usize)?;
-        Ok(OpCode::Xxx { k: k, x: x })
+        Ok(OpCode::Xxx { k, x })
     }
 
     fn handle_xxx1(&mut self) -> DviousResult<OpCode> {
@@ -449,12 +524,12 @@ impl Disassembler {
 
         Ok(OpCode::FntDef {
             k: k.into(),
-            c: c,
-            s: s,
-            d: d,
-            a: a,
-            l: l,
-            n: n,
+            c,
+            s,
+            d,
+            a,
+            l,
+            n,
         })
     }
 
@@ -488,21 +563,18 @@ impl Disassembler {
         let k = self.reader.read_be::<u8>()?;
 
         Ok(OpCode::Pre {
-            i: i,
-            num: num,
-            den: den,
-            mag: mag,
-            k: k,
+            i,
+            num,
+            den,
+            mag,
+            k,
             x: self.reader.read_vector_be::<u8>(k as usize)?,
         })
     }
 
     fn handle_post(&mut self) -> DviousResult<OpCode> {
-        self.reader.read_be::<i32>()?;
-        self.last_post = Option::Some(self.number_of_instructions);
-
         Ok(OpCode::Post {
-            p: self.last_bop,
+            p: self.read_pointer()?,
             num: self.reader.read_be::<u32>()?,
             den: self.reader.read_be::<u32>()?,
             mag: self.reader.read_be::<u32>()?,
@@ -514,11 +586,8 @@ impl Disassembler {
     }
 
     fn handle_post_post(&mut self) -> DviousResult<OpCode> {
-        // Consume unused pointer to post command
-        self.reader.read_be::<i32>()?;
-
         let result = OpCode::PostPost {
-            q: self.last_post,
+            q: self.read_pointer()?,
             i: self.reader.read_be::<u8>()?,
         };
 
@@ -529,13 +598,23 @@ impl Disassembler {
 
         Ok(result)
     }
+
+    /// Pointers are byte offsets into the file, where -1 means that there is no target
+    fn read_pointer(&mut self) -> DviousResult<Option<usize>> {
+        let pointer = self.reader.read_be::<i32>()?;
+        if pointer < 0 {
+            Ok(Option::None)
+        } else {
+            Ok(Option::Some(pointer as usize))
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::dvi::disassembler::disassemble;
-    use crate::dvi::opcodes::OpCode;
-    use crate::errors::DviousResult;
+    use crate::dvi::disassembler::{disassemble, disassemble_spanned, OpCodeIter};
+    use crate::dvi::opcodes::{OpCode, SpannedOpCode};
+    use crate::errors::{DviousError, DviousResult};
 
     #[test]
     fn test_disassemble_set_char() {
@@ -636,7 +715,7 @@ mod tests {
     }
 
     #[test]
-    #[cfg_attr(rustfmt, rustfmt_skip)]
+    #[rustfmt::skip]
     fn test_disassemble_bop() {
         let result = disassemble(vec![
             139,
@@ -902,10 +981,11 @@ mod tests {
 
     #[test]
     fn test_disassemble_fnt_num() {
-        for i in 172..234 + 1 {
+        for i in 171..234 + 1 {
             let result = disassemble(vec![i]);
+            let k = i32::from(i - 171);
 
-            assert_that_opcode_was_generated(result, OpCode::Fnt { k: i32::from(i) })
+            assert_that_opcode_was_generated(result, OpCode::Fnt { k })
         }
     }
 
@@ -994,7 +1074,7 @@ mod tests {
     // fnt_def
 
     #[test]
-    #[cfg_attr(rustfmt, rustfmt_skip)]
+    #[rustfmt::skip]
     fn test_disassemble_fnt_def1() {
         let result = disassemble(vec![
             243,
@@ -1022,7 +1102,7 @@ mod tests {
     }
 
     #[test]
-    #[cfg_attr(rustfmt, rustfmt_skip)]
+    #[rustfmt::skip]
     fn test_disassemble_fnt_def2() {
         let result = disassemble(vec![
             244,
@@ -1050,7 +1130,7 @@ mod tests {
     }
 
     #[test]
-    #[cfg_attr(rustfmt, rustfmt_skip)]
+    #[rustfmt::skip]
     fn test_disassemble_fnt_def3() {
         let result = disassemble(vec![
             245,
@@ -1078,7 +1158,7 @@ mod tests {
     }
 
     #[test]
-    #[cfg_attr(rustfmt, rustfmt_skip)]
+    #[rustfmt::skip]
     fn test_disassemble_fnt_def4() {
         let result = disassemble(vec![
             246,
@@ -1108,7 +1188,7 @@ mod tests {
     // Pre and post
 
     #[test]
-    #[cfg_attr(rustfmt, rustfmt_skip)]
+    #[rustfmt::skip]
     fn test_disassemble_pre() {
         let result = disassemble(vec![
             247,
@@ -1134,7 +1214,7 @@ mod tests {
     }
 
     #[test]
-    #[cfg_attr(rustfmt, rustfmt_skip)]
+    #[rustfmt::skip]
     fn test_disassemble_post() {
         let result = disassemble(vec![
             248,
@@ -1151,7 +1231,7 @@ mod tests {
         assert_that_opcode_was_generated(
             result,
             OpCode::Post {
-                p: Option::None,
+                p: Option::Some(0x42),
                 num: 0xDEADBEEF,
                 den: 0xCAFEBABE,
                 mag: 0xBAAAAAAD,
@@ -1164,7 +1244,7 @@ mod tests {
     }
 
     #[test]
-    #[cfg_attr(rustfmt, rustfmt_skip)]
+    #[rustfmt::skip]
     fn test_disassemble_post_post() {
         let result = disassemble(vec![
             249,
@@ -1182,6 +1262,94 @@ mod tests {
         )
     }
 
+    #[test]
+    #[rustfmt::skip]
+    fn test_disassemble_post_post_pointer() {
+        let result = disassemble(vec![
+            249,
+            0x00, 0x00, 0x01, 0x2A,
+            0x02,
+            0xDF, 0xDF, 0xDF, 0xDF
+        ]);
+
+        assert_that_opcode_was_generated(
+            result,
+            OpCode::PostPost {
+                q: Option::Some(0x12A),
+                i: 0x02,
+            }
+        )
+    }
+
+    // Iterator
+
+    #[test]
+    fn test_opcode_iter_yields_opcodes_lazily() {
+        let source: &[u8] = &[0x42, 141, 142];
+        let mut iter = OpCodeIter::new(source);
+
+        assert_eq!(iter.next().unwrap().unwrap(), OpCode::Set { c: 0x42 });
+        assert_eq!(iter.next().unwrap().unwrap(), OpCode::Push);
+        assert_eq!(iter.next().unwrap().unwrap(), OpCode::Pop);
+        assert!(iter.next().is_none(), "Expected that iterator is exhausted");
+    }
+
+    #[test]
+    fn test_opcode_iter_stops_after_error() {
+        let source: &[u8] = &[0x42, 250, 141];
+        let mut iter = OpCodeIter::new(source);
+
+        assert_eq!(iter.next().unwrap().unwrap(), OpCode::Set { c: 0x42 });
+        match iter.next() {
+            Some(Err(DviousError::UnknownOpcodeError(250, 1))) => {}
+            other => panic!("Expected UnknownOpcodeError, was {:?}", other),
+        }
+        assert!(iter.next().is_none(), "Expected that iterator is exhausted");
+    }
+
+    // Spans
+
+    #[test]
+    fn test_disassemble_spanned() {
+        let result =
+            disassemble_spanned(vec![0x42, 143, 0xD6, 249, 0, 0, 0, 1, 2, 223, 223]).unwrap();
+
+        assert_eq!(
+            result,
+            vec![
+                SpannedOpCode {
+                    offset: 0,
+                    length: 1,
+                    byte: 0x42,
+                    opcode: OpCode::Set { c: 0x42 },
+                },
+                SpannedOpCode {
+                    offset: 1,
+                    length: 2,
+                    byte: 143,
+                    opcode: OpCode::Right { b: -42 },
+                },
+                SpannedOpCode {
+                    offset: 3,
+                    length: 8,
+                    byte: 249,
+                    opcode: OpCode::PostPost {
+                        q: Option::Some(1),
+                        i: 2,
+                    },
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_disassemble_unknown_opcode_reports_offset() {
+        match disassemble(vec![141, 141, 255]) {
+            Err(DviousError::UnknownOpcodeError(255, 2)) => {}
+            other => panic!("Expected UnknownOpcodeError, was {:?}", other),
+        }
+    }
+
     // Helper
 
     fn assert_that_opcode_was_generated(result: DviousResult<Vec<OpCode>>, opcode: OpCode) {
@@ -0,0 +1,1517 @@
+use std::io::{Cursor, SeekFrom, Write};
+
+use crate::dvi::pixels::PixelRounding;
+use crate::dvi::units::DviUnits;
+use crate::errors::{DviousError, DviousResult};
+use crate::fonts::resolver::FontResolver;
+use crate::fonts::tfm::TexFontMetric;
+use crate::fonts::verification::{FontVerifier, FontWarning};
+use crate::util::byte_reader::ByteReader;
+use crate::util::dimension::Dimension;
+
+const BANNER: &str = "This is DVItype, Version 3.6";
+const ID_BYTE: u8 = 2;
+const STACK_SIZE: i32 = 100;
+const INFINITY: i64 = 0o17777777777;
+const INVALID_WIDTH: i64 = 0o17777777777;
+
+const SET1: u8 = 128;
+const SET_RULE: u8 = 132;
+const PUT1: u8 = 133;
+const PUT_RULE: u8 = 137;
+const NOP: u8 = 138;
+const BOP: u8 = 139;
+const EOP: u8 = 140;
+const PUSH: u8 = 141;
+const POP: u8 = 142;
+const RIGHT1: u8 = 143;
+const W0: u8 = 147;
+const X0: u8 = 152;
+const DOWN1: u8 = 157;
+const Y0: u8 = 161;
+const Z0: u8 = 166;
+const FNT_NUM_0: u8 = 171;
+const FNT1: u8 = 235;
+const XXX1: u8 = 239;
+const FNT_DEF1: u8 = 243;
+const PRE: u8 = 247;
+const POST: u8 = 248;
+const POST_POST: u8 = 249;
+
+/// The amount of output, from the least to the most detailed
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub enum OutMode {
+    ErrorsOnly = 0,
+    Terse = 1,
+    MnemonicsOnly = 2,
+    Verbose = 3,
+    TheWorks = 4,
+}
+
+impl OutMode {
+    pub fn from_level(level: u8) -> Option<OutMode> {
+        match level {
+            0 => Some(OutMode::ErrorsOnly),
+            1 => Some(OutMode::Terse),
+            2 => Some(OutMode::MnemonicsOnly),
+            3 => Some(OutMode::Verbose),
+            4 => Some(OutMode::TheWorks),
+            _ => None,
+        }
+    }
+}
+
+/// The options that dvitype asks for in its dialog
+#[derive(Clone, Debug, PartialEq)]
+pub struct DvitypeOptions {
+    pub out_mode: OutMode,
+    /// The counts of the first page to translate, `None` matches any value
+    pub start_page: Vec<Option<i32>>,
+    pub max_pages: i32,
+    /// Pixels per inch of the assumed output device
+    pub resolution: f64,
+    /// Overrides the magnification of the DVI file if positive
+    pub new_mag: i32,
+}
+
+impl Default for DvitypeOptions {
+    fn default() -> DvitypeOptions {
+        DvitypeOptions {
+            out_mode: OutMode::TheWorks,
+            start_page: vec![None],
+            max_pages: 1_000_000,
+            resolution: 300.0,
+            new_mag: 0,
+        }
+    }
+}
+
+impl DvitypeOptions {
+    /// Parses a starting page like `1.*.-5`, which matches pages with `\count0 = 1`
+    /// and `\count2 = -5`
+    pub fn parse_start_page(spec: &str) -> Option<Vec<Option<i32>>> {
+        let counts = spec.split('.').collect::<Vec<&str>>();
+        if counts.len() > 10 {
+            return None;
+        }
+
+        counts
+            .into_iter()
+            .map(|count| match count {
+                "*" => Some(None),
+                _ => count.parse::<i32>().ok().map(Some),
+            })
+            .collect()
+    }
+}
+
+/// Writes the listing that dvitype produces for the given DVI file, with the font
+/// metrics obtained from `resolver`. If the DVI file is so broken that dvitype gives
+/// up, the listing up to that point is written and the reason is returned as error.
+pub fn dvitype<W, R>(
+    bytes: Vec<u8>,
+    options: &DvitypeOptions,
+    resolver: &mut R,
+    out: &mut W,
+) -> DviousResult<()>
+where
+    W: Write,
+    R: FontResolver + ?Sized,
+{
+    let mut dvitype = Dvitype::new(bytes, options, resolver);
+    let result = dvitype.run();
+    out.write_all(dvitype.text.as_bytes())?;
+    result
+}
+
+struct DvitypeFont {
+    number: i32,
+    name: Vec<u8>,
+    check_sum: i32,
+    scaled_size: i32,
+    design_size: i32,
+    space: i64,
+    bc: i32,
+    ec: i32,
+    widths: Vec<i64>,
+    pixel_widths: Vec<i64>,
+}
+
+#[derive(Clone, Copy, Default)]
+struct StackFrame {
+    h: i64,
+    v: i64,
+    w: i64,
+    x: i64,
+    y: i64,
+    z: i64,
+    hh: i64,
+    vv: i64,
+}
+
+/// How the translation of a single command inside of a page ended
+enum Outcome {
+    Done,
+    EndOfPage,
+    Failed,
+}
+
+/// A port of dvitype.web; the comments name the sections of the program
+struct Dvitype<'a, R: ?Sized> {
+    input: ByteReader<Cursor<Vec<u8>>>,
+    resolver: &'a mut R,
+    text: String,
+
+    // Options
+    out_mode: OutMode,
+    start_count: [i32; 10],
+    start_there: [bool; 10],
+    start_vals: usize,
+    max_pages: i32,
+    resolution: f64,
+    new_mag: i32,
+
+    // Fonts
+    fonts: Vec<DvitypeFont>,
+    verifier: FontVerifier,
+    pixels: PixelRounding,
+    true_conv: f64,
+    numerator: i32,
+    denominator: i32,
+    mag: i32,
+
+    // Text buffer
+    text_buf: Vec<u8>,
+
+    // State of the page
+    state: StackFrame,
+    stack: Vec<StackFrame>,
+    max_v: i64,
+    max_h: i64,
+    max_s: i32,
+    max_v_so_far: i64,
+    max_h_so_far: i64,
+    max_s_so_far: i32,
+    total_pages: i32,
+    page_count: i32,
+    s: i32,
+    ss: i32,
+    cur_font: Option<usize>,
+    showing: bool,
+    a: usize,
+    count: [i32; 10],
+
+    // Moving around in the file
+    in_postamble: bool,
+    old_backpointer: i32,
+    new_backpointer: i32,
+    started: bool,
+    post_loc: usize,
+    first_backpointer: i32,
+    after_pre: usize,
+}
+
+impl<'a, R> Dvitype<'a, R>
+where
+    R: FontResolver + ?Sized,
+{
+    fn new(bytes: Vec<u8>, options: &DvitypeOptions, resolver: &'a mut R) -> Dvitype<'a, R> {
+        let mut start_count = [0; 10];
+        let mut start_there = [false; 10];
+        for (k, count) in options.start_page.iter().take(10).enumerate() {
+            if let Some(count) = *count {
+                start_count[k] = count;
+                start_there[k] = true;
+            }
+        }
+
+        Dvitype {
+            input: ByteReader::new(bytes),
+            resolver,
+            text: String::new(),
+            out_mode: options.out_mode,
+            start_count,
+            start_there,
+            start_vals: options.start_page.len().clamp(1, 10) - 1,
+            max_pages: options.max_pages,
+            resolution: options.resolution,
+            new_mag: options.new_mag,
+            fonts: Vec::new(),
+            verifier: FontVerifier::default(),
+            pixels: PixelRounding::new(options.resolution),
+            true_conv: 0.0,
+            numerator: 0,
+            denominator: 0,
+            mag: 0,
+            text_buf: Vec::new(),
+            state: StackFrame::default(),
+            stack: Vec::new(),
+            max_v: INFINITY - 99,
+            max_h: INFINITY - 99,
+            max_s: STACK_SIZE + 1,
+            max_v_so_far: 0,
+            max_h_so_far: 0,
+            max_s_so_far: 0,
+            total_pages: 0,
+            page_count: 0,
+            s: 0,
+            ss: 0,
+            cur_font: None,
+            showing: false,
+            a: 0,
+            count: [0; 10],
+            in_postamble: false,
+            old_backpointer: -1,
+            new_backpointer: 0,
+            started: false,
+            post_loc: 0,
+            first_backpointer: 0,
+            after_pre: 0,
+        }
+    }
+
+    /// The main program (107)
+    fn run(&mut self) -> DviousResult<()> {
+        self.print_ln(BANNER);
+        self.print_options();
+        self.process_preamble()?;
+        if self.out_mode == OutMode::TheWorks {
+            self.find_postamble()?;
+            self.in_postamble = true;
+            self.read_postamble()?;
+            self.in_postamble = false;
+            self.count_pages()?;
+        }
+        self.skip_pages(false)?;
+        if !self.in_postamble {
+            self.translate_pages()?;
+        }
+        if self.out_mode < OutMode::TheWorks {
+            if !self.in_postamble {
+                self.skip_pages(true)?;
+            }
+            if self.signed_quad() != self.old_backpointer {
+                let message = format!(
+                    "backpointer in byte {} should be {}!",
+                    self.cur_loc() - 4,
+                    self.old_backpointer
+                );
+                self.print_ln(&message);
+            }
+            self.read_postamble()?;
+        }
+        Ok(())
+    }
+
+    // Output
+
+    fn print(&mut self, text: &str) {
+        self.text.push_str(text);
+    }
+
+    fn print_ln(&mut self, text: &str) {
+        self.text.push_str(text);
+        self.text.push('\n');
+    }
+
+    fn abort(&mut self, message: &str) -> DviousError {
+        self.print_ln(&format!(" {}", message));
+        DviousError::DviParseError(message.to_string())
+    }
+
+    fn bad_dvi(&mut self, message: &str) -> DviousError {
+        self.abort(&format!("Bad DVI file: {}!", message))
+    }
+
+    /// Prints all the selected options (56)
+    fn print_options(&mut self) {
+        self.print_ln("Options selected:");
+        self.print("  Starting page = ");
+        for k in 0..=self.start_vals {
+            let count = if self.start_there[k] {
+                self.start_count[k].to_string()
+            } else {
+                "*".to_string()
+            };
+            self.print(&count);
+            if k < self.start_vals {
+                self.print(".");
+            } else {
+                self.print_ln(" ");
+            }
+        }
+        let max_pages = format!("  Maximum number of pages = {}", self.max_pages);
+        self.print_ln(&max_pages);
+        let out_mode = format!("  Output level = {}", self.out_mode as u8);
+        self.print(&out_mode);
+        self.print_ln(match self.out_mode {
+            OutMode::ErrorsOnly => " (showing bops, fonts, and error messages only)",
+            OutMode::Terse => " (terse)",
+            OutMode::MnemonicsOnly => " (mnemonics)",
+            OutMode::Verbose => " (verbose)",
+            OutMode::TheWorks => " (the works)",
+        });
+        let resolution = format!("  Resolution = {:12.8} pixels per inch", self.resolution);
+        self.print_ln(&resolution);
+        if self.new_mag > 0 {
+            let new_mag = format!(
+                "  New magnification factor = {:8.3}",
+                f64::from(self.new_mag) / 1000.0
+            );
+            self.print_ln(&new_mag);
+        }
+    }
+
+    /// Displays the introductory comment and computes the conversion factors (109, 110)
+    fn process_preamble(&mut self) -> DviousResult<()> {
+        if self.get_byte() != PRE {
+            return Err(self.bad_dvi("First byte isn't start of preamble!"));
+        }
+        if self.get_byte() != ID_BYTE {
+            let message = format!("identification in byte 1 should be {}!", ID_BYTE);
+            self.print_ln(&message);
+        }
+
+        self.numerator = self.signed_quad();
+        self.denominator = self.signed_quad();
+        if self.numerator <= 0 {
+            let message = format!("numerator is {}", self.numerator);
+            return Err(self.bad_dvi(&message));
+        }
+        if self.denominator <= 0 {
+            let message = format!("denominator is {}", self.denominator);
+            return Err(self.bad_dvi(&message));
+        }
+        let message = format!(
+            "numerator/denominator={}/{}",
+            self.numerator, self.denominator
+        );
+        self.print_ln(&message);
+
+        let numerator = f64::from(self.numerator);
+        let denominator = f64::from(self.denominator);
+        self.verifier = FontVerifier::new(self.numerator as u32, self.denominator as u32);
+        self.true_conv = (numerator / 254_000.0) * (self.resolution / denominator);
+        self.mag = self.signed_quad();
+        if self.new_mag > 0 {
+            self.mag = self.new_mag;
+        } else if self.mag <= 0 {
+            let message = format!("magnification is {}", self.mag);
+            return Err(self.bad_dvi(&message));
+        }
+        let units = DviUnits::new(
+            self.numerator as u32,
+            self.denominator as u32,
+            self.mag as u32,
+        );
+        self.pixels.set_units(units);
+        let message = format!(
+            "magnification={}; {:16.8} pixels per DVI unit",
+            self.mag,
+            self.pixels.conv()
+        );
+        self.print_ln(&message);
+
+        let mut p = self.get_byte();
+        self.print("'");
+        while p > 0 {
+            p -= 1;
+            let c = xchr(self.get_byte());
+            self.text.push(c);
+        }
+        self.print_ln("'");
+        self.after_pre = self.cur_loc();
+        Ok(())
+    }
+
+    // Input
+
+    fn cur_loc(&self) -> usize {
+        self.input.position()
+    }
+
+    fn eof(&mut self) -> bool {
+        !self.input.has_more()
+    }
+
+    fn move_to_byte(&mut self, n: usize) {
+        // Moving past the end just positions the reader at the end of the file
+        let _ = self
+            .input
+            .seek(SeekFrom::Start(n.min(self.input.len()) as u64));
+    }
+
+    fn get_byte(&mut self) -> u8 {
+        self.input.read_be::<u8>().unwrap_or(0)
+    }
+
+    fn signed_byte(&mut self) -> i32 {
+        i32::from(self.get_byte() as i8)
+    }
+
+    fn get_two_bytes(&mut self) -> i32 {
+        let a = i32::from(self.get_byte());
+        a * 256 + i32::from(self.get_byte())
+    }
+
+    fn signed_pair(&mut self) -> i32 {
+        let a = self.signed_byte();
+        a * 256 + i32::from(self.get_byte())
+    }
+
+    fn get_three_bytes(&mut self) -> i32 {
+        let a = self.get_two_bytes();
+        a * 256 + i32::from(self.get_byte())
+    }
+
+    fn signed_trio(&mut self) -> i32 {
+        let a = self.signed_pair();
+        a * 256 + i32::from(self.get_byte())
+    }
+
+    fn signed_quad(&mut self) -> i32 {
+        let a = self.signed_trio();
+        a.wrapping_mul(256).wrapping_add(i32::from(self.get_byte()))
+    }
+
+    /// Computes the first parameter of each opcode (75)
+    fn first_par(&mut self, o: u8) -> i32 {
+        match o {
+            0..=127 => i32::from(o),
+            128 | 133 | 235 | 239 | 243 => i32::from(self.get_byte()),
+            129 | 134 | 236 | 240 | 244 => self.get_two_bytes(),
+            130 | 135 | 237 | 241 | 245 => self.get_three_bytes(),
+            143 | 148 | 153 | 157 | 162 | 167 => self.signed_byte(),
+            144 | 149 | 154 | 158 | 163 | 168 => self.signed_pair(),
+            145 | 150 | 155 | 159 | 164 | 169 => self.signed_trio(),
+            131 | 132 | 136 | 137 | 146 | 151 | 156 | 160 | 165 | 170 | 238 | 242 | 246 => {
+                self.signed_quad()
+            }
+            W0 => self.state.w as i32,
+            X0 => self.state.x as i32,
+            Y0 => self.state.y as i32,
+            Z0 => self.state.z as i32,
+            171..=234 => i32::from(o - FNT_NUM_0),
+            _ => 0,
+        }
+    }
+
+    // Fonts
+
+    fn print_font(&mut self, f: Option<usize>) {
+        match f {
+            Some(f) => {
+                let name: String = self.fonts[f].name.iter().map(|&b| xchr(b)).collect();
+                self.print(&name);
+            }
+            None => self.print("UNDEFINED!"),
+        }
+    }
+
+    fn font_space(&self) -> i64 {
+        self.cur_font.map_or(0, |f| self.fonts[f].space)
+    }
+
+    /// Processes a `fnt_def` command for the external font number `e` (59)
+    fn define_font(&mut self, e: i32) -> DviousResult<()> {
+        let existing = self.fonts.iter().position(|font| font.number == e);
+
+        // Read the font parameters and print the font name (61)
+        let c = self.signed_quad();
+        let q = self.signed_quad();
+        let d = self.signed_quad();
+        let conv = self.pixels.conv();
+        let m = if q <= 0 || d <= 0 {
+            1000
+        } else {
+            ((1000.0 * conv * f64::from(q)) / (self.true_conv * f64::from(d))).round() as i64
+        };
+        let p = self.get_byte();
+        let n = self.get_byte();
+        if self.showing {
+            self.print(": ");
+        } else {
+            self.print(&format!("Font {}: ", e));
+        }
+        let mut name = Vec::new();
+        if usize::from(n) + usize::from(p) == 0 {
+            self.print("null font name!");
+        } else {
+            for _ in 0..usize::from(n) + usize::from(p) {
+                name.push(self.get_byte());
+            }
+        }
+        let printed: String = name.iter().map(|&b| xchr(b)).collect();
+        self.print(&printed);
+        if !self.showing && m != 1000 {
+            self.print(&format!(" scaled {}", m));
+        }
+
+        let postamble_is_reference = (self.out_mode == OutMode::TheWorks && self.in_postamble)
+            || (self.out_mode < OutMode::TheWorks && !self.in_postamble);
+        if postamble_is_reference {
+            if existing.is_some() {
+                self.print_ln("---this font was already defined!");
+            }
+        } else if existing.is_none() {
+            self.print_ln("---this font wasn't loaded before!");
+        }
+
+        match existing {
+            None => self.load_font(e, name, c, q, d),
+            Some(f) => {
+                // Check that the current font definition matches the old one (60)
+                if self.fonts[f].check_sum != c {
+                    self.print_ln("---check sum doesn't match previous definition!");
+                }
+                if self.fonts[f].scaled_size != q {
+                    self.print_ln("---scaled size doesn't match previous definition!");
+                }
+                if self.fonts[f].design_size != d {
+                    self.print_ln("---design size doesn't match previous definition!");
+                }
+                if self.fonts[f].name != name {
+                    self.print_ln("---font name doesn't match previous definition!");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Loads the new font, unless there are problems (62, 63)
+    fn load_font(&mut self, e: i32, name: Vec<u8>, c: i32, q: i32, d: i32) {
+        let tfm = self.resolver.resolve(
+            &String::from_utf8_lossy(&name),
+            c as u32,
+            q as u32,
+            d as u32,
+        );
+        match tfm {
+            Err(DviousError::TfmParseError(_)) => {
+                if q <= 0 || q >= 0o1000000000 {
+                    self.print(&format!("---not loaded, bad scale ({})!", q));
+                } else if d <= 0 || d >= 0o1000000000 {
+                    self.print(&format!("---not loaded, bad design size ({})!", d));
+                } else {
+                    self.print_ln("---not loaded, TFM file is bad");
+                }
+            }
+            Err(_) => self.print("---not loaded, TFM file can't be opened!"),
+            Ok(tfm) => {
+                if q <= 0 || q >= 0o1000000000 {
+                    self.print(&format!("---not loaded, bad scale ({})!", q));
+                } else if d <= 0 || d >= 0o1000000000 {
+                    self.print(&format!("---not loaded, bad design size ({})!", d));
+                } else if let Some(font) = self.in_tfm(&tfm, q) {
+                    let font = DvitypeFont {
+                        number: e,
+                        name,
+                        check_sum: c,
+                        scaled_size: q,
+                        design_size: d,
+                        space: i64::from(q / 6),
+                        ..font
+                    };
+                    self.finish_loading(font, &tfm);
+                } else {
+                    self.print_ln("---not loaded, TFM file is bad");
+                }
+            }
+        }
+        if self.out_mode == OutMode::ErrorsOnly {
+            self.print_ln(" ");
+        }
+    }
+
+    fn finish_loading(&mut self, font: DvitypeFont, tfm: &TexFontMetric) {
+        let (c, q, d) = (font.check_sum, font.scaled_size, font.design_size);
+        let name = String::from_utf8_lossy(&font.name);
+        for warning in self.verifier.verify(&name, c as u32, d as u32, tfm) {
+            match warning {
+                FontWarning::ChecksumMismatch { dvi, tfm, .. } => {
+                    self.print_ln("---beware: check sums do not agree!");
+                    self.print_ln(&format!("   ({} vs. {})", dvi as i32, tfm as i32));
+                }
+                FontWarning::DesignSizeMismatch { dvi, tfm, .. } => {
+                    self.print_ln("---beware: design sizes do not agree!");
+                    self.print_ln(&format!("   ({} vs. {})", dvi, tfm));
+                }
+            }
+            self.print("   ");
+        }
+        self.print(&format!("---loaded at size {} DVI units", q));
+        let conv = self.pixels.conv();
+        let magnification =
+            ((100.0 * conv * f64::from(q)) / (self.true_conv * f64::from(d))).round() as i64;
+        if magnification != 100 {
+            self.print_ln(" ");
+            self.print(&format!(" (this font is magnified {}%)", magnification));
+        }
+        self.fonts.push(font);
+    }
+
+    /// Absorbs the widths of a font scaled to `z`, or returns `None` if the metrics
+    /// are bad (34-40)
+    fn in_tfm(&mut self, tfm: &TexFontMetric, z: i32) -> Option<DvitypeFont> {
+        let nw = tfm.width_table.len();
+        if nw == 0 || nw > 256 {
+            return None;
+        }
+
+        if tfm.header.design_size.raw() < 0 {
+            return None;
+        }
+
+        let mut in_width = Vec::with_capacity(nw);
+        for &width in &tfm.width_table {
+            in_width.push(i64::from(width.scale(z)?));
+        }
+        if in_width[0] != 0 {
+            return None;
+        }
+
+        let bc = tfm
+            .char_info_table
+            .keys()
+            .min()
+            .map_or(1, |&c| i32::from(c));
+        let ec = tfm
+            .char_info_table
+            .keys()
+            .max()
+            .map_or(0, |&c| i32::from(c));
+        let mut widths = Vec::new();
+        let mut pixel_widths = Vec::new();
+        for c in bc..=ec {
+            let index = tfm
+                .char_info_table
+                .get(&(c as u8))
+                .map_or(0, |info| usize::from(info.width_index));
+            if index >= nw {
+                return None;
+            }
+            if index == 0 {
+                widths.push(INVALID_WIDTH);
+                pixel_widths.push(0);
+            } else {
+                widths.push(in_width[index]);
+                pixel_widths.push(self.pixels.pixel_round(Dimension::new(in_width[index])));
+            }
+        }
+
+        Some(DvitypeFont {
+            number: 0,
+            name: Vec::new(),
+            check_sum: 0,
+            scaled_size: 0,
+            design_size: 0,
+            space: 0,
+            bc,
+            ec,
+            widths,
+            pixel_widths,
+        })
+    }
+
+    // Translation of pages
+
+    fn flush_text(&mut self) {
+        if !self.text_buf.is_empty() {
+            if self.out_mode > OutMode::ErrorsOnly {
+                let text: String = self.text_buf.iter().map(|&b| xchr(b)).collect();
+                self.print_ln(&format!("[{}]", text));
+            }
+            self.text_buf.clear();
+        }
+    }
+
+    fn out_text(&mut self, c: u8) {
+        if self.text_buf.len() == 79 - 2 {
+            self.flush_text();
+        }
+        self.text_buf.push(c);
+    }
+
+    fn show(&mut self, text: &str) {
+        self.flush_text();
+        self.showing = true;
+        self.print(&format!("{}: {}", self.a, text));
+    }
+
+    fn major(&mut self, text: &str) {
+        if self.out_mode > OutMode::ErrorsOnly {
+            self.show(text);
+        }
+    }
+
+    fn minor(&mut self, text: &str) {
+        if self.out_mode > OutMode::Terse {
+            self.showing = true;
+            self.print(&format!("{}: {}", self.a, text));
+        }
+    }
+
+    fn error(&mut self, text: &str) {
+        if !self.showing {
+            self.show(text);
+        } else {
+            self.print(&format!(" {}", text));
+        }
+    }
+
+    /// Translates the commands of a page after its `bop`, returning whether the page
+    /// ended properly (79, 80)
+    fn do_page(&mut self) -> DviousResult<bool> {
+        self.cur_font = None;
+        self.s = 0;
+        self.state = StackFrame::default();
+        loop {
+            self.a = self.cur_loc();
+            self.showing = false;
+            let o = self.get_byte();
+            let p = self.first_par(o);
+            if self.eof() {
+                return Err(self.bad_dvi("the file ended prematurely"));
+            }
+            match self.translate_command(o, p)? {
+                Outcome::Done => {
+                    if self.showing {
+                        self.print_ln(" ");
+                    }
+                }
+                Outcome::EndOfPage => return Ok(true),
+                Outcome::Failed => {
+                    self.print_ln("!");
+                    return Ok(false);
+                }
+            }
+        }
+    }
+
+    /// Starts the translation of command `o` (81-88)
+    fn translate_command(&mut self, o: u8, p: i32) -> DviousResult<Outcome> {
+        match o {
+            0..=127 => {
+                if o > b' ' && o <= b'~' {
+                    self.out_text(p as u8);
+                    self.minor(&format!("setchar{}", p));
+                } else {
+                    self.major(&format!("setchar{}", p));
+                }
+                Ok(self.finish_set(o, p))
+            }
+            128..=131 => {
+                self.major(&format!("set{} {}", o - SET1 + 1, p));
+                Ok(self.finish_set(o, p))
+            }
+            133..=136 => {
+                self.major(&format!("put{} {}", o - PUT1 + 1, p));
+                Ok(self.finish_set(o, p))
+            }
+            SET_RULE => {
+                self.major("setrule");
+                Ok(self.finish_rule(o, p))
+            }
+            PUT_RULE => {
+                self.major("putrule");
+                Ok(self.finish_rule(o, p))
+            }
+            NOP => {
+                self.minor("nop");
+                Ok(Outcome::Done)
+            }
+            BOP => {
+                self.error("bop occurred before eop!");
+                Ok(Outcome::Failed)
+            }
+            EOP => {
+                self.major("eop");
+                if self.s != 0 {
+                    let message = format!("stack not empty at end of page (level {})!", self.s);
+                    self.error(&message);
+                }
+                self.print_ln(" ");
+                Ok(Outcome::EndOfPage)
+            }
+            PUSH => {
+                self.major("push");
+                if self.s == self.max_s_so_far {
+                    self.max_s_so_far = self.s + 1;
+                    if self.s == self.max_s {
+                        self.error("deeper than claimed in postamble!");
+                    }
+                    if self.s == STACK_SIZE {
+                        let message =
+                            format!("DVItype capacity exceeded (stack size={})", STACK_SIZE);
+                        self.error(&message);
+                        return Ok(Outcome::Failed);
+                    }
+                }
+                self.stack.push(self.state);
+                self.s += 1;
+                self.ss = self.s - 1;
+                Ok(self.show_state())
+            }
+            POP => {
+                self.major("pop");
+                match self.stack.pop() {
+                    None => self.error("(illegal at level zero)!"),
+                    Some(state) => {
+                        self.s -= 1;
+                        self.state = state;
+                    }
+                }
+                self.ss = self.s;
+                Ok(self.show_state())
+            }
+            143..=146 => Ok(self.out_space(&format!("right{}", o - RIGHT1 + 1), p)),
+            147..=151 => {
+                self.state.w = i64::from(p);
+                Ok(self.out_space(&format!("w{}", o - W0), p))
+            }
+            152..=156 => {
+                self.state.x = i64::from(p);
+                Ok(self.out_space(&format!("x{}", o - X0), p))
+            }
+            _ => self.special_cases(o, p),
+        }
+    }
+
+    /// The commands of a page that are less common (82-87)
+    fn special_cases(&mut self, o: u8, p: i32) -> DviousResult<Outcome> {
+        let outcome = match o {
+            157..=160 => self.out_vmove(&format!("down{}", o - DOWN1 + 1), p),
+            161..=165 => {
+                self.state.y = i64::from(p);
+                self.out_vmove(&format!("y{}", o - Y0), p)
+            }
+            166..=170 => {
+                self.state.z = i64::from(p);
+                self.out_vmove(&format!("z{}", o - Z0), p)
+            }
+            171..=234 => {
+                self.major(&format!("fntnum{}", p));
+                self.change_font(p)
+            }
+            235..=238 => {
+                self.major(&format!("fnt{} {}", o - FNT1 + 1, p));
+                self.change_font(p)
+            }
+            243..=246 => {
+                self.major(&format!("fntdef{} {}", o - FNT_DEF1 + 1, p));
+                self.define_font(p)?;
+                Outcome::Done
+            }
+            XXX1..=242 => {
+                self.major("xxx '");
+                let mut bad_char = false;
+                if p < 0 {
+                    self.error("string of negative length!");
+                }
+                for _ in 0..p {
+                    let q = self.get_byte();
+                    if !(b' '..=b'~').contains(&q) {
+                        bad_char = true;
+                    }
+                    if self.showing {
+                        self.text.push(xchr(q));
+                    }
+                }
+                if self.showing {
+                    self.print("'");
+                }
+                if bad_char {
+                    self.error("non-ASCII character in xxx command!");
+                }
+                Outcome::Done
+            }
+            PRE => {
+                self.error("preamble command within a page!");
+                Outcome::Failed
+            }
+            POST | POST_POST => {
+                self.error("postamble command within a page!");
+                Outcome::Failed
+            }
+            _ => {
+                self.error(&format!("undefined command {}!", o));
+                Outcome::Done
+            }
+        };
+        Ok(outcome)
+    }
+
+    /// Finishes a command that either sets or puts a character (89)
+    fn finish_set(&mut self, o: u8, p: i32) -> Outcome {
+        let p = if p < 0 {
+            255 - ((-1 - p) % 256)
+        } else if p >= 256 {
+            p % 256
+        } else {
+            p
+        };
+
+        let q = match self.cur_font {
+            Some(f) if p >= self.fonts[f].bc && p <= self.fonts[f].ec => {
+                self.fonts[f].widths[(p - self.fonts[f].bc) as usize]
+            }
+            _ => INVALID_WIDTH,
+        };
+        if q == INVALID_WIDTH {
+            self.error(&format!("character {} invalid in font ", p));
+            self.print_font(self.cur_font);
+            if self.cur_font.is_some() {
+                self.print("!");
+            }
+        }
+        if o >= PUT1 {
+            return Outcome::Done;
+        }
+
+        let q = if q == INVALID_WIDTH {
+            0
+        } else {
+            let font = &self.fonts[self.cur_font.unwrap_or_default()];
+            self.state.hh += font.pixel_widths[(p - font.bc) as usize];
+            q
+        };
+        self.move_right(q)
+    }
+
+    /// Finishes a command that either sets or puts a rule (90)
+    fn finish_rule(&mut self, o: u8, p: i32) -> Outcome {
+        let q = self.signed_quad();
+        if self.showing {
+            self.print(&format!(" height {}, width {}", p, q));
+            if self.out_mode > OutMode::MnemonicsOnly {
+                if p <= 0 || q <= 0 {
+                    self.print(" (invisible)");
+                } else {
+                    let pixels = format!(
+                        " ({}x{} pixels)",
+                        self.pixels.rule_pixels(Dimension::from(p)),
+                        self.pixels.rule_pixels(Dimension::from(q))
+                    );
+                    self.print(&pixels);
+                }
+            }
+        }
+        if o == PUT_RULE {
+            return Outcome::Done;
+        }
+        if self.showing && self.out_mode > OutMode::MnemonicsOnly {
+            self.print_ln(" ");
+        }
+        self.state.hh += self.pixels.rule_pixels(Dimension::from(q));
+        self.move_right(i64::from(q))
+    }
+
+    /// Rounds small motions like kerns, but large ones like spaces by rounding the
+    /// position, so that accumulated rounding errors disappear (84)
+    fn out_space(&mut self, name: &str, p: i32) -> Outcome {
+        let h = Dimension::new(self.state.h);
+        let space = Dimension::new(self.font_space());
+        if self.pixels.is_word_space(Dimension::from(p), space) {
+            self.out_text(b' ');
+        }
+        self.state.hh = self
+            .pixels
+            .move_right(self.state.hh, h, Dimension::from(p), space);
+        self.minor(&format!("{} {}", name, p));
+        self.move_right(i64::from(p))
+    }
+
+    /// Like `out_space`, but with a larger threshold (85)
+    fn out_vmove(&mut self, name: &str, p: i32) -> Outcome {
+        let v = Dimension::new(self.state.v);
+        let space = Dimension::new(self.font_space());
+        self.state.vv = self
+            .pixels
+            .move_down(self.state.vv, v, Dimension::from(p), space);
+        self.major(&format!("{} {}", name, p));
+        self.move_down(i64::from(p))
+    }
+
+    /// Finishes a command that sets `h = h + q` (91)
+    fn move_right(&mut self, mut q: i64) -> Outcome {
+        let h = self.state.h;
+        if h > 0 && q > 0 && h > INFINITY - q {
+            let message = format!(
+                "arithmetic overflow! parameter changed from {} to {}",
+                q,
+                INFINITY - h
+            );
+            self.error(&message);
+            q = INFINITY - h;
+        }
+        if h < 0 && q < 0 && -h > q + INFINITY {
+            let message = format!(
+                "arithmetic overflow! parameter changed from {} to {}",
+                q,
+                (-h) - INFINITY
+            );
+            self.error(&message);
+            q = (-h) - INFINITY;
+        }
+        self.state.hh = self
+            .pixels
+            .correct_drift(self.state.hh, Dimension::new(h + q));
+        if self.showing && self.out_mode > OutMode::MnemonicsOnly {
+            let sign = if q >= 0 { "+" } else { "" };
+            let message = format!(" h:={}{}{}={}, hh:={}", h, sign, q, h + q, self.state.hh);
+            self.print(&message);
+        }
+        self.state.h = h + q;
+        if self.state.h.abs() > self.max_h_so_far {
+            if self.state.h.abs() > self.max_h + 99 {
+                let message = format!("warning: |h|>{}!", self.max_h);
+                self.error(&message);
+                self.max_h = self.state.h.abs();
+            }
+            self.max_h_so_far = self.state.h.abs();
+        }
+        Outcome::Done
+    }
+
+    /// Finishes a command that sets `v = v + p` (92)
+    fn move_down(&mut self, mut p: i64) -> Outcome {
+        let v = self.state.v;
+        if v > 0 && p > 0 && v > INFINITY - p {
+            let message = format!(
+                "arithmetic overflow! parameter changed from {} to {}",
+                p,
+                INFINITY - v
+            );
+            self.error(&message);
+            p = INFINITY - v;
+        }
+        if v < 0 && p < 0 && -v > p + INFINITY {
+            let message = format!(
+                "arithmetic overflow! parameter changed from {} to {}",
+                p,
+                (-v) - INFINITY
+            );
+            self.error(&message);
+            p = (-v) - INFINITY;
+        }
+        self.state.vv = self
+            .pixels
+            .correct_drift(self.state.vv, Dimension::new(v + p));
+        if self.showing && self.out_mode > OutMode::MnemonicsOnly {
+            let sign = if p >= 0 { "+" } else { "" };
+            let message = format!(" v:={}{}{}={}, vv:={}", v, sign, p, v + p, self.state.vv);
+            self.print(&message);
+        }
+        self.state.v = v + p;
+        if self.state.v.abs() > self.max_v_so_far {
+            if self.state.v.abs() > self.max_v + 99 {
+                let message = format!("warning: |v|>{}!", self.max_v);
+                self.error(&message);
+                self.max_v = self.state.v.abs();
+            }
+            self.max_v_so_far = self.state.v.abs();
+        }
+        Outcome::Done
+    }
+
+    /// Shows the values of all registers after `push` and `pop` (93)
+    fn show_state(&mut self) -> Outcome {
+        if self.showing && self.out_mode > OutMode::MnemonicsOnly {
+            self.print_ln(" ");
+            let state = self.state;
+            let message = format!(
+                "level {}:(h={},v={},w={},x={},y={},z={},hh={},vv={})",
+                self.ss, state.h, state.v, state.w, state.x, state.y, state.z, state.hh, state.vv
+            );
+            self.print(&message);
+        }
+        Outcome::Done
+    }
+
+    /// Finishes a command that changes the current font (94)
+    fn change_font(&mut self, p: i32) -> Outcome {
+        self.cur_font = self.fonts.iter().position(|font| font.number == p);
+        if self.cur_font.is_none() {
+            let message = format!("invalid font selection: font {} was never defined!", p);
+            self.error(&message);
+        }
+        if self.showing && self.out_mode > OutMode::MnemonicsOnly {
+            self.print(" current font is ");
+            self.print_font(self.cur_font);
+        }
+        Outcome::Done
+    }
+
+    /// Translates up to `max_pages` pages (111)
+    fn translate_pages(&mut self) -> DviousResult<()> {
+        while self.max_pages > 0 {
+            self.max_pages -= 1;
+            self.print_ln(" ");
+            let beginning = format!("{}: beginning of page ", self.cur_loc() - 45);
+            self.print(&beginning);
+            for k in 0..=self.start_vals {
+                let count = self.count[k].to_string();
+                self.print(&count);
+                if k < self.start_vals {
+                    self.print(".");
+                } else {
+                    self.print_ln(" ");
+                }
+            }
+            if !self.do_page()? {
+                return Err(self.bad_dvi("page ended unexpectedly"));
+            }
+            self.scan_bop()?;
+            if self.in_postamble {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    // Skipping pages
+
+    fn start_match(&self) -> bool {
+        (0..=self.start_vals).all(|k| !self.start_there[k] || self.start_count[k] == self.count[k])
+    }
+
+    /// Skips pages until finding the starting page or the postamble (95, 96)
+    fn skip_pages(&mut self, mut bop_seen: bool) -> DviousResult<()> {
+        self.showing = false;
+        loop {
+            if !bop_seen {
+                self.scan_bop()?;
+                if self.in_postamble {
+                    return Ok(());
+                }
+                if !self.started && self.start_match() {
+                    self.started = true;
+                    return Ok(());
+                }
+            }
+
+            loop {
+                if self.eof() {
+                    return Err(self.bad_dvi("the file ended prematurely"));
+                }
+                let k = self.get_byte();
+                let mut p = self.first_par(k);
+                match k {
+                    SET_RULE | PUT_RULE => {
+                        self.signed_quad();
+                    }
+                    243..=246 => {
+                        self.define_font(p)?;
+                        self.print_ln(" ");
+                    }
+                    XXX1..=242 => {
+                        while p > 0 {
+                            self.get_byte();
+                            p -= 1;
+                        }
+                    }
+                    BOP | PRE | POST | POST_POST | 250..=255 => {
+                        let message = format!("illegal command at byte {}", self.cur_loc() - 1);
+                        return Err(self.bad_dvi(&message));
+                    }
+                    _ => {}
+                }
+                if k == EOP {
+                    break;
+                }
+            }
+            bop_seen = false;
+        }
+    }
+
+    /// Reads commands after the preamble or an `eop` until finding a `bop` or the
+    /// postamble (99)
+    fn scan_bop(&mut self) -> DviousResult<()> {
+        let mut k;
+        loop {
+            if self.eof() {
+                return Err(self.bad_dvi("the file ended prematurely"));
+            }
+            k = self.get_byte();
+            if (FNT_DEF1..FNT_DEF1 + 4).contains(&k) {
+                let p = self.first_par(k);
+                self.define_font(p)?;
+                k = NOP;
+            }
+            if k != NOP {
+                break;
+            }
+        }
+
+        if k == POST {
+            self.in_postamble = true;
+            return Ok(());
+        }
+        if k != BOP {
+            let message = format!("byte {} is not bop", self.cur_loc() - 1);
+            return Err(self.bad_dvi(&message));
+        }
+        self.new_backpointer = self.cur_loc() as i32 - 1;
+        self.page_count += 1;
+        for k in 0..10 {
+            self.count[k] = self.signed_quad();
+        }
+        if self.signed_quad() != self.old_backpointer {
+            let message = format!(
+                "backpointer in byte {} should be {}!",
+                self.cur_loc() - 4,
+                self.old_backpointer
+            );
+            self.print_ln(&message);
+        }
+        self.old_backpointer = self.new_backpointer;
+        Ok(())
+    }
+
+    // Using the backpointers
+
+    /// Finds the postamble, working back from the end (100)
+    fn find_postamble(&mut self) -> DviousResult<()> {
+        let n = self.input.len();
+        if n < 53 {
+            return Err(self.bad_dvi(&format!("only {} bytes long", n)));
+        }
+        let mut m = n - 4;
+        let mut k;
+        loop {
+            if m == 0 {
+                return Err(self.bad_dvi("all 223s"));
+            }
+            self.move_to_byte(m);
+            k = self.get_byte();
+            m -= 1;
+            if k != 223 {
+                break;
+            }
+        }
+        if k != ID_BYTE {
+            return Err(self.bad_dvi(&format!("ID byte is {}", k)));
+        }
+        self.move_to_byte(m - 3);
+        let q = self.signed_quad();
+        if q < 0 || i64::from(q) > m as i64 - 33 {
+            let message = format!("post pointer {} at byte {}", q, m - 3);
+            return Err(self.bad_dvi(&message));
+        }
+        self.move_to_byte(q as usize);
+        if self.get_byte() != POST {
+            return Err(self.bad_dvi(&format!("byte {} is not post", q)));
+        }
+        self.post_loc = q as usize;
+        self.first_backpointer = self.signed_quad();
+        Ok(())
+    }
+
+    /// Counts the pages and moves to the starting page (102)
+    fn count_pages(&mut self) -> DviousResult<()> {
+        let mut q = self.post_loc as i64;
+        let mut p = i64::from(self.first_backpointer);
+        let mut start_loc = -1;
+        if p < 0 {
+            self.in_postamble = true;
+        } else {
+            loop {
+                if p > q - 46 {
+                    let message = format!("page link {} after byte {}", p, q);
+                    return Err(self.bad_dvi(&message));
+                }
+                q = p;
+                self.move_to_byte(q as usize);
+                if self.get_byte() == BOP {
+                    self.page_count += 1;
+                } else {
+                    return Err(self.bad_dvi(&format!("byte {} is not bop", q)));
+                }
+                for k in 0..10 {
+                    self.count[k] = self.signed_quad();
+                }
+                p = i64::from(self.signed_quad());
+                if self.start_match() {
+                    start_loc = q;
+                    self.old_backpointer = p as i32;
+                }
+                if p < 0 {
+                    break;
+                }
+            }
+            if start_loc < 0 {
+                return Err(self.abort("starting page number could not be found!"));
+            }
+            if self.old_backpointer < 0 {
+                start_loc = self.after_pre as i64;
+            }
+            self.move_to_byte(start_loc as usize);
+        }
+        if self.page_count != self.total_pages {
+            let message = format!(
+                "there are really {} pages, not {}!",
+                self.page_count, self.total_pages
+            );
+            self.print_ln(&message);
+        }
+        Ok(())
+    }
+
+    // Reading the postamble
+
+    /// Reads, translates and checks the postamble, just after its `post` and the
+    /// pointer that follows (103-106)
+    fn read_postamble(&mut self) -> DviousResult<()> {
+        self.showing = false;
+        self.post_loc = self.cur_loc() - 5;
+        let message = format!("Postamble starts at byte {}.", self.post_loc);
+        self.print_ln(&message);
+        if self.signed_quad() != self.numerator {
+            self.print_ln("numerator doesn't match the preamble!");
+        }
+        if self.signed_quad() != self.denominator {
+            self.print_ln("denominator doesn't match the preamble!");
+        }
+        if self.signed_quad() != self.mag && self.new_mag == 0 {
+            self.print_ln("magnification doesn't match the preamble!");
+        }
+        self.max_v = i64::from(self.signed_quad());
+        self.max_h = i64::from(self.signed_quad());
+        let message = format!("maxv={}, maxh={}", self.max_v, self.max_h);
+        self.print(&message);
+        self.max_s = self.get_two_bytes();
+        self.total_pages = self.get_two_bytes();
+        let message = format!(
+            ", maxstackdepth={}, totalpages={}",
+            self.max_s, self.total_pages
+        );
+        self.print_ln(&message);
+
+        if self.out_mode < OutMode::TheWorks {
+            self.compare_postamble();
+        }
+
+        // Process the font definitions of the postamble (106)
+        let mut k;
+        loop {
+            k = self.get_byte();
+            if (FNT_DEF1..FNT_DEF1 + 4).contains(&k) {
+                let p = self.first_par(k);
+                self.define_font(p)?;
+                self.print_ln(" ");
+                k = NOP;
+            }
+            if k != NOP {
+                break;
+            }
+        }
+        if k != POST_POST {
+            let message = format!("byte {} is not postpost!", self.cur_loc() - 1);
+            self.print_ln(&message);
+        }
+
+        // Make sure that the end of the file is well-formed (105)
+        if self.signed_quad() as i64 != self.post_loc as i64 {
+            let message = format!("bad postamble pointer in byte {}!", self.cur_loc() - 4);
+            self.print_ln(&message);
+        }
+        if self.get_byte() != ID_BYTE {
+            let message = format!(
+                "identification in byte {} should be {}!",
+                self.cur_loc() - 1,
+                ID_BYTE
+            );
+            self.print_ln(&message);
+        }
+        let k = self.cur_loc();
+        let mut m = 223;
+        while m == 223 && !self.eof() {
+            m = self.get_byte();
+        }
+        if !self.eof() {
+            let message = format!("signature in byte {} should be 223", self.cur_loc() - 1);
+            return Err(self.bad_dvi(&message));
+        } else if self.cur_loc() < k + 4 {
+            let message = format!(
+                "not enough signature bytes at end of file ({})",
+                self.cur_loc() - k
+            );
+            self.print_ln(&message);
+        }
+        Ok(())
+    }
+
+    /// Compares the postamble parameters with the accumulated facts (104)
+    fn compare_postamble(&mut self) {
+        if self.max_v + 99 < self.max_v_so_far {
+            let message = format!("warning: observed maxv was {}", self.max_v_so_far);
+            self.print_ln(&message);
+        }
+        if self.max_h + 99 < self.max_h_so_far {
+            let message = format!("warning: observed maxh was {}", self.max_h_so_far);
+            self.print_ln(&message);
+        }
+        if self.max_s < self.max_s_so_far {
+            let message = format!("warning: observed maxstackdepth was {}", self.max_s_so_far);
+            self.print_ln(&message);
+        }
+        if self.page_count != self.total_pages {
+            let message = format!(
+                "there are really {} pages, not {}!",
+                self.page_count, self.total_pages
+            );
+            self.print_ln(&message);
+        }
+    }
+}
+
+/// Characters outside of visible ASCII are printed as question marks
+fn xchr(c: u8) -> char {
+    if (b' '..=b'~').contains(&c) {
+        c as char
+    } else {
+        '?'
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dvi::dvitype::*;
+    use crate::fonts::resolver::MemoryResolver;
+
+    #[test]
+    fn test_parse_start_page() {
+        assert_eq!(DvitypeOptions::parse_start_page("*"), Some(vec![None]));
+        assert_eq!(
+            DvitypeOptions::parse_start_page("1.*.-5"),
+            Some(vec![Some(1), None, Some(-5)])
+        );
+        assert_eq!(DvitypeOptions::parse_start_page("1.a"), None);
+        assert_eq!(
+            DvitypeOptions::parse_start_page("1.2.3.4.5.6.7.8.9.10.11"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_print_options() {
+        let options = DvitypeOptions {
+            out_mode: OutMode::Verbose,
+            start_page: vec![Some(1), None],
+            max_pages: 2,
+            resolution: 72.27,
+            new_mag: 2000,
+        };
+        let mut resolver = MemoryResolver::new();
+        let mut dvitype = Dvitype::new(vec![], &options, &mut resolver);
+
+        dvitype.print_options();
+
+        assert_eq!(
+            dvitype.text,
+            "Options selected:\n  Starting page = 1.* \n  Maximum number of pages = 2\n  \
+             Output level = 3 (verbose)\n  Resolution =  72.27000000 pixels per inch\n  \
+             New magnification factor =    2.000\n"
+        );
+    }
+}
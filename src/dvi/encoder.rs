@@ -0,0 +1,411 @@
+use crate::dvi::opcodes::{OpCode, SpannedOpCode};
+use crate::errors::{DviousError, DviousResult};
+
+const PADDING_BYTE: u8 = 223;
+const POST_POST_LEN: usize = 6;
+
+/// Encodes the opcodes exactly as they were disassembled, using the original opcode
+/// bytes and keeping the padding after `post_post`. For well-formed input this yields
+/// a file that is byte-identical to the original one.
+pub fn encode_spanned(opcodes: &[SpannedOpCode]) -> DviousResult<Vec<u8>> {
+    let mut bytes = Vec::new();
+    for spanned in opcodes {
+        encode_opcode(&spanned.opcode, spanned.byte, &mut bytes)?;
+
+        if let OpCode::PostPost { .. } = spanned.opcode {
+            let padding = spanned.length.saturating_sub(POST_POST_LEN);
+            bytes.resize(bytes.len() + padding, PADDING_BYTE);
+        }
+    }
+    Ok(bytes)
+}
+
+/// Returns the opcode byte that encodes `opcode` in the fewest bytes.
+pub fn smallest_opcode_byte(opcode: &OpCode) -> u8 {
+    match *opcode {
+        OpCode::Set { c } if (0..=127).contains(&c) => c as u8,
+        OpCode::Set { c } => 128 + first_parameter_width(i64::from(c)),
+        OpCode::SetRule { .. } => 132,
+        OpCode::Put { c } => 133 + first_parameter_width(i64::from(c)),
+        OpCode::PutRule { .. } => 137,
+        OpCode::Nop => 138,
+        OpCode::Bop { .. } => 139,
+        OpCode::Eop => 140,
+        OpCode::Push => 141,
+        OpCode::Pop => 142,
+        OpCode::Right { b } => 143 + signed_width(b),
+        OpCode::W0 => 147,
+        OpCode::W { b } => 148 + signed_width(b),
+        OpCode::X0 => 152,
+        OpCode::X { b } => 153 + signed_width(b),
+        OpCode::Down { a } => 157 + signed_width(a),
+        OpCode::Y0 => 161,
+        OpCode::Y { a } => 162 + signed_width(a),
+        OpCode::Z0 => 166,
+        OpCode::Z { a } => 167 + signed_width(a),
+        OpCode::Fnt { k } if (0..=63).contains(&k) => 171 + k as u8,
+        OpCode::Fnt { k } => 235 + first_parameter_width(i64::from(k)),
+        OpCode::Xxx { k, .. } => 239 + first_parameter_width(i64::from(k)),
+        OpCode::FntDef { k, .. } => 243 + first_parameter_width(i64::from(k)),
+        OpCode::Pre { .. } => 247,
+        OpCode::Post { .. } => 248,
+        OpCode::PostPost { .. } => 249,
+    }
+}
+
+/// Offset from the first variant for an unsigned parameter of one to three bytes,
+/// falling back to the four byte variant.
+fn first_parameter_width(value: i64) -> u8 {
+    match value {
+        0..=0xFF => 0,
+        0x100..=0xFFFF => 1,
+        0x1_0000..=0xFF_FFFF => 2,
+        _ => 3,
+    }
+}
+
+/// Offset from the first variant for a signed parameter of one to four bytes.
+fn signed_width(value: i32) -> u8 {
+    match value {
+        -0x80..=0x7F => 0,
+        -0x8000..=0x7FFF => 1,
+        -0x80_0000..=0x7F_FFFF => 2,
+        _ => 3,
+    }
+}
+
+/// Appends `opcode` to `out`, using `byte` as the opcode. The opcode byte selects the
+/// width of the first parameter, e.g. 128..131 for `set1`..`set4`. It is an error to
+/// use a byte that belongs to a different command or is too small for the parameter.
+pub fn encode_opcode(opcode: &OpCode, byte: u8, out: &mut Vec<u8>) -> DviousResult<()> {
+    match *opcode {
+        OpCode::Set { c } => match byte {
+            0..=127 if c == i32::from(byte) => out.push(byte),
+            128..=131 => write_first_parameter(out, byte, 128, c)?,
+            _ => return Err(mismatch(opcode, byte)),
+        },
+        OpCode::SetRule { a, b } => {
+            expect_byte(opcode, byte, 132)?;
+            out.push(byte);
+            write_i32(out, a);
+            write_i32(out, b);
+        }
+        OpCode::Put { c } => match byte {
+            133..=136 => write_first_parameter(out, byte, 133, c)?,
+            _ => return Err(mismatch(opcode, byte)),
+        },
+        OpCode::PutRule { a, b } => {
+            expect_byte(opcode, byte, 137)?;
+            out.push(byte);
+            write_i32(out, a);
+            write_i32(out, b);
+        }
+        OpCode::Nop => expect_and_push(opcode, byte, 138, out)?,
+        OpCode::Bop {
+            c0,
+            c1,
+            c2,
+            c3,
+            c4,
+            c5,
+            c6,
+            c7,
+            c8,
+            c9,
+            p,
+        } => {
+            expect_byte(opcode, byte, 139)?;
+            out.push(byte);
+            for &count in [c0, c1, c2, c3, c4, c5, c6, c7, c8, c9, p].iter() {
+                write_i32(out, count);
+            }
+        }
+        OpCode::Eop => expect_and_push(opcode, byte, 140, out)?,
+        OpCode::Push => expect_and_push(opcode, byte, 141, out)?,
+        OpCode::Pop => expect_and_push(opcode, byte, 142, out)?,
+        OpCode::Right { b } => write_movement(opcode, out, byte, 143, b)?,
+        OpCode::W0 => expect_and_push(opcode, byte, 147, out)?,
+        OpCode::W { b } => write_movement(opcode, out, byte, 148, b)?,
+        OpCode::X0 => expect_and_push(opcode, byte, 152, out)?,
+        OpCode::X { b } => write_movement(opcode, out, byte, 153, b)?,
+        OpCode::Down { a } => write_movement(opcode, out, byte, 157, a)?,
+        OpCode::Y0 => expect_and_push(opcode, byte, 161, out)?,
+        OpCode::Y { a } => write_movement(opcode, out, byte, 162, a)?,
+        OpCode::Z0 => expect_and_push(opcode, byte, 166, out)?,
+        OpCode::Z { a } => write_movement(opcode, out, byte, 167, a)?,
+        OpCode::Fnt { k } => match byte {
+            171..=234 if k == i32::from(byte - 171) => out.push(byte),
+            235..=238 => write_first_parameter(out, byte, 235, k)?,
+            _ => return Err(mismatch(opcode, byte)),
+        },
+        OpCode::Xxx { k, ref x } => {
+            if !(239..=242).contains(&byte) {
+                return Err(mismatch(opcode, byte));
+            }
+            if k as usize != x.len() {
+                return Err(DviousError::DviEncodeError(format!(
+                    "xxx specifies [{}] bytes, but carries [{}]",
+                    k,
+                    x.len()
+                )));
+            }
+            out.push(byte);
+            write_unsigned(out, u64::from(k), usize::from(byte - 238))?;
+            out.extend_from_slice(x);
+        }
+        OpCode::FntDef {
+            k,
+            c,
+            s,
+            d,
+            a,
+            l,
+            ref n,
+        } => {
+            if !(243..=246).contains(&byte) {
+                return Err(mismatch(opcode, byte));
+            }
+            if usize::from(a) + usize::from(l) != n.len() {
+                return Err(DviousError::DviEncodeError(format!(
+                    "fnt_def specifies [{}] bytes for the font name, but carries [{}]",
+                    usize::from(a) + usize::from(l),
+                    n.len()
+                )));
+            }
+            write_first_parameter(out, byte, 243, k)?;
+            write_u32(out, c);
+            write_u32(out, s);
+            write_u32(out, d);
+            out.push(a);
+            out.push(l);
+            out.extend_from_slice(n);
+        }
+        OpCode::Pre {
+            i,
+            num,
+            den,
+            mag,
+            k,
+            ref x,
+        } => {
+            expect_byte(opcode, byte, 247)?;
+            if usize::from(k) != x.len() {
+                return Err(DviousError::DviEncodeError(format!(
+                    "pre specifies [{}] bytes of comment, but carries [{}]",
+                    k,
+                    x.len()
+                )));
+            }
+            out.push(byte);
+            out.push(i);
+            write_u32(out, num);
+            write_u32(out, den);
+            write_u32(out, mag);
+            out.push(k);
+            out.extend_from_slice(x);
+        }
+        OpCode::Post {
+            p,
+            num,
+            den,
+            mag,
+            l,
+            u,
+            s,
+            t,
+        } => {
+            expect_byte(opcode, byte, 248)?;
+            out.push(byte);
+            write_pointer(out, p)?;
+            write_u32(out, num);
+            write_u32(out, den);
+            write_u32(out, mag);
+            write_u32(out, l);
+            write_u32(out, u);
+            out.extend_from_slice(&s.to_be_bytes());
+            out.extend_from_slice(&t.to_be_bytes());
+        }
+        OpCode::PostPost { q, i } => {
+            expect_byte(opcode, byte, 249)?;
+            out.push(byte);
+            write_pointer(out, q)?;
+            out.push(i);
+        }
+    }
+    Ok(())
+}
+
+/// For commands with four variants, where the last one takes a signed four byte
+/// parameter and the others take unsigned parameters of one to three bytes.
+fn write_first_parameter(out: &mut Vec<u8>, byte: u8, first: u8, value: i32) -> DviousResult<()> {
+    let width = usize::from(byte - first) + 1;
+    out.push(byte);
+    if width == 4 {
+        write_i32(out, value);
+        Ok(())
+    } else if value < 0 {
+        Err(DviousError::DviEncodeError(format!(
+            "Negative parameter [{}] cannot be encoded in [{}] unsigned bytes",
+            value, width
+        )))
+    } else {
+        write_unsigned(out, value as u64, width)
+    }
+}
+
+/// Movements always take signed parameters of one to four bytes.
+fn write_movement(
+    opcode: &OpCode,
+    out: &mut Vec<u8>,
+    byte: u8,
+    first: u8,
+    value: i32,
+) -> DviousResult<()> {
+    if byte < first || byte > first + 3 {
+        return Err(mismatch(opcode, byte));
+    }
+    out.push(byte);
+    write_signed(out, value, usize::from(byte - first) + 1)
+}
+
+fn write_unsigned(out: &mut Vec<u8>, value: u64, width: usize) -> DviousResult<()> {
+    if value >> (8 * width) != 0 {
+        return Err(DviousError::DviEncodeError(format!(
+            "Parameter [{}] does not fit into [{}] unsigned bytes",
+            value, width
+        )));
+    }
+    out.extend_from_slice(&value.to_be_bytes()[8 - width..]);
+    Ok(())
+}
+
+fn write_signed(out: &mut Vec<u8>, value: i32, width: usize) -> DviousResult<()> {
+    let bits = 8 * width as u32;
+    let fits = bits == 32 || (value >> (bits - 1) == 0 || value >> (bits - 1) == -1);
+    if !fits {
+        return Err(DviousError::DviEncodeError(format!(
+            "Parameter [{}] does not fit into [{}] signed bytes",
+            value, width
+        )));
+    }
+    out.extend_from_slice(&value.to_be_bytes()[4 - width..]);
+    Ok(())
+}
+
+fn write_i32(out: &mut Vec<u8>, value: i32) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+fn write_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+fn write_pointer(out: &mut Vec<u8>, pointer: Option<usize>) -> DviousResult<()> {
+    let value = match pointer {
+        Some(offset) if offset > i32::MAX as usize => {
+            return Err(DviousError::DviEncodeError(format!(
+                "Pointer [{}] does not fit into four bytes",
+                offset
+            )))
+        }
+        Some(offset) => offset as i32,
+        None => -1,
+    };
+    write_i32(out, value);
+    Ok(())
+}
+
+fn expect_byte(opcode: &OpCode, byte: u8, expected: u8) -> DviousResult<()> {
+    if byte == expected {
+        Ok(())
+    } else {
+        Err(mismatch(opcode, byte))
+    }
+}
+
+fn expect_and_push(opcode: &OpCode, byte: u8, expected: u8, out: &mut Vec<u8>) -> DviousResult<()> {
+    expect_byte(opcode, byte, expected)?;
+    out.push(byte);
+    Ok(())
+}
+
+fn mismatch(opcode: &OpCode, byte: u8) -> DviousError {
+    DviousError::DviEncodeError(format!(
+        "Opcode byte [{}] cannot be used to encode [{}]",
+        byte, opcode
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dvi::disassembler::disassemble_spanned;
+    use crate::dvi::encoder::*;
+
+    #[test]
+    fn test_encode_keeps_parameter_width() {
+        let bytes = vec![128, 0x41, 131, 0, 0, 0, 0x41, 146, 0xFF, 0xFF, 0xFF, 0xD6];
+
+        let opcodes = disassemble_spanned(bytes.clone()).unwrap();
+
+        assert_eq!(opcodes[0].opcode, opcodes[1].opcode);
+        assert_eq!(encode_spanned(&opcodes).unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_encode_keeps_padding() {
+        let bytes = vec![249, 0, 0, 0, 0x2A, 2, 223, 223, 223, 223, 223, 223, 223];
+
+        let opcodes = disassemble_spanned(bytes.clone()).unwrap();
+
+        assert_eq!(encode_spanned(&opcodes).unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_encode_fnt_num() {
+        let mut out = Vec::new();
+
+        encode_opcode(&OpCode::Fnt { k: 7 }, 178, &mut out).unwrap();
+
+        assert_eq!(out, vec![178]);
+    }
+
+    #[test]
+    fn test_encode_signed_movement() {
+        let mut out = Vec::new();
+
+        encode_opcode(&OpCode::Down { a: -4200 }, 158, &mut out).unwrap();
+
+        assert_eq!(out, vec![158, 0xEF, 0x98]);
+    }
+
+    #[test]
+    fn test_smallest_opcode_byte() {
+        assert_eq!(smallest_opcode_byte(&OpCode::Set { c: 0x41 }), 0x41);
+        assert_eq!(smallest_opcode_byte(&OpCode::Set { c: 0x80 }), 128);
+        assert_eq!(smallest_opcode_byte(&OpCode::Set { c: 0x1_0000 }), 130);
+        assert_eq!(smallest_opcode_byte(&OpCode::Set { c: -1 }), 131);
+        assert_eq!(smallest_opcode_byte(&OpCode::Right { b: -0x80 }), 143);
+        assert_eq!(smallest_opcode_byte(&OpCode::Right { b: 0x80 }), 144);
+        assert_eq!(smallest_opcode_byte(&OpCode::Down { a: 0x80_0000 }), 160);
+        assert_eq!(smallest_opcode_byte(&OpCode::Fnt { k: 63 }), 234);
+        assert_eq!(smallest_opcode_byte(&OpCode::Fnt { k: 64 }), 235);
+    }
+
+    #[test]
+    fn test_encode_rejects_too_narrow_byte() {
+        let mut out = Vec::new();
+
+        let result = encode_opcode(&OpCode::Right { b: 0x1234 }, 143, &mut out);
+
+        assert!(result.is_err(), "Expected Err, was Ok");
+    }
+
+    #[test]
+    fn test_encode_rejects_wrong_command() {
+        let mut out = Vec::new();
+
+        let result = encode_opcode(&OpCode::Push, 142, &mut out);
+
+        assert!(result.is_err(), "Expected Err, was Ok");
+    }
+}
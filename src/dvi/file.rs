@@ -0,0 +1,320 @@
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::dvi::disassembler::Disassembler;
+use crate::dvi::opcodes::OpCode;
+use crate::errors::{DviousError, DviousResult};
+use crate::util::byte_reader::ByteReader;
+
+const PADDING_BYTE: u8 = 223;
+const MIN_PADDING_LEN: usize = 4;
+const POST_POST: u8 = 249;
+const POST_POST_LEN: usize = 6;
+
+/// Random access to the pages of a DVI file. The page index is built the way
+/// dvitype does it: the `post_post` pointer at the end of the file leads to the
+/// postamble, whose pointer to the final `bop` starts the chain of back pointers.
+pub struct DviFile<R: Read + Seek> {
+    disassembler: Disassembler<R>,
+    preamble: OpCode,
+    postamble: OpCode,
+    font_definitions: Vec<OpCode>,
+    pages: Vec<usize>,
+}
+
+impl DviFile<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> DviousResult<DviFile<BufReader<File>>> {
+        let file = File::open(path)?;
+        DviFile::new(BufReader::new(file))
+    }
+}
+
+impl<R: Read + Seek> DviFile<R> {
+    pub fn new(source: R) -> DviousResult<DviFile<R>> {
+        let mut disassembler = Disassembler::new(ByteReader::from_reader(source));
+
+        disassembler.seek(SeekFrom::Start(0))?;
+        let preamble = disassembler.disassemble_next()?;
+        match preamble {
+            OpCode::Pre { .. } => {}
+            _ => {
+                return Err(DviousError::DviParseError(format!(
+                    "Expected DVI file to start with a preamble, but found [{}]",
+                    preamble
+                )))
+            }
+        }
+
+        let post_post_offset = Self::find_post_post(&mut disassembler)?;
+        disassembler.seek(SeekFrom::Start(post_post_offset as u64))?;
+        let post_offset = match disassembler.disassemble_next()? {
+            OpCode::PostPost { q: Some(q), .. } => q,
+            opcode => {
+                return Err(DviousError::DviParseError(format!(
+                    "Expected post_post with a valid pointer at byte [{}], but found [{}]",
+                    post_post_offset, opcode
+                )))
+            }
+        };
+
+        disassembler.seek(SeekFrom::Start(post_offset as u64))?;
+        let postamble = disassembler.disassemble_next()?;
+        let last_bop = match postamble {
+            OpCode::Post { p, .. } => p,
+            _ => {
+                return Err(DviousError::DviParseError(format!(
+                    "Expected postamble at byte [{}], but found [{}]",
+                    post_offset, postamble
+                )))
+            }
+        };
+
+        let font_definitions = Self::read_postamble_font_definitions(&mut disassembler)?;
+        let pages = Self::read_page_index(&mut disassembler, last_bop, post_offset)?;
+
+        Ok(DviFile {
+            disassembler,
+            preamble,
+            postamble,
+            font_definitions,
+            pages,
+        })
+    }
+
+    pub fn preamble(&self) -> &OpCode {
+        &self.preamble
+    }
+
+    pub fn postamble(&self) -> &OpCode {
+        &self.postamble
+    }
+
+    /// The font definitions found between `post` and `post_post`
+    pub fn font_definitions(&self) -> &[OpCode] {
+        &self.font_definitions
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Byte offsets of the `bop` of every page, in the order the pages appear in the file
+    pub fn page_offsets(&self) -> &[usize] {
+        &self.pages
+    }
+
+    /// Decodes the `n`-th page (counting from zero), from its `bop` up to and including its `eop`.
+    pub fn page(&mut self, n: usize) -> DviousResult<Vec<OpCode>> {
+        let offset = match self.pages.get(n) {
+            Some(&offset) => offset,
+            None => return Err(DviousError::IndexOutOfBoundsError),
+        };
+
+        self.disassembler.seek(SeekFrom::Start(offset as u64))?;
+
+        let mut opcodes = Vec::new();
+        loop {
+            let opcode = self.disassembler.disassemble_next()?;
+            let is_eop = opcode == OpCode::Eop;
+            opcodes.push(opcode);
+            if is_eop {
+                break;
+            }
+        }
+
+        Ok(opcodes)
+    }
+
+    /// Skips the trailing 223s backwards, of which there have to be at least four, and
+    /// returns the offset of the `post_post` command
+    fn find_post_post(disassembler: &mut Disassembler<R>) -> DviousResult<usize> {
+        let len = disassembler.seek(SeekFrom::End(0))?;
+
+        let mut k = len;
+        while k > 0 && disassembler.read_u8_at(k - 1)? == PADDING_BYTE {
+            k -= 1;
+        }
+        if len - k < MIN_PADDING_LEN {
+            return Err(DviousError::DviParseError(format!(
+                "Expected at least [{}] bytes [{}] at the end of the DVI file, but found [{}]",
+                MIN_PADDING_LEN,
+                PADDING_BYTE,
+                len - k
+            )));
+        }
+
+        // k - 1 is now the identification byte, preceded by the pointer q and the opcode
+        if k < POST_POST_LEN || disassembler.read_u8_at(k - POST_POST_LEN)? != POST_POST {
+            return Err(DviousError::DviParseError(
+                "Could not find post_post at the end of the DVI file".to_string(),
+            ));
+        }
+
+        Ok(k - POST_POST_LEN)
+    }
+
+    fn read_postamble_font_definitions(
+        disassembler: &mut Disassembler<R>,
+    ) -> DviousResult<Vec<OpCode>> {
+        let mut font_definitions = Vec::new();
+        loop {
+            match disassembler.disassemble_next()? {
+                opcode @ OpCode::FntDef { .. } => font_definitions.push(opcode),
+                OpCode::Nop => {}
+                OpCode::PostPost { .. } => break,
+                opcode => {
+                    return Err(DviousError::DviParseError(format!(
+                        "Unexpected [{}] in postamble",
+                        opcode
+                    )))
+                }
+            }
+        }
+        Ok(font_definitions)
+    }
+
+    /// Follows the `bop` back pointers starting from the final page
+    fn read_page_index(
+        disassembler: &mut Disassembler<R>,
+        last_bop: Option<usize>,
+        post_offset: usize,
+    ) -> DviousResult<Vec<usize>> {
+        let mut pages = Vec::new();
+        let mut next = last_bop;
+        let mut limit = post_offset;
+
+        while let Some(offset) = next {
+            // Pointers have to go backwards, otherwise we would loop forever
+            if offset >= limit {
+                return Err(DviousError::DviParseError(format!(
+                    "Back pointer [{}] does not point before [{}]",
+                    offset, limit
+                )));
+            }
+
+            disassembler.seek(SeekFrom::Start(offset as u64))?;
+            next = match disassembler.disassemble_next()? {
+                OpCode::Bop { p, .. } if p < 0 => None,
+                OpCode::Bop { p, .. } => Some(p as usize),
+                opcode => {
+                    return Err(DviousError::DviParseError(format!(
+                        "Expected bop at byte [{}], but found [{}]",
+                        offset, opcode
+                    )))
+                }
+            };
+
+            pages.push(offset);
+            limit = offset;
+        }
+
+        pages.reverse();
+        Ok(pages)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::dvi::file::DviFile;
+    use crate::dvi::opcodes::OpCode;
+    use crate::errors::DviousError;
+
+    #[test]
+    fn test_page_index() {
+        let dvi = DviFile::new(Cursor::new(two_page_fixture())).unwrap();
+
+        assert_eq!(dvi.page_count(), 2);
+        assert_eq!(dvi.page_offsets(), &[15, 62]);
+        assert_eq!(dvi.font_definitions().len(), 0);
+    }
+
+    #[test]
+    fn test_page() {
+        let mut dvi = DviFile::new(Cursor::new(two_page_fixture())).unwrap();
+
+        let page = dvi.page(1).unwrap();
+
+        assert_eq!(page.len(), 3);
+        assert_eq!(page[1], OpCode::Set { c: 0x62 });
+        assert_eq!(page[2], OpCode::Eop);
+    }
+
+    #[test]
+    fn test_page_out_of_bounds() {
+        let mut dvi = DviFile::new(Cursor::new(two_page_fixture())).unwrap();
+
+        match dvi.page(2) {
+            Err(DviousError::IndexOutOfBoundsError) => {}
+            other => panic!("Expected IndexOutOfBoundsError, was {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_missing_post_post() {
+        let mut bytes = two_page_fixture();
+        bytes.truncate(bytes.len() - 8);
+
+        match DviFile::new(Cursor::new(bytes)) {
+            Err(DviousError::DviParseError(_)) => {}
+            Err(why) => panic!("Expected DviParseError, was {:?}", why),
+            Ok(_) => panic!("Expected DviParseError, was Ok"),
+        }
+    }
+
+    #[test]
+    fn test_too_few_padding_bytes() {
+        let mut bytes = two_page_fixture();
+        bytes.pop();
+
+        match DviFile::new(Cursor::new(bytes)) {
+            Err(DviousError::DviParseError(message)) => assert_eq!(
+                message,
+                "Expected at least [4] bytes [223] at the end of the DVI file, but found [3]"
+            ),
+            Err(why) => panic!("Expected DviParseError, was {:?}", why),
+            Ok(_) => panic!("Expected DviParseError, was Ok"),
+        }
+    }
+
+    /// Two pages that only set a single character each
+    #[rustfmt::skip]
+    fn two_page_fixture() -> Vec<u8> {
+        let mut bytes = vec![
+            247, 2,
+            0x01, 0x83, 0x92, 0xC0,
+            0x1C, 0x3B, 0x00, 0x00,
+            0x00, 0x00, 0x03, 0xE8,
+            0,
+        ];
+
+        for (c, p) in [(0x61, -1_i32), (0x62, 15)].iter() {
+            bytes.push(139);
+            for counter in 0..10_i32 {
+                bytes.extend_from_slice(&counter.to_be_bytes());
+            }
+            bytes.extend_from_slice(&p.to_be_bytes());
+            bytes.push(*c);
+            bytes.push(140);
+        }
+
+        let post = bytes.len() as i32;
+        bytes.push(248);
+        bytes.extend_from_slice(&62_i32.to_be_bytes());
+        bytes.extend_from_slice(&[
+            0x01, 0x83, 0x92, 0xC0,
+            0x1C, 0x3B, 0x00, 0x00,
+            0x00, 0x00, 0x03, 0xE8,
+            0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00,
+            0x00, 0x01,
+            0x00, 0x02,
+        ]);
+        bytes.push(249);
+        bytes.extend_from_slice(&post.to_be_bytes());
+        bytes.extend_from_slice(&[2, 223, 223, 223, 223]);
+        bytes
+    }
+}
@@ -1,133 +1,872 @@
+use std::collections::HashMap;
+
+use crate::dvi::device::{DviDevice, PageCollector};
 use crate::dvi::opcodes::OpCode;
-use crate::errors::DviousResult;
+use crate::dvi::page::{Glyph, Page, PixelPosition, PixelRule, Rule};
+use crate::dvi::pixels::PixelRounding;
+use crate::dvi::units::DviUnits;
+use crate::errors::{DviousError, DviousResult};
+use crate::fonts::resolver::{FontResolver, KpsewhichResolver};
 use crate::fonts::tfm::*;
+use crate::fonts::verification::{FontVerifier, FontWarning};
+use crate::util::dimension::Dimension;
 
-
-/// The units used internally by the interpreter are TeX scaled points (sp).
+/// The units used internally by the interpreter are DVI units, which are TeX scaled
+/// points (sp) for files written by TeX.
 pub struct Interpreter {
     registers: RegisterFrame,
-    f: Option<u32>,
+    f: Option<i32>,
     stack: Vec<RegisterFrame>,
-    fonts: Vec<FontInformation>,
+    fonts: HashMap<i32, FontInformation>,
+    resolver: Box<dyn FontResolver>,
+    in_page: bool,
+    collector: PageCollector,
+    verifier: FontVerifier,
+    strict: bool,
+    warnings: Vec<FontWarning>,
+    pixels: Option<PixelRounding>,
+    units: DviUnits,
 }
 
-#[derive(Default, Debug)]
+/// The registers of the DVI machine; `hh` and `vv` are the position in pixels
+/// and only change if the interpreter rounds to pixels
+#[derive(Clone, Default, Debug)]
 struct RegisterFrame {
-    h: i64,
-    v: i64,
-    w: i64,
-    x: i64,
-    y: i64,
-    z: i64,
+    h: Dimension,
+    v: Dimension,
+    w: Dimension,
+    x: Dimension,
+    y: Dimension,
+    z: Dimension,
+    hh: i64,
+    vv: i64,
 }
 
+/// A font defined by `fnt_def`, with its scaled size `s` and design size `d` in DVI
+/// units and its metrics
 #[derive(Debug)]
-struct FontInformation {
-    s: i32,
-    d: i32,
-    tfm: TexFontMetric,
+pub struct FontInformation {
+    pub name: String,
+    pub s: i32,
+    pub d: i32,
+    pub tfm: TexFontMetric,
+}
+
+impl Default for Interpreter {
+    fn default() -> Interpreter {
+        Interpreter::new()
+    }
 }
 
 impl Interpreter {
-    fn new() -> Interpreter {
+    /// Creates an interpreter that finds the metrics of fonts with kpsewhich
+    pub fn new() -> Interpreter {
+        Interpreter::with_resolver(KpsewhichResolver)
+    }
+
+    /// Creates an interpreter that obtains the metrics of fonts from `resolver`
+    pub fn with_resolver<R: FontResolver + 'static>(resolver: R) -> Interpreter {
         Interpreter {
             registers: Default::default(),
             f: Option::None,
             stack: Vec::new(),
-            fonts: Vec::new(),
+            fonts: HashMap::new(),
+            resolver: Box::new(resolver),
+            in_page: false,
+            collector: Default::default(),
+            verifier: Default::default(),
+            strict: false,
+            warnings: Vec::new(),
+            pixels: Option::None,
+            units: Default::default(),
         }
     }
 
-    pub fn execute(&mut self, instructions: Vec<OpCode>) -> DviousResult<()> {
+    /// Rounds the positions of glyphs and rules to pixels like dvitype does, with the
+    /// units and magnification taken from the preamble
+    pub fn set_pixel_rounding(&mut self, pixels: PixelRounding) {
+        self.pixels = Some(pixels);
+    }
+
+    /// In strict mode, disagreements between font definitions and their metrics are
+    /// errors instead of warnings
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// The units of the preamble, which convert the dimensions of pages to physical
+    /// units
+    pub fn units(&self) -> DviUnits {
+        self.units
+    }
+
+    /// The font defined with number `k`
+    pub fn font(&self, k: i32) -> Option<&FontInformation> {
+        self.fonts.get(&k)
+    }
+
+    /// The disagreements between font definitions and their metrics found so far
+    pub fn warnings(&self) -> &[FontWarning] {
+        &self.warnings
+    }
+
+    /// Executes the instructions and returns the pages completed by them. Characters,
+    /// rules and specials outside of `bop` and `eop` only change the position.
+    pub fn execute(&mut self, instructions: Vec<OpCode>) -> DviousResult<Vec<Page>> {
+        let mut collector = std::mem::take(&mut self.collector);
+        let result = self.execute_with_device(instructions, &mut collector);
+        let pages = collector.pages.drain(..).collect();
+        self.collector = collector;
+        result.map(|_| pages)
+    }
+
+    /// Executes the instructions and reports the contents of the pages to `device`
+    pub fn execute_with_device<D: DviDevice>(
+        &mut self,
+        instructions: Vec<OpCode>,
+        device: &mut D,
+    ) -> DviousResult<()> {
         for instruction in instructions {
-            match instruction {
-                OpCode::Set { c } => self.handle_set(c),
-                _ => unimplemented!(),
+            self.execute_one(instruction, device)?;
+        }
+        Ok(())
+    }
+
+    fn execute_one(&mut self, instruction: OpCode, device: &mut dyn DviDevice) -> DviousResult<()> {
+        match instruction {
+            OpCode::Set { c } => {
+                let dimensions = self.handle_put(c, device)?;
+                if let Some(ref pixels) = self.pixels {
+                    self.registers.hh += pixels.pixel_round(dimensions.width);
+                }
+                self.advance(dimensions.width);
+            }
+            OpCode::SetRule { a, b } => {
+                self.handle_put_rule(a, b, device);
+                if let Some(ref pixels) = self.pixels {
+                    self.registers.hh += pixels.rule_pixels(Dimension::from(b));
+                }
+                self.advance(Dimension::from(b));
+            }
+            OpCode::Put { c } => {
+                self.handle_put(c, device)?;
+            }
+            OpCode::PutRule { a, b } => self.handle_put_rule(a, b, device),
+            OpCode::Nop => {}
+            OpCode::Bop {
+                c0,
+                c1,
+                c2,
+                c3,
+                c4,
+                c5,
+                c6,
+                c7,
+                c8,
+                c9,
+                ..
+            } => self.handle_bop([c0, c1, c2, c3, c4, c5, c6, c7, c8, c9], device),
+            OpCode::Eop => self.handle_eop(device)?,
+            OpCode::Push => self.stack.push(self.registers.clone()),
+            OpCode::Pop => self.handle_pop()?,
+            OpCode::Right { b } => self.move_right(Dimension::from(b)),
+            OpCode::W0 => self.move_right(self.registers.w),
+            OpCode::W { b } => {
+                self.registers.w = Dimension::from(b);
+                self.move_right(self.registers.w);
+            }
+            OpCode::X0 => self.move_right(self.registers.x),
+            OpCode::X { b } => {
+                self.registers.x = Dimension::from(b);
+                self.move_right(self.registers.x);
+            }
+            OpCode::Down { a } => self.move_down(Dimension::from(a)),
+            OpCode::Y0 => self.move_down(self.registers.y),
+            OpCode::Y { a } => {
+                self.registers.y = Dimension::from(a);
+                self.move_down(self.registers.y);
             }
+            OpCode::Z0 => self.move_down(self.registers.z),
+            OpCode::Z { a } => {
+                self.registers.z = Dimension::from(a);
+                self.move_down(self.registers.z);
+            }
+            OpCode::Fnt { k } => self.handle_fnt(k)?,
+            OpCode::Xxx { ref x, .. } => self.handle_xxx(x, device),
+            OpCode::FntDef {
+                k, c, s, d, ref n, ..
+            } => self.handle_fnt_def(k, c, s, d, n)?,
+            OpCode::Pre { num, den, mag, .. } => {
+                self.verifier = FontVerifier::new(num, den);
+                self.units = DviUnits::new(num, den, mag);
+                if let Some(ref mut pixels) = self.pixels {
+                    pixels.set_units(self.units);
+                }
+            }
+            OpCode::Post { .. } | OpCode::PostPost { .. } => {}
         }
         Ok(())
     }
 
-    fn handle_set(&mut self, _c: i32) {
-        // self.registers.h += self.get_character_width(c);
+    fn handle_put(&mut self, c: i32, device: &mut dyn DviDevice) -> DviousResult<CharMetrics> {
+        let dimensions = self.get_character_dimensions(c)?;
+        if self.in_page {
+            device.glyph(&Glyph {
+                font: self.f.unwrap_or_default(),
+                c,
+                h: self.registers.h,
+                v: self.registers.v,
+                width: dimensions.width,
+                height: dimensions.height,
+                depth: dimensions.depth,
+                pixels: self.pixels.as_ref().map(|_| PixelPosition {
+                    hh: self.registers.hh,
+                    vv: self.registers.vv,
+                }),
+            });
+        }
+        Ok(dimensions)
+    }
+
+    fn handle_put_rule(&mut self, a: i32, b: i32, device: &mut dyn DviDevice) {
+        // Rules without a positive extent in both directions are invisible
+        if self.in_page && a > 0 && b > 0 {
+            let (width, height) = (Dimension::from(b), Dimension::from(a));
+            device.rule(&Rule {
+                h: self.registers.h,
+                v: self.registers.v,
+                width,
+                height,
+                pixels: self.pixels.as_ref().map(|pixels| PixelRule {
+                    hh: self.registers.hh,
+                    vv: self.registers.vv,
+                    width: pixels.rule_pixels(width),
+                    height: pixels.rule_pixels(height),
+                }),
+            });
+        }
+    }
+
+    /// Moves right after a character or rule, whose width in pixels was added already
+    fn advance(&mut self, width: Dimension) {
+        self.registers.h += width;
+        if let Some(ref pixels) = self.pixels {
+            self.registers.hh = pixels.correct_drift(self.registers.hh, self.registers.h);
+        }
+    }
+
+    fn move_right(&mut self, p: Dimension) {
+        if let Some(ref pixels) = self.pixels {
+            let space = self.font_space();
+            let registers = &mut self.registers;
+            registers.hh = pixels.move_right(registers.hh, registers.h, p, space);
+            registers.h += p;
+            registers.hh = pixels.correct_drift(registers.hh, registers.h);
+        } else {
+            self.registers.h += p;
+        }
+    }
+
+    fn move_down(&mut self, p: Dimension) {
+        if let Some(ref pixels) = self.pixels {
+            let space = self.font_space();
+            let registers = &mut self.registers;
+            registers.vv = pixels.move_down(registers.vv, registers.v, p, space);
+            registers.v += p;
+            registers.vv = pixels.correct_drift(registers.vv, registers.v);
+        } else {
+            self.registers.v += p;
+        }
+    }
+
+    /// The threshold between kerns and spaces is a sixth of the size of the font
+    fn font_space(&self) -> Dimension {
+        self.f
+            .and_then(|f| self.fonts.get(&f))
+            .map_or(Dimension::ZERO, |font| Dimension::from(font.s / 6))
+    }
+
+    fn handle_xxx(&mut self, x: &[u8], device: &mut dyn DviDevice) {
+        if self.in_page {
+            device.special(x, self.registers.h, self.registers.v);
+        }
+    }
+
+    fn handle_bop(&mut self, counts: [i32; 10], device: &mut dyn DviDevice) {
+        self.registers = Default::default();
+        self.stack.clear();
+        self.f = Option::None;
+        self.in_page = true;
+        device.begin_page(&counts);
     }
 
-    // fn get_char_info(&self, c: i32) {
-    //     let tfm = &self.fonts[self.f as usize];
-    //     let char_info = &tfm.char_info_table;
-    // }
+    fn handle_eop(&mut self, device: &mut dyn DviDevice) -> DviousResult<()> {
+        if !self.stack.is_empty() {
+            return Err(DviousError::DviInterpretError(format!(
+                "Stack not empty at end of page: [{}] frames left",
+                self.stack.len()
+            )));
+        }
+        if !self.in_page {
+            return Err(DviousError::DviInterpretError(
+                "End of page without beginning of page".to_string(),
+            ));
+        }
+        self.in_page = false;
+        device.end_page();
+        Ok(())
+    }
 
-    // fn get_character_width(&self, c: i32) -> Fixword {
-    //     let char_info = self.get_char_info(c);
-    //     0.
-    // }
+    fn handle_pop(&mut self) -> DviousResult<()> {
+        match self.stack.pop() {
+            Some(registers) => {
+                self.registers = registers;
+                Ok(())
+            }
+            None => Err(DviousError::DviInterpretError(
+                "Pop on empty stack".to_string(),
+            )),
+        }
+    }
+
+    fn handle_fnt(&mut self, k: i32) -> DviousResult<()> {
+        if !self.fonts.contains_key(&k) {
+            return Err(DviousError::DviInterpretError(format!(
+                "Font [{}] selected, but never defined",
+                k
+            )));
+        }
+        self.f = Some(k);
+        Ok(())
+    }
+
+    fn handle_fnt_def(&mut self, k: i32, c: u32, s: u32, d: u32, n: &[u8]) -> DviousResult<()> {
+        // Definitions in the postamble repeat the ones in the pages
+        if self.fonts.contains_key(&k) {
+            return Ok(());
+        }
+
+        let name = String::from_utf8_lossy(n);
+        let tfm = self.resolver.resolve(&name, c, s, d)?;
+        for warning in self.verifier.verify(&name, c, d, &tfm) {
+            if self.strict {
+                return Err(DviousError::FontMismatchError(warning));
+            }
+            self.warnings.push(warning);
+        }
+        let font = FontInformation {
+            name: name.into_owned(),
+            s: s as i32,
+            d: d as i32,
+            tfm,
+        };
+        self.fonts.insert(k, font);
+        Ok(())
+    }
+
+    fn get_character_dimensions(&self, c: i32) -> DviousResult<CharMetrics> {
+        let font = match self.f.and_then(|f| self.fonts.get(&f)) {
+            Some(font) => font,
+            None => {
+                return Err(DviousError::DviInterpretError(format!(
+                    "Character [{}] typeset without selecting a font",
+                    c
+                )))
+            }
+        };
+
+        let exists = matches!(
+            font.tfm.char_info_table.get(&(c as u8)),
+            Some(info) if (0..256).contains(&c) && info.width_index != 0
+        );
+        if !exists {
+            return Err(DviousError::DviInterpretError(format!(
+                "Character [{}] does not exist in font [{}]",
+                c,
+                self.f.unwrap_or_default()
+            )));
+        }
+
+        let at_size = Dimension::from(font.s);
+        font.tfm.char_metrics(c as u8, at_size).ok_or_else(|| {
+            DviousError::TfmParseError(format!(
+                "Dimensions of character [{}] invalid at size [{}]",
+                c, font.s
+            ))
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
+    use crate::dvi::device::*;
     use crate::dvi::interpreter::*;
     use crate::dvi::opcodes::*;
-    
+    use crate::dvi::page::*;
+    use crate::dvi::pixels::PixelRounding;
+    use crate::fonts::resolver::MemoryResolver;
+    use crate::fonts::tfm::builder::TfmBuilder;
+    use crate::util::dimension::Dimension;
+    use crate::util::num::FixWord;
 
     #[test]
     fn test_execute_set() {
-        let instructions = vec![OpCode::Set { c: 0x42 }];
+        let instructions = vec![OpCode::Fnt { k: 1 }, OpCode::Set { c: 0x42 }];
         let mut interpreter = interpreter_fixture();
 
         interpreter.execute(instructions).unwrap();
 
-        // assert_eq!(interpreter.registers.h, 1);
+        assert_eq!(interpreter.registers.h, sp(655360));
     }
 
-    fn interpreter_fixture() -> Interpreter {
-        // Create font
-
-        let font_header = TfmMetricHeader {
-            checksum: 0xDEAD,
-            design_size: 10.0,
-            encoding: Option::None,
-            font_identifier: Option::None,
-            face: Option::None,
-            misc: Vec::new(),
-        };
+    #[test]
+    fn test_execute_put_keeps_position() {
+        let instructions = vec![
+            OpCode::Fnt { k: 1 },
+            OpCode::Put { c: 0x42 },
+            OpCode::PutRule { a: 10, b: 20 },
+        ];
+        let mut interpreter = interpreter_fixture();
 
-        let char_info = TfmCharInfo {
-            character: 0x42,
-            width_index: 1,
-            height_index: 1,
-            depth_index: 1,
-            italic_index: 1,
-            tag: TfmCharInfoTag::None,
-        };
+        interpreter.execute(instructions).unwrap();
+
+        assert_eq!(interpreter.registers.h, sp(0));
+    }
+
+    #[test]
+    fn test_execute_set_rule() {
+        let instructions = vec![OpCode::SetRule { a: 10, b: 20 }];
+        let mut interpreter = interpreter_fixture();
+
+        interpreter.execute(instructions).unwrap();
+
+        assert_eq!(interpreter.registers.h, sp(20));
+        assert_eq!(interpreter.registers.v, sp(0));
+    }
+
+    #[test]
+    fn test_execute_registers() {
+        let instructions = vec![
+            OpCode::W { b: 1 },
+            OpCode::W0,
+            OpCode::X { b: 10 },
+            OpCode::X0,
+            OpCode::Right { b: -100 },
+            OpCode::Y { a: 2 },
+            OpCode::Y0,
+            OpCode::Z { a: 20 },
+            OpCode::Z0,
+            OpCode::Down { a: 200 },
+        ];
+        let mut interpreter = interpreter_fixture();
+
+        interpreter.execute(instructions).unwrap();
+
+        let registers = &interpreter.registers;
+        assert_eq!((registers.h, registers.v), (sp(-78), sp(244)));
+        assert_eq!((registers.w, registers.x), (sp(1), sp(10)));
+        assert_eq!((registers.y, registers.z), (sp(2), sp(20)));
+    }
+
+    #[test]
+    fn test_execute_push_pop() {
+        let instructions = vec![
+            OpCode::Right { b: 5 },
+            OpCode::Push,
+            OpCode::W { b: 7 },
+            OpCode::Down { a: 3 },
+            OpCode::Pop,
+        ];
+        let mut interpreter = interpreter_fixture();
+
+        interpreter.execute(instructions).unwrap();
+
+        let registers = &interpreter.registers;
+        assert_eq!(
+            (registers.h, registers.v, registers.w),
+            (sp(5), sp(0), sp(0))
+        );
+        assert!(interpreter.stack.is_empty());
+    }
+
+    #[test]
+    fn test_execute_pop_underflow() {
+        let mut interpreter = interpreter_fixture();
+
+        let result = interpreter.execute(vec![OpCode::Pop]);
+
+        assert!(matches!(result, Err(DviousError::DviInterpretError(_))));
+    }
 
-        let mut char_infos = HashMap::new();
-        char_infos.insert(0x42, char_info);
-
-        let tfm = TexFontMetric {
-            header: font_header,
-            char_info_table: char_infos,
-            width_table: vec![0.0, 1.0],
-            heigth_table: vec![0.0, 2.0],
-            depth_table: vec![0.0, 3.0],
-            italic_table: vec![0.0, 4.0],
-            lig_kern_table: vec![],
-            kern_table: vec![0.0, 5.0],
-            extension_table: vec![],
-            param_table: vec![0.0, 6.0],
+    #[test]
+    fn test_execute_eop_with_pushed_frames() {
+        let mut interpreter = interpreter_fixture();
+
+        let result = interpreter.execute(vec![OpCode::Push, OpCode::Eop]);
+
+        assert!(matches!(result, Err(DviousError::DviInterpretError(_))));
+    }
+
+    #[test]
+    fn test_execute_undefined_font() {
+        let mut interpreter = interpreter_fixture();
+
+        let result = interpreter.execute(vec![OpCode::Fnt { k: 2 }]);
+
+        assert!(matches!(result, Err(DviousError::DviInterpretError(_))));
+    }
+
+    #[test]
+    fn test_execute_set_without_font() {
+        let mut interpreter = interpreter_fixture();
+
+        let result = interpreter.execute(vec![OpCode::Set { c: 0x42 }]);
+
+        assert!(matches!(result, Err(DviousError::DviInterpretError(_))));
+    }
+
+    #[test]
+    fn test_execute_missing_character() {
+        let mut interpreter = interpreter_fixture();
+
+        let result = interpreter.execute(vec![OpCode::Fnt { k: 1 }, OpCode::Set { c: 0x43 }]);
+
+        assert!(matches!(result, Err(DviousError::DviInterpretError(_))));
+    }
+
+    #[test]
+    fn test_execute_bop_resets() {
+        let instructions = vec![
+            OpCode::Fnt { k: 1 },
+            OpCode::W { b: 1 },
+            OpCode::Down { a: 2 },
+            OpCode::Push,
+            bop(),
+        ];
+        let mut interpreter = interpreter_fixture();
+
+        interpreter.execute(instructions).unwrap();
+
+        let registers = &interpreter.registers;
+        assert_eq!(
+            (registers.h, registers.v, registers.w),
+            (sp(0), sp(0), sp(0))
+        );
+        assert!(interpreter.stack.is_empty());
+        assert_eq!(interpreter.f, None);
+    }
+
+    #[test]
+    fn test_execute_fnt_def_loads_font() {
+        let mut resolver = MemoryResolver::new();
+        resolver.insert("cmr10", font_with_b());
+        let mut interpreter = Interpreter::with_resolver(resolver);
+        let instructions = vec![
+            OpCode::FntDef {
+                k: 3,
+                c: 0xDEAD,
+                s: 2 * 655360,
+                d: 655360,
+                a: 0,
+                l: 5,
+                n: b"cmr10".to_vec(),
+            },
+            OpCode::Fnt { k: 3 },
+            OpCode::Set { c: 0x42 },
+        ];
+
+        interpreter.execute(instructions).unwrap();
+
+        assert_eq!(interpreter.registers.h, sp(2 * 655360));
+    }
+
+    #[test]
+    fn test_execute_produces_page() {
+        let instructions = vec![
+            bop(),
+            OpCode::Fnt { k: 1 },
+            OpCode::Down { a: 100 },
+            OpCode::Set { c: 0x42 },
+            OpCode::Xxx {
+                k: 5,
+                x: b"color".to_vec(),
+            },
+            OpCode::SetRule { a: 10, b: 20 },
+            OpCode::PutRule { a: 0, b: 20 },
+            OpCode::Put { c: 0x42 },
+            OpCode::Eop,
+        ];
+        let mut interpreter = interpreter_fixture();
+
+        let pages = interpreter.execute(instructions).unwrap();
+
+        let glyph = Glyph {
+            font: 1,
+            c: 0x42,
+            h: sp(0),
+            v: sp(100),
+            width: sp(655360),
+            height: sp(2 * 655360),
+            depth: sp(3 * 655360),
+            pixels: None,
+        };
+        let expected = Page {
+            counts: [1, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            glyphs: vec![
+                glyph.clone(),
+                Glyph {
+                    h: sp(655360 + 20),
+                    ..glyph
+                },
+            ],
+            rules: vec![Rule {
+                h: sp(655360),
+                v: sp(100),
+                width: sp(20),
+                height: sp(10),
+                pixels: None,
+            }],
+            specials: vec![Special {
+                h: sp(655360),
+                v: sp(100),
+                x: b"color".to_vec(),
+            }],
         };
+        assert_eq!(pages, vec![expected]);
+    }
+
+    #[test]
+    fn test_execute_pages_are_returned_once() {
+        let mut interpreter = interpreter_fixture();
+
+        let first = interpreter
+            .execute(vec![bop(), OpCode::Eop, bop()])
+            .unwrap();
+        let second = interpreter.execute(vec![OpCode::Eop]).unwrap();
+
+        assert_eq!(first.len(), 1);
+        assert_eq!(second.len(), 1);
+    }
 
+    #[test]
+    fn test_execute_eop_without_bop() {
+        let mut interpreter = interpreter_fixture();
+
+        let result = interpreter.execute(vec![OpCode::Eop]);
+
+        assert!(matches!(result, Err(DviousError::DviInterpretError(_))));
+    }
+
+    #[test]
+    fn test_execute_with_device() {
+        #[derive(Default)]
+        struct Recorder {
+            calls: Vec<String>,
+        }
+
+        impl DviDevice for Recorder {
+            fn begin_page(&mut self, counts: &[i32; 10]) {
+                self.calls.push(format!("begin {}", counts[0]));
+            }
+
+            fn glyph(&mut self, glyph: &Glyph) {
+                self.calls.push(format!(
+                    "glyph {} {} {}",
+                    glyph.c,
+                    glyph.h.raw(),
+                    glyph.v.raw()
+                ));
+            }
+
+            fn rule(&mut self, rule: &Rule) {
+                self.calls.push(format!(
+                    "rule {} {} {} {}",
+                    rule.h.raw(),
+                    rule.v.raw(),
+                    rule.width.raw(),
+                    rule.height.raw()
+                ));
+            }
+
+            fn end_page(&mut self) {
+                self.calls.push("end".to_string());
+            }
+        }
+
+        let instructions = vec![
+            bop(),
+            OpCode::Fnt { k: 1 },
+            OpCode::Set { c: 0x42 },
+            OpCode::Down { a: 5 },
+            OpCode::PutRule { a: 10, b: 20 },
+            OpCode::Xxx {
+                k: 1,
+                x: b"x".to_vec(),
+            },
+            OpCode::Eop,
+        ];
+        let mut interpreter = interpreter_fixture();
+        let mut recorder = Recorder::default();
+
+        interpreter
+            .execute_with_device(instructions, &mut recorder)
+            .unwrap();
+
+        assert_eq!(
+            recorder.calls,
+            vec!["begin 1", "glyph 66 0 0", "rule 655360 5 20 10", "end"]
+        );
+    }
+
+    #[test]
+    fn test_execute_fnt_def_warns_about_mismatch() {
+        let mut resolver = MemoryResolver::new();
+        resolver.insert("cmr10", font_with_b());
+        let mut interpreter = Interpreter::with_resolver(resolver);
+
+        interpreter.execute(vec![fnt_def(0xBEEF)]).unwrap();
+
+        assert_eq!(
+            interpreter.warnings(),
+            &[FontWarning::ChecksumMismatch {
+                font: "cmr10".to_string(),
+                dvi: 0xBEEF,
+                tfm: 0xDEAD,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_execute_fnt_def_strict() {
+        let mut resolver = MemoryResolver::new();
+        resolver.insert("cmr10", font_with_b());
+        let mut interpreter = Interpreter::with_resolver(resolver);
+        interpreter.set_strict(true);
+
+        let result = interpreter.execute(vec![fnt_def(0xBEEF)]);
+
+        assert!(matches!(
+            result,
+            Err(DviousError::FontMismatchError(
+                FontWarning::ChecksumMismatch { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_execute_with_pixel_rounding() {
+        let instructions = vec![
+            bop(),
+            OpCode::Fnt { k: 1 },
+            OpCode::Down { a: 65536 / 2 },
+            OpCode::Set { c: 0x42 },
+            OpCode::Right { b: 65536 / 3 },
+            OpCode::Push,
+            OpCode::SetRule {
+                a: 65536 / 4,
+                b: 65536,
+            },
+            OpCode::Pop,
+            OpCode::Put { c: 0x42 },
+            OpCode::Eop,
+        ];
+        let mut interpreter = interpreter_fixture();
+        interpreter.set_pixel_rounding(PixelRounding::new(72.27));
+
+        let pages = interpreter.execute(instructions).unwrap();
+
+        let page = &pages[0];
+        let pixels: Vec<_> = page.glyphs.iter().map(|glyph| glyph.pixels).collect();
+        // The kern of a third of a point is rounded on its own
+        assert_eq!(
+            pixels,
+            vec![
+                Some(PixelPosition { hh: 0, vv: 1 }),
+                Some(PixelPosition { hh: 10, vv: 1 })
+            ]
+        );
+        assert_eq!(
+            page.rules[0].pixels,
+            Some(PixelRule {
+                hh: 10,
+                vv: 1,
+                width: 1,
+                height: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn test_execute_corrects_drift() {
+        let mut instructions = vec![bop(), OpCode::Fnt { k: 1 }];
+        instructions.extend(vec![OpCode::Right { b: 65536 / 2 }; 8]);
+        let mut interpreter = interpreter_fixture();
+        interpreter.set_pixel_rounding(PixelRounding::new(72.27));
+
+        interpreter.execute(instructions).unwrap();
+
+        // Rounding every half point on its own would drift to 8 pixels instead of 4
+        assert_eq!(interpreter.registers.h, sp(4 * 65536));
+        assert_eq!(interpreter.registers.hh, 6);
+    }
+
+    fn fnt_def(c: u32) -> OpCode {
+        OpCode::FntDef {
+            k: 3,
+            c,
+            s: 655360,
+            d: 655360,
+            a: 0,
+            l: 5,
+            n: b"cmr10".to_vec(),
+        }
+    }
+
+    fn sp(raw: i64) -> Dimension {
+        Dimension::new(raw)
+    }
+
+    fn bop() -> OpCode {
+        OpCode::Bop {
+            c0: 1,
+            c1: 0,
+            c2: 0,
+            c3: 0,
+            c4: 0,
+            c5: 0,
+            c6: 0,
+            c7: 0,
+            c8: 0,
+            c9: 0,
+            p: -1,
+        }
+    }
+
+    fn font_with_b() -> TexFontMetric {
+        TfmBuilder::new()
+            .checksum(0xDEAD)
+            .char(
+                0x42,
+                FixWord::ONE,
+                FixWord::from_f64(2.0),
+                FixWord::from_f64(3.0),
+            )
+            .italic(0x42, FixWord::from_f64(4.0))
+            .kerns(&[FixWord::ZERO, FixWord::from_f64(5.0)])
+            .params(&[FixWord::ZERO, FixWord::from_f64(6.0)])
+            .build()
+    }
+
+    fn interpreter_fixture() -> Interpreter {
         let font_information = FontInformation {
-            tfm: tfm,
+            name: "cmr10".to_string(),
+            tfm: font_with_b(),
             s: 655360,
             d: 655360,
         };
-        // Build interpreter
-
 
-        let mut interpreter = Interpreter::new();
-        interpreter.fonts.push(font_information);
+        let mut interpreter = Interpreter::with_resolver(MemoryResolver::new());
+        interpreter.fonts.insert(1, font_information);
         interpreter
     }
-
 }
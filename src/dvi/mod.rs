@@ -1,3 +1,14 @@
+pub mod asm;
+pub mod bbox;
+pub mod device;
 pub mod disassembler;
+pub mod dvitype;
+pub mod encoder;
+pub mod file;
 pub mod interpreter;
 pub mod opcodes;
+pub mod page;
+pub mod pixels;
+pub mod text;
+pub mod units;
+pub mod writer;
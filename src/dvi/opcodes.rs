@@ -1,7 +1,9 @@
 use std::fmt;
 
-#[derive(Debug, PartialEq)]
-#[cfg_attr(rustfmt, rustfmt_skip)]
+use crate::dvi::encoder::smallest_opcode_byte;
+
+#[derive(Clone, Debug, PartialEq)]
+#[rustfmt::skip]
 pub enum OpCode {
     Set { c: i32 },
     SetRule { a: i32, b: i32 },
@@ -30,8 +32,164 @@ pub enum OpCode {
     PostPost { q: Option<usize>, i: u8 }
 }
 
+/// A decoded opcode together with where it was found: the byte offset of the
+/// command, the number of bytes it spans (including any trailing padding after
+/// `post_post`) and the original opcode byte.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpannedOpCode {
+    pub offset: usize,
+    pub length: usize,
+    pub byte: u8,
+    pub opcode: OpCode,
+}
+
+impl OpCode {
+    /// The command like dvitype names it, e.g. `set_char_72` or `down4 41484288`.
+    /// `byte` is the opcode byte, which selects the width in names like `down4`.
+    /// Pointers of `bop`, `post` and `post_post` are left out.
+    pub fn mnemonic(&self, byte: u8) -> String {
+        let n = |first: u8| byte.wrapping_sub(first) + 1;
+        match self {
+            OpCode::Set { c } if byte < 128 => format!("set_char_{}", c),
+            OpCode::Set { c } => format!("set{} {}", n(128), c),
+            OpCode::SetRule { a, b } => format!("setrule height {}, width {}", a, b),
+            OpCode::Put { c } => format!("put{} {}", n(133), c),
+            OpCode::PutRule { a, b } => format!("putrule height {}, width {}", a, b),
+            OpCode::Nop => "nop".to_string(),
+            OpCode::Bop {
+                c0,
+                c1,
+                c2,
+                c3,
+                c4,
+                c5,
+                c6,
+                c7,
+                c8,
+                c9,
+                ..
+            } => format!(
+                "bop {} {} {} {} {} {} {} {} {} {}",
+                c0, c1, c2, c3, c4, c5, c6, c7, c8, c9
+            ),
+            OpCode::Eop => "eop".to_string(),
+            OpCode::Push => "push".to_string(),
+            OpCode::Pop => "pop".to_string(),
+            OpCode::Right { b } => format!("right{} {}", n(143), b),
+            OpCode::W0 => "w0".to_string(),
+            OpCode::W { b } => format!("w{} {}", n(148), b),
+            OpCode::X0 => "x0".to_string(),
+            OpCode::X { b } => format!("x{} {}", n(153), b),
+            OpCode::Down { a } => format!("down{} {}", n(157), a),
+            OpCode::Y0 => "y0".to_string(),
+            OpCode::Y { a } => format!("y{} {}", n(162), a),
+            OpCode::Z0 => "z0".to_string(),
+            OpCode::Z { a } => format!("z{} {}", n(167), a),
+            OpCode::Fnt { k } if (171..=234).contains(&byte) => format!("fntnum{}", k),
+            OpCode::Fnt { k } => format!("fnt{} {}", n(235), k),
+            OpCode::Xxx { x, .. } => format!("xxx '{}'", String::from_utf8_lossy(x)),
+            OpCode::FntDef { k, n: name, .. } => {
+                format!("fntdef{} {}: {}", n(243), k, String::from_utf8_lossy(name))
+            }
+            OpCode::Pre {
+                i,
+                num,
+                den,
+                mag,
+                x,
+                ..
+            } => format!(
+                "pre {} {}/{} {} '{}'",
+                i,
+                num,
+                den,
+                mag,
+                String::from_utf8_lossy(x)
+            ),
+            OpCode::Post {
+                num,
+                den,
+                mag,
+                l,
+                u,
+                s,
+                t,
+                ..
+            } => format!(
+                "post {}/{} {} maxv={}, maxh={}, maxstackdepth={}, totalpages={}",
+                num, den, mag, l, u, s, t
+            ),
+            OpCode::PostPost { i, .. } => format!("post_post {}", i),
+        }
+    }
+}
+
+/// Formats the opcode with `OpCode::mnemonic`, using the smallest encoding
 impl fmt::Display for OpCode {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt::Debug::fmt(self, f)
+        f.write_str(&self.mnemonic(smallest_opcode_byte(self)))
+    }
+}
+
+/// Formats the opcode like a line of dvitype, e.g. `87: down4 41484288`
+impl fmt::Display for SpannedOpCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.offset, self.opcode.mnemonic(self.byte))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dvi::opcodes::*;
+
+    #[test]
+    fn test_display_uses_smallest_encoding() {
+        assert_eq!(OpCode::Set { c: 72 }.to_string(), "set_char_72");
+        assert_eq!(OpCode::Set { c: 200 }.to_string(), "set1 200");
+        assert_eq!(OpCode::Down { a: 1_966_080 }.to_string(), "down3 1966080");
+        assert_eq!(OpCode::Right { b: -1 }.to_string(), "right1 -1");
+        assert_eq!(OpCode::Fnt { k: 7 }.to_string(), "fntnum7");
+        assert_eq!(OpCode::Fnt { k: 300 }.to_string(), "fnt2 300");
+        assert_eq!(OpCode::W0.to_string(), "w0");
+        assert_eq!(
+            OpCode::PutRule { a: 1, b: 2 }.to_string(),
+            "putrule height 1, width 2"
+        );
+        assert_eq!(
+            OpCode::PostPost { q: None, i: 2 }.to_string(),
+            "post_post 2"
+        );
+    }
+
+    #[test]
+    fn test_display_spanned_uses_original_byte() {
+        let spanned = SpannedOpCode {
+            offset: 42,
+            length: 5,
+            byte: 160,
+            opcode: OpCode::Down { a: 1 },
+        };
+
+        assert_eq!(spanned.to_string(), "42: down4 1");
+    }
+
+    #[test]
+    fn test_display_text() {
+        let xxx = OpCode::Xxx {
+            k: 5,
+            x: b"color".to_vec(),
+        };
+        let fnt_def = OpCode::FntDef {
+            k: 7,
+            c: 0,
+            s: 655_360,
+            d: 655_360,
+            a: 0,
+            l: 5,
+            n: b"cmr10".to_vec(),
+        };
+
+        assert_eq!(xxx.to_string(), "xxx 'color'");
+        assert_eq!(fnt_def.to_string(), "fntdef1 7: cmr10");
     }
 }
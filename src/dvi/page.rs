@@ -0,0 +1,62 @@
+use crate::util::dimension::Dimension;
+
+/// The contents of a page between `bop` and `eop`. All positions and dimensions are
+/// in DVI units, with `v` growing downwards from the top left corner of the page.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Page {
+    /// The ten counters `\count0` to `\count9` given in the `bop`
+    pub counts: [i32; 10],
+    pub glyphs: Vec<Glyph>,
+    pub rules: Vec<Rule>,
+    pub specials: Vec<Special>,
+}
+
+/// A character whose reference point is at `(h, v)`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Glyph {
+    /// The font number of the `fnt_def`
+    pub font: i32,
+    pub c: i32,
+    pub h: Dimension,
+    pub v: Dimension,
+    pub width: Dimension,
+    pub height: Dimension,
+    pub depth: Dimension,
+    /// The position in pixels if the interpreter rounds to pixels
+    pub pixels: Option<PixelPosition>,
+}
+
+/// A solid black rectangle whose bottom left corner is at `(h, v)`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rule {
+    pub h: Dimension,
+    pub v: Dimension,
+    pub width: Dimension,
+    pub height: Dimension,
+    /// The position and extent in pixels if the interpreter rounds to pixels
+    pub pixels: Option<PixelRule>,
+}
+
+/// A position in device pixels, see `PixelRounding`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PixelPosition {
+    pub hh: i64,
+    pub vv: i64,
+}
+
+/// A rule in device pixels, see `PixelRounding`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PixelRule {
+    pub hh: i64,
+    pub vv: i64,
+    pub width: i64,
+    pub height: i64,
+}
+
+/// The bytes of an `xxx` command and the position where it occurred
+#[derive(Clone, Debug, PartialEq)]
+pub struct Special {
+    pub h: Dimension,
+    pub v: Dimension,
+    pub x: Vec<u8>,
+}
@@ -0,0 +1,168 @@
+use crate::dvi::units::DviUnits;
+use crate::util::dimension::Dimension;
+
+/// How far rounded pixel positions may drift from the true positions, like dvitype
+pub const DEFAULT_MAX_DRIFT: i64 = 2;
+
+/// The device coordinates of dvitype, xdvi and dvips: positions are rounded to
+/// pixels at a resolution, small motions like kerns are rounded on their own, large
+/// ones like spaces reposition from the true position, and the accumulated error is
+/// kept within `max_drift` pixels.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PixelRounding {
+    resolution: f64,
+    max_drift: i64,
+    conv: f64,
+}
+
+impl PixelRounding {
+    /// Creates the rounding for `resolution` pixels per inch, assuming the units and
+    /// magnification of TeX until `set_units` is called
+    pub fn new(resolution: f64) -> PixelRounding {
+        let mut rounding = PixelRounding {
+            resolution,
+            max_drift: DEFAULT_MAX_DRIFT,
+            conv: 0.0,
+        };
+        rounding.set_units(DviUnits::default());
+        rounding
+    }
+
+    /// Sets `num`, `den` and `mag` of the preamble
+    pub fn set_units(&mut self, units: DviUnits) {
+        self.conv = units.pixels_per_dvi_unit(self.resolution);
+    }
+
+    pub fn set_max_drift(&mut self, max_drift: i64) {
+        self.max_drift = max_drift;
+    }
+
+    pub fn resolution(&self) -> f64 {
+        self.resolution
+    }
+
+    /// Pixels per DVI unit
+    pub fn conv(&self) -> f64 {
+        self.conv
+    }
+
+    /// Converts DVI units to the nearest number of pixels
+    pub fn pixel_round(&self, x: Dimension) -> i64 {
+        (self.conv * x.raw() as f64).round() as i64
+    }
+
+    /// Converts the extent of a rule to pixels, rounding up so that thin rules
+    /// remain visible
+    pub fn rule_pixels(&self, x: Dimension) -> i64 {
+        let exact = self.conv * x.raw() as f64;
+        let n = exact.trunc() as i64;
+        if (n as f64) < exact {
+            n + 1
+        } else {
+            n
+        }
+    }
+
+    /// Whether a horizontal motion by `p` is at least a word space or at most four
+    /// negative word spaces, which dvitype takes as a space between words
+    pub fn is_word_space(&self, p: Dimension, font_space: Dimension) -> bool {
+        p >= font_space || p <= font_space * -4
+    }
+
+    /// The pixel position after a horizontal motion by `p` from `h`, which was at
+    /// pixel `hh`. Word spaces reposition from the true position.
+    pub fn move_right(&self, hh: i64, h: Dimension, p: Dimension, font_space: Dimension) -> i64 {
+        if self.is_word_space(p, font_space) {
+            self.pixel_round(h + p)
+        } else {
+            hh + self.pixel_round(p)
+        }
+    }
+
+    /// Like `move_right`, with a threshold of five word spaces in either direction
+    pub fn move_down(&self, vv: i64, v: Dimension, p: Dimension, font_space: Dimension) -> i64 {
+        if p.abs() >= font_space * 5 {
+            self.pixel_round(v + p)
+        } else {
+            vv + self.pixel_round(p)
+        }
+    }
+
+    /// Pulls the pixel position `pixels` back to within `max_drift` of the rounded
+    /// true `position`
+    pub fn correct_drift(&self, pixels: i64, position: Dimension) -> i64 {
+        let rounded = self.pixel_round(position);
+        if rounded - pixels > self.max_drift {
+            rounded - self.max_drift
+        } else if pixels - rounded > self.max_drift {
+            rounded + self.max_drift
+        } else {
+            pixels
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dvi::pixels::*;
+    use crate::dvi::units::*;
+
+    #[test]
+    fn test_conv() {
+        let rounding = PixelRounding::new(72.27);
+
+        // One point is 65536 DVI units
+        assert!((rounding.conv() * 65536.0 - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_conv_with_magnification() {
+        let mut rounding = PixelRounding::new(72.27);
+
+        rounding.set_units(DviUnits::new(TEX_NUMERATOR, TEX_DENOMINATOR, 2000));
+
+        assert_eq!(rounding.pixel_round(Dimension::new(65536)), 2);
+    }
+
+    #[test]
+    fn test_rule_pixels_round_up() {
+        let rounding = PixelRounding::new(72.27);
+
+        assert_eq!(rounding.pixel_round(Dimension::new(65536 / 4)), 0);
+        assert_eq!(rounding.rule_pixels(Dimension::new(65536 / 4)), 1);
+        assert_eq!(rounding.rule_pixels(Dimension::new(65536)), 1);
+        assert_eq!(rounding.rule_pixels(Dimension::new(0)), 0);
+    }
+
+    #[test]
+    fn test_move_right_thresholds() {
+        let rounding = PixelRounding::new(72.27);
+        let space = Dimension::new(65536);
+        let h = space * 9;
+
+        // Kerns accumulate the rounded motion
+        assert_eq!(rounding.move_right(10, h, space / 2, space), 11);
+        // Spaces reposition from the true position
+        assert_eq!(rounding.move_right(10, h, space, space), 10);
+        assert_eq!(rounding.move_right(10, h, space * -4, space), 5);
+    }
+
+    #[test]
+    fn test_move_down_thresholds() {
+        let rounding = PixelRounding::new(72.27);
+        let space = Dimension::new(65536);
+        let v = space * 9;
+
+        assert_eq!(rounding.move_down(10, v, space * 4, space), 14);
+        assert_eq!(rounding.move_down(10, v, space * -5, space), 4);
+    }
+
+    #[test]
+    fn test_correct_drift() {
+        let rounding = PixelRounding::new(72.27);
+
+        assert_eq!(rounding.correct_drift(7, Dimension::new(65536 * 10)), 8);
+        assert_eq!(rounding.correct_drift(13, Dimension::new(65536 * 10)), 12);
+        assert_eq!(rounding.correct_drift(11, Dimension::new(65536 * 10)), 11);
+    }
+}
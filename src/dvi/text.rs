@@ -0,0 +1,245 @@
+use std::char::REPLACEMENT_CHARACTER;
+use std::collections::HashMap;
+
+use crate::dvi::interpreter::{FontInformation, Interpreter};
+use crate::dvi::page::Page;
+use crate::fonts::encoding::Encoding;
+use crate::util::dimension::Dimension;
+
+/// Gaps wider than this fraction of the space of a font separate words. TeX shrinks
+/// the spaces of text fonts by at most a third, and kerns are much smaller.
+pub const SPACE_FRACTION: f64 = 0.5;
+
+/// Reconstructs text from the glyphs of pages. Character codes are mapped to Unicode
+/// with the encoding named in the TFM file of their font, unless another one was set
+/// for the font. The codes of fonts without a known encoding are taken as ASCII.
+#[derive(Debug, Default)]
+pub struct TextExtractor {
+    encodings: HashMap<String, Encoding>,
+}
+
+impl TextExtractor {
+    pub fn new() -> TextExtractor {
+        Default::default()
+    }
+
+    /// Uses `encoding` for the font `name`, e.g. one read from the `.enc` file of a
+    /// font map
+    pub fn set_encoding<S: Into<String>>(&mut self, name: S, encoding: Encoding) {
+        self.encodings.insert(name.into(), encoding);
+    }
+
+    /// Reconstructs the text of a page from its glyphs in the order they were
+    /// typeset, similar to dvi2tty. Gaps wider than `SPACE_FRACTION` of the space of
+    /// the font become spaces, and every line ends where the baseline moves by more
+    /// than half the size of the font, so that superscripts and subscripts stay on
+    /// their line. Codes that are not in the encoding are replaced with U+FFFD.
+    pub fn page_text(&self, page: &Page, interpreter: &Interpreter) -> String {
+        let mut encodings: HashMap<i32, Option<Encoding>> = HashMap::new();
+        let mut text = String::new();
+        // The baseline of the line and where the previous glyph ended
+        let mut line: Option<(Dimension, Dimension)> = None;
+        for glyph in &page.glyphs {
+            let font = interpreter.font(glyph.font);
+            let (word_gap, baseline_skip) = match font {
+                Some(font) => (word_gap(font), Dimension::from(font.s / 2)),
+                None => (Dimension::ZERO, Dimension::ZERO),
+            };
+
+            let baseline = match line {
+                Some((baseline, _)) if (glyph.v - baseline).abs() > baseline_skip => {
+                    text.push('\n');
+                    glyph.v
+                }
+                Some((baseline, end)) => {
+                    if glyph.h - end > word_gap {
+                        text.push(' ');
+                    }
+                    baseline
+                }
+                None => glyph.v,
+            };
+
+            let encoding = encodings
+                .entry(glyph.font)
+                .or_insert_with(|| font.and_then(|font| self.encoding(font)));
+            match encoding {
+                Some(encoding) => match encoding.to_unicode(glyph.c) {
+                    Some(unicode) => text.push_str(unicode),
+                    None => text.push(REPLACEMENT_CHARACTER),
+                },
+                None => text.push(ascii(glyph.c)),
+            }
+            line = Some((baseline, glyph.h + glyph.width));
+        }
+
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        text
+    }
+
+    fn encoding(&self, font: &FontInformation) -> Option<Encoding> {
+        match self.encodings.get(&font.name) {
+            Some(encoding) => Some(encoding.clone()),
+            None => Encoding::for_tfm(&font.tfm),
+        }
+    }
+}
+
+/// The text of a page with the encodings named in the TFM files, see `TextExtractor`
+pub fn page_text(page: &Page, interpreter: &Interpreter) -> String {
+    TextExtractor::new().page_text(page, interpreter)
+}
+
+/// The smallest gap between words, from the `space` parameter of the font or from a
+/// third of its size if there is none, like in math fonts
+fn word_gap(font: &FontInformation) -> Dimension {
+    let space = font
+        .tfm
+        .space()
+        .and_then(|space| space.scale(font.s))
+        .filter(|&space| space > 0)
+        .unwrap_or(font.s / 3);
+    Dimension::new((f64::from(space) * SPACE_FRACTION) as i64)
+}
+
+fn ascii(c: i32) -> char {
+    match c {
+        32..=126 => c as u8 as char,
+        _ => REPLACEMENT_CHARACTER,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dvi::interpreter::Interpreter;
+    use crate::dvi::opcodes::*;
+    use crate::dvi::text::*;
+    use crate::fonts::resolver::MemoryResolver;
+    use crate::fonts::tfm::builder::TfmBuilder;
+    use crate::util::num::FixWord;
+
+    #[test]
+    fn test_page_text_words() {
+        let text = text_of(vec![
+            OpCode::Set { c: 0x48 },
+            // A kern stays within the word
+            OpCode::Right { b: 10000 },
+            OpCode::Set { c: 0x69 },
+            // An interword space, shrunk by a third
+            OpCode::Right { b: 2 * 218453 / 3 },
+            OpCode::Set { c: 0x48 },
+        ]);
+
+        assert_eq!(text, "Hi H\n");
+    }
+
+    #[test]
+    fn test_page_text_lines() {
+        let text = text_of(vec![
+            OpCode::Push,
+            OpCode::Set { c: 0x48 },
+            // A superscript stays on the line
+            OpCode::Down { a: -200000 },
+            OpCode::Set { c: 0x69 },
+            OpCode::Pop,
+            OpCode::Down { a: 786432 },
+            OpCode::Set { c: 0x69 },
+        ]);
+
+        assert_eq!(text, "Hi\ni\n");
+    }
+
+    #[test]
+    fn test_page_text_replaces_unknown_characters() {
+        let text = text_of(vec![OpCode::Set { c: 0x0C }]);
+
+        assert_eq!(text, "\u{FFFD}\n");
+    }
+
+    #[test]
+    fn test_page_text_of_empty_page() {
+        assert_eq!(text_of(vec![]), "");
+    }
+
+    #[test]
+    fn test_page_text_with_encoding_of_tfm() {
+        let instructions = vec![OpCode::Set { c: 0x0C }, OpCode::Set { c: 0x69 }];
+
+        let text = text_with(Some("TeX text"), &TextExtractor::new(), instructions);
+
+        assert_eq!(text, "fii\n");
+    }
+
+    #[test]
+    fn test_page_text_with_encoding_of_font() {
+        let mut glyph_names = vec![".notdef"; 256];
+        glyph_names[0x48] = "Eth";
+        let mut extractor = TextExtractor::new();
+        extractor.set_encoding("cmr10", Encoding::from_glyph_names("Test", &glyph_names));
+
+        let text = text_with(
+            Some("TeX text"),
+            &extractor,
+            vec![OpCode::Set { c: 0x48 }, OpCode::Set { c: 0x69 }],
+        );
+
+        assert_eq!(text, "\u{D0}\u{FFFD}\n");
+    }
+
+    fn text_of(instructions: Vec<OpCode>) -> String {
+        text_with(None, &TextExtractor::new(), instructions)
+    }
+
+    fn text_with(
+        encoding: Option<&str>,
+        extractor: &TextExtractor,
+        instructions: Vec<OpCode>,
+    ) -> String {
+        let half = FixWord::from_f64(0.5);
+        let mut cmr10 = TfmBuilder::new()
+            .char(0x0C, half, FixWord::ZERO, FixWord::ZERO)
+            .char(0x48, half, FixWord::ZERO, FixWord::ZERO)
+            .char(0x69, half, FixWord::ZERO, FixWord::ZERO)
+            // The slant and space of cmr10
+            .params(&[FixWord::ZERO, FixWord::from_raw(349526)]);
+        if let Some(encoding) = encoding {
+            cmr10 = cmr10.encoding(encoding);
+        }
+        let mut resolver = MemoryResolver::new();
+        resolver.insert("cmr10", cmr10.build());
+        let mut interpreter = Interpreter::with_resolver(resolver);
+
+        let mut opcodes = vec![
+            OpCode::FntDef {
+                k: 1,
+                c: 0,
+                s: 655360,
+                d: 655360,
+                a: 0,
+                l: 5,
+                n: b"cmr10".to_vec(),
+            },
+            OpCode::Bop {
+                c0: 1,
+                c1: 0,
+                c2: 0,
+                c3: 0,
+                c4: 0,
+                c5: 0,
+                c6: 0,
+                c7: 0,
+                c8: 0,
+                c9: 0,
+                p: -1,
+            },
+            OpCode::Fnt { k: 1 },
+        ];
+        opcodes.extend(instructions);
+        opcodes.push(OpCode::Eop);
+        let pages = interpreter.execute(opcodes).unwrap();
+
+        extractor.page_text(&pages[0], &interpreter)
+    }
+}
@@ -0,0 +1,131 @@
+use crate::util::dimension::Dimension;
+
+/// The default `num`, `den` and `mag` of DVI files written by TeX
+pub const TEX_NUMERATOR: u32 = 25_400_000;
+pub const TEX_DENOMINATOR: u32 = 473_628_672;
+pub const TEX_MAGNIFICATION: u32 = 1000;
+
+/// The units understood by the dviasm text format and `DviUnits`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Unit {
+    Sp,
+    Pt,
+    Bp,
+    Mm,
+    Cm,
+    In,
+}
+
+impl Unit {
+    pub fn from_suffix(suffix: &str) -> Option<Unit> {
+        match suffix {
+            "sp" => Some(Unit::Sp),
+            "pt" => Some(Unit::Pt),
+            "bp" => Some(Unit::Bp),
+            "mm" => Some(Unit::Mm),
+            "cm" => Some(Unit::Cm),
+            "in" => Some(Unit::In),
+            _ => None,
+        }
+    }
+
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Unit::Sp => "sp",
+            Unit::Pt => "pt",
+            Unit::Bp => "bp",
+            Unit::Mm => "mm",
+            Unit::Cm => "cm",
+            Unit::In => "in",
+        }
+    }
+
+    /// How many of this unit one DVI unit is, given the `num` and `den` of the preamble
+    pub fn per_dvi_unit(self, num: u32, den: u32) -> f64 {
+        let num = f64::from(num);
+        let den = f64::from(den);
+        match self {
+            Unit::Sp => (num / 25_400_000.) * (473_628_672. / den),
+            Unit::Pt => (num / 25_400_000.) * (7227. / den),
+            Unit::Bp => (num / 254_000.) * (72. / den),
+            Unit::Mm => (num / 10000.) / den,
+            Unit::Cm => (num / 100_000.) / den,
+            Unit::In => (num / 254_000.) * (1. / den),
+        }
+    }
+}
+
+/// The `num`, `den` and `mag` of a preamble, which give DVI units a physical size
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DviUnits {
+    pub num: u32,
+    pub den: u32,
+    pub mag: u32,
+}
+
+impl Default for DviUnits {
+    fn default() -> DviUnits {
+        DviUnits::new(TEX_NUMERATOR, TEX_DENOMINATOR, TEX_MAGNIFICATION)
+    }
+}
+
+impl DviUnits {
+    pub fn new(num: u32, den: u32, mag: u32) -> DviUnits {
+        DviUnits { num, den, mag }
+    }
+
+    /// How many of `unit` one magnified DVI unit is
+    pub fn per_dvi_unit(self, unit: Unit) -> f64 {
+        unit.per_dvi_unit(self.num, self.den) * (f64::from(self.mag) / 1000.0)
+    }
+
+    /// How many pixels one magnified DVI unit is at `resolution` pixels per inch,
+    /// computed like dvitype computes `conv`
+    pub fn pixels_per_dvi_unit(self, resolution: f64) -> f64 {
+        let num = f64::from(self.num);
+        let den = f64::from(self.den);
+        (num / 254_000.0) * (resolution / den) * (f64::from(self.mag) / 1000.0)
+    }
+
+    pub fn convert(self, dimension: Dimension, unit: Unit) -> f64 {
+        self.per_dvi_unit(unit) * dimension.raw() as f64
+    }
+
+    pub fn to_pixels(self, dimension: Dimension, resolution: f64) -> f64 {
+        self.pixels_per_dvi_unit(resolution) * dimension.raw() as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dvi::units::*;
+    use crate::util::dimension::Dimension;
+
+    #[test]
+    fn test_convert_tex_units() {
+        let units = DviUnits::default();
+        let ten_points = Dimension::new(655_360);
+
+        assert!((units.convert(ten_points, Unit::Sp) - 655_360.0).abs() < 1e-6);
+        assert!((units.convert(ten_points, Unit::Pt) - 10.0).abs() < 1e-9);
+        assert!((units.convert(ten_points, Unit::Bp) - 9.962_640).abs() < 1e-6);
+        assert!((units.convert(ten_points, Unit::Mm) - 3.514_598).abs() < 1e-6);
+        assert!((units.convert(ten_points, Unit::Cm) - 0.351_460).abs() < 1e-6);
+        assert!((units.convert(ten_points, Unit::In) - 10.0 / 72.27).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_convert_with_magnification() {
+        let units = DviUnits::new(TEX_NUMERATOR, TEX_DENOMINATOR, 2000);
+
+        assert!((units.convert(Dimension::new(65_536), Unit::Pt) - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_to_pixels() {
+        let units = DviUnits::default();
+
+        assert!((units.to_pixels(Dimension::new(65_536), 72.27) - 1.0).abs() < 1e-12);
+        assert!((units.to_pixels(Dimension::new(4_736_287), 300.0) - 300.0).abs() < 1e-4);
+    }
+}
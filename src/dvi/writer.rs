@@ -0,0 +1,573 @@
+use std::collections::HashMap;
+use std::io::Write;
+
+use crate::dvi::bbox::BoundingBox;
+use crate::dvi::encoder::{encode_opcode, smallest_opcode_byte};
+use crate::dvi::opcodes::OpCode;
+use crate::errors::{DviousError, DviousResult};
+use crate::fonts::tfm::{CharMetrics, TexFontMetric};
+use crate::util::dimension::Dimension;
+
+const PADDING_BYTE: u8 = 223;
+
+pub fn write_dvi(opcodes: &[OpCode]) -> DviousResult<Vec<u8>> {
+    let mut writer = DviWriter::new(Vec::new());
+    for opcode in opcodes {
+        writer.write(opcode)?;
+    }
+    writer.finish()
+}
+
+/// Writes the preamble and pages in `opcodes` and generates the postamble from them.
+pub fn write_dvi_with_postamble(opcodes: &[OpCode]) -> DviousResult<Vec<u8>> {
+    let mut writer = DviWriter::new(Vec::new());
+    for opcode in opcodes {
+        writer.write(opcode)?;
+    }
+    writer.write_postamble()?;
+    writer.finish()
+}
+
+/// The values that `post` reports about the pages of a document.
+#[derive(Debug, Default, PartialEq)]
+pub struct PostambleStatistics {
+    /// `l`, the largest vertical extent of any page
+    pub max_v: u32,
+    /// `u`, the largest horizontal extent of any page
+    pub max_h: u32,
+    /// `s`, the deepest nesting of `push` commands
+    pub max_stack_depth: u16,
+    /// `t`, the number of `bop` commands
+    pub total_pages: u16,
+}
+
+/// Serializes opcodes into a DVI byte stream, using the smallest encoding for every
+/// parameter. The pointers of `bop`, `post` and `post_post` are ignored and replaced
+/// by the actual offsets, and `post_post` is followed by four to seven bytes of 223
+/// so that the length of the file is a multiple of four.
+///
+/// While writing pages, the writer follows the movements on every page to collect
+/// the `PostambleStatistics`. The extents of a page span the reference point, the
+/// positions reached and the boxes of rules and characters. Characters only take
+/// up space if the metrics of their font were registered with `set_font_metrics`.
+pub struct DviWriter<W: Write> {
+    out: W,
+    position: usize,
+    last_bop: Option<usize>,
+    last_post: Option<usize>,
+    buffer: Vec<u8>,
+    preamble: Option<OpCode>,
+    font_definitions: Vec<OpCode>,
+    font_metrics: HashMap<i32, TexFontMetric>,
+    extents: PageExtents,
+    statistics: PostambleStatistics,
+    max_extents: (Option<u32>, Option<u32>),
+}
+
+/// Registers while following a page and the box spanned by the page so far
+#[derive(Default)]
+struct PageExtents {
+    registers: Registers,
+    f: Option<i32>,
+    stack: Vec<Registers>,
+    bbox: Option<BoundingBox>,
+}
+
+#[derive(Clone, Copy, Default)]
+struct Registers {
+    h: Dimension,
+    v: Dimension,
+    w: Dimension,
+    x: Dimension,
+    y: Dimension,
+    z: Dimension,
+}
+
+impl PageExtents {
+    fn include(&mut self, bbox: BoundingBox) {
+        self.bbox = Some(match self.bbox {
+            Some(page) => page.union(bbox),
+            None => bbox,
+        });
+    }
+}
+
+impl<W: Write> DviWriter<W> {
+    pub fn new(out: W) -> DviWriter<W> {
+        DviWriter {
+            out,
+            position: 0,
+            last_bop: Option::None,
+            last_post: Option::None,
+            buffer: Vec::new(),
+            preamble: Option::None,
+            font_definitions: Vec::new(),
+            font_metrics: HashMap::new(),
+            extents: Default::default(),
+            statistics: Default::default(),
+            max_extents: (Option::None, Option::None),
+        }
+    }
+
+    /// Makes the widths of the characters in font `k` known for the statistics
+    pub fn set_font_metrics(&mut self, k: i32, tfm: TexFontMetric) {
+        self.font_metrics.insert(k, tfm);
+    }
+
+    /// Writes the given `l` and `u` into the postamble instead of the gathered ones,
+    /// e.g. to keep the values of a document that is reassembled
+    pub fn set_max_extents(&mut self, max_v: Option<u32>, max_h: Option<u32>) {
+        self.max_extents = (max_v, max_h);
+    }
+
+    /// The statistics gathered from the pages written so far
+    pub fn statistics(&self) -> &PostambleStatistics {
+        &self.statistics
+    }
+
+    /// Writes `post` with the gathered statistics, the definitions of every font used
+    /// in the pages and `post_post`. Requires that the preamble was written before.
+    pub fn write_postamble(&mut self) -> DviousResult<()> {
+        let (i, num, den, mag) = match self.preamble {
+            Some(OpCode::Pre {
+                i, num, den, mag, ..
+            }) => (i, num, den, mag),
+            _ => {
+                return Err(DviousError::DviEncodeError(
+                    "Cannot write a postamble without a preamble".to_string(),
+                ))
+            }
+        };
+
+        self.write(&OpCode::Post {
+            p: Option::None,
+            num,
+            den,
+            mag,
+            l: self.max_extents.0.unwrap_or(self.statistics.max_v),
+            u: self.max_extents.1.unwrap_or(self.statistics.max_h),
+            s: self.statistics.max_stack_depth,
+            t: self.statistics.total_pages,
+        })?;
+
+        let font_definitions = std::mem::take(&mut self.font_definitions);
+        for font_definition in &font_definitions {
+            self.write(font_definition)?;
+        }
+        self.font_definitions = font_definitions;
+
+        self.write(&OpCode::PostPost { q: Option::None, i })
+    }
+
+    pub fn write(&mut self, opcode: &OpCode) -> DviousResult<()> {
+        let position = self.position;
+        let mut opcode = opcode.clone();
+        match opcode {
+            OpCode::Bop { ref mut p, .. } => {
+                *p = self.last_bop.map_or(-1, |offset| offset as i32);
+                self.last_bop = Option::Some(position);
+            }
+            OpCode::Post { ref mut p, .. } => {
+                *p = self.last_bop;
+                self.last_post = Option::Some(position);
+            }
+            OpCode::PostPost { ref mut q, .. } => *q = self.last_post,
+            _ => {}
+        }
+        if self.last_post.is_none() {
+            self.gather_statistics(&opcode)?;
+        }
+        self.emit(&opcode)?;
+
+        if let OpCode::PostPost { .. } = opcode {
+            let padding = 4 + (4 - self.position % 4) % 4;
+            self.buffer.clear();
+            self.buffer.resize(padding, PADDING_BYTE);
+            self.flush_buffer()?;
+        }
+        Ok(())
+    }
+
+    /// Number of bytes written so far
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn finish(mut self) -> DviousResult<W> {
+        self.out.flush()?;
+        Ok(self.out)
+    }
+
+    fn gather_statistics(&mut self, opcode: &OpCode) -> DviousResult<()> {
+        let e = &mut self.extents;
+        let r = &mut e.registers;
+        match *opcode {
+            OpCode::Pre { .. } => self.preamble = Option::Some(opcode.clone()),
+            OpCode::Bop { .. } => {
+                *e = Default::default();
+                self.statistics.total_pages = self.statistics.total_pages.saturating_add(1);
+            }
+            OpCode::Eop => {
+                if let Some(bbox) = e.bbox {
+                    let (width, height) = (bbox.width().raw(), bbox.height().raw());
+                    self.statistics.max_h = self.statistics.max_h.max(clamp_to_u32(width));
+                    self.statistics.max_v = self.statistics.max_v.max(clamp_to_u32(height));
+                }
+            }
+            OpCode::Set { c } | OpCode::Put { c } => {
+                let metrics = char_metrics(&self.font_metrics, &self.font_definitions, e.f, c);
+                let (h, v) = (r.h, r.v);
+                if let OpCode::Set { .. } = *opcode {
+                    r.h += metrics.width;
+                }
+                e.include(BoundingBox::from_corners(
+                    h,
+                    v - metrics.height,
+                    h + metrics.width,
+                    v + metrics.depth,
+                ));
+            }
+            OpCode::SetRule { a, b } | OpCode::PutRule { a, b } => {
+                let (h, v) = (r.h, r.v);
+                let (height, width) = (Dimension::from(a), Dimension::from(b));
+                if let OpCode::SetRule { .. } = *opcode {
+                    r.h += width;
+                }
+                e.include(BoundingBox::from_corners(h, v - height, h + width, v));
+            }
+            OpCode::Push => {
+                e.stack.push(*r);
+                let depth = e.stack.len() as u16;
+                self.statistics.max_stack_depth = self.statistics.max_stack_depth.max(depth);
+            }
+            OpCode::Pop => match e.stack.pop() {
+                Some(registers) => *r = registers,
+                None => {
+                    return Err(DviousError::DviEncodeError(
+                        "Pop on empty stack".to_string(),
+                    ))
+                }
+            },
+            OpCode::Right { b } => r.h += Dimension::from(b),
+            OpCode::W0 => r.h += r.w,
+            OpCode::W { b } => {
+                r.w = Dimension::from(b);
+                r.h += r.w;
+            }
+            OpCode::X0 => r.h += r.x,
+            OpCode::X { b } => {
+                r.x = Dimension::from(b);
+                r.h += r.x;
+            }
+            OpCode::Down { a } => r.v += Dimension::from(a),
+            OpCode::Y0 => r.v += r.y,
+            OpCode::Y { a } => {
+                r.y = Dimension::from(a);
+                r.v += r.y;
+            }
+            OpCode::Z0 => r.v += r.z,
+            OpCode::Z { a } => {
+                r.z = Dimension::from(a);
+                r.v += r.z;
+            }
+            OpCode::Fnt { k } => e.f = Option::Some(k),
+            OpCode::FntDef { k, .. } => {
+                let known = self
+                    .font_definitions
+                    .iter()
+                    .any(|definition| matches!(*definition, OpCode::FntDef { k: known, .. } if known == k));
+                if !known {
+                    self.font_definitions.push(opcode.clone());
+                }
+            }
+            _ => {}
+        }
+        // The reference point and every position reached belong to the page
+        let (h, v) = (e.registers.h, e.registers.v);
+        e.include(BoundingBox::from_corners(
+            Dimension::ZERO,
+            Dimension::ZERO,
+            h,
+            v,
+        ));
+        Ok(())
+    }
+
+    fn emit(&mut self, opcode: &OpCode) -> DviousResult<()> {
+        self.buffer.clear();
+        encode_opcode(opcode, smallest_opcode_byte(opcode), &mut self.buffer)?;
+        self.flush_buffer()
+    }
+
+    fn flush_buffer(&mut self) -> DviousResult<()> {
+        self.out.write_all(&self.buffer)?;
+        self.position += self.buffer.len();
+        Ok(())
+    }
+}
+
+/// Dimensions of character `c` in DVI units, or zero if the metrics of the font are
+/// not known
+fn char_metrics(
+    font_metrics: &HashMap<i32, TexFontMetric>,
+    font_definitions: &[OpCode],
+    f: Option<i32>,
+    c: i32,
+) -> CharMetrics {
+    let k = match f {
+        Some(k) => k,
+        None => return Default::default(),
+    };
+    let scale = font_definitions
+        .iter()
+        .find_map(|definition| match *definition {
+            OpCode::FntDef { k: defined, s, .. } if defined == k => Some(s),
+            _ => None,
+        });
+
+    match (font_metrics.get(&k), scale) {
+        (Some(tfm), Some(s)) if (0..=255).contains(&c) => tfm
+            .char_metrics(c as u8, Dimension::new(i64::from(s)))
+            .unwrap_or_default(),
+        _ => Default::default(),
+    }
+}
+
+fn clamp_to_u32(value: i64) -> u32 {
+    value.max(0).min(i64::from(u32::MAX)) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dvi::disassembler::disassemble;
+    use crate::dvi::opcodes::OpCode;
+    use crate::dvi::writer::*;
+    use crate::fonts::tfm::builder::TfmBuilder;
+    use crate::util::num::FixWord;
+
+    #[test]
+    fn test_write_smallest_encoding() {
+        let opcodes = vec![
+            OpCode::Set { c: 0x41 },
+            OpCode::Set { c: 0xC4 },
+            OpCode::Right { b: -42 },
+            OpCode::Fnt { k: 3 },
+        ];
+
+        let bytes = write_dvi(&opcodes).unwrap();
+
+        assert_eq!(bytes, vec![0x41, 128, 0xC4, 143, 0xD6, 174]);
+    }
+
+    #[test]
+    fn test_write_computes_pointers() {
+        let opcodes = vec![
+            bop(),
+            OpCode::Eop,
+            bop(),
+            OpCode::Eop,
+            post(),
+            OpCode::PostPost {
+                q: Option::None,
+                i: 2,
+            },
+        ];
+
+        let bytes = write_dvi(&opcodes).unwrap();
+        let decoded = disassemble(bytes).unwrap();
+
+        assert_eq!(decoded[0], bop_with_pointer(-1));
+        assert_eq!(decoded[2], bop_with_pointer(0));
+        match decoded[4] {
+            OpCode::Post { p, .. } => assert_eq!(p, Option::Some(46)),
+            ref other => panic!("Expected post, was {:?}", other),
+        }
+        match decoded[5] {
+            OpCode::PostPost { q, .. } => assert_eq!(q, Option::Some(92)),
+            ref other => panic!("Expected post_post, was {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_write_pads_to_multiple_of_four() {
+        for extra in 0..4 {
+            let mut opcodes = vec![OpCode::Nop; extra];
+            opcodes.push(OpCode::PostPost {
+                q: Option::None,
+                i: 2,
+            });
+
+            let bytes = write_dvi(&opcodes).unwrap();
+            let padding = bytes.iter().rev().take_while(|&&b| b == 223).count();
+
+            assert_eq!(bytes.len() % 4, 0);
+            assert!((4..=7).contains(&padding), "Unexpected padding {}", padding);
+        }
+    }
+
+    #[test]
+    fn test_write_postamble_statistics() {
+        let opcodes = vec![
+            pre(),
+            bop(),
+            OpCode::Push,
+            OpCode::Push,
+            OpCode::Down { a: 500 },
+            OpCode::SetRule { a: 10, b: 300 },
+            OpCode::Pop,
+            OpCode::Pop,
+            OpCode::Push,
+            OpCode::Down { a: 200 },
+            OpCode::Pop,
+            OpCode::Eop,
+            bop(),
+            OpCode::Right { b: 400 },
+            OpCode::Eop,
+        ];
+        let mut writer = DviWriter::new(Vec::new());
+        for opcode in &opcodes {
+            writer.write(opcode).unwrap();
+        }
+
+        assert_eq!(
+            writer.statistics(),
+            &PostambleStatistics {
+                max_v: 500,
+                max_h: 400,
+                max_stack_depth: 2,
+                total_pages: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_write_postamble_statistics_with_glyphs() {
+        let opcodes = vec![
+            pre(),
+            bop(),
+            font_definition(),
+            OpCode::Fnt { k: 7 },
+            OpCode::Right { b: -100 },
+            OpCode::Down { a: 1000 },
+            OpCode::Set { c: 0x42 },
+            OpCode::Eop,
+        ];
+        let mut writer = DviWriter::new(Vec::new());
+        writer.set_font_metrics(
+            7,
+            TfmBuilder::new()
+                .char(
+                    0x42,
+                    FixWord::ONE,
+                    FixWord::from_f64(2.0),
+                    FixWord::from_f64(0.5),
+                )
+                .build(),
+        );
+        for opcode in &opcodes {
+            writer.write(opcode).unwrap();
+        }
+
+        // From 100 left of the reference point to the right edge of the glyph, and
+        // from the top of the glyph above the reference point down to its depth
+        assert_eq!(writer.statistics().max_h, 100 + 655_360 - 100);
+        assert_eq!(writer.statistics().max_v, 1_310_720 + 327_680);
+    }
+
+    #[test]
+    fn test_write_pop_underflow() {
+        let opcodes = vec![pre(), bop(), OpCode::Pop, OpCode::Eop];
+
+        let result = write_dvi(&opcodes);
+
+        assert!(matches!(result, Err(DviousError::DviEncodeError(_))));
+    }
+
+    #[test]
+    fn test_write_postamble_mirrors_font_definitions() {
+        let font_definition = font_definition();
+        let opcodes = vec![
+            pre(),
+            bop(),
+            font_definition.clone(),
+            OpCode::Fnt { k: 7 },
+            OpCode::Eop,
+            bop(),
+            font_definition.clone(),
+            OpCode::Eop,
+        ];
+
+        let bytes = write_dvi_with_postamble(&opcodes).unwrap();
+        let decoded = disassemble(bytes).unwrap();
+
+        assert_eq!(decoded.len(), opcodes.len() + 3);
+        assert_eq!(decoded[opcodes.len() + 1], font_definition);
+        match decoded[opcodes.len() + 2] {
+            OpCode::PostPost { i, .. } => assert_eq!(i, 2),
+            ref other => panic!("Expected post_post, was {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_write_postamble_without_preamble() {
+        let mut writer = DviWriter::new(Vec::new());
+
+        assert!(writer.write_postamble().is_err(), "Expected Err, was Ok");
+    }
+
+    fn font_definition() -> OpCode {
+        OpCode::FntDef {
+            k: 7,
+            c: 0xDEAD,
+            s: 655_360,
+            d: 655_360,
+            a: 0,
+            l: 5,
+            n: b"cmr10".to_vec(),
+        }
+    }
+
+    fn pre() -> OpCode {
+        OpCode::Pre {
+            i: 2,
+            num: 25_400_000,
+            den: 473_628_672,
+            mag: 1000,
+            k: 0,
+            x: vec![],
+        }
+    }
+
+    fn bop() -> OpCode {
+        bop_with_pointer(0x1234)
+    }
+
+    fn bop_with_pointer(p: i32) -> OpCode {
+        OpCode::Bop {
+            c0: 1,
+            c1: 0,
+            c2: 0,
+            c3: 0,
+            c4: 0,
+            c5: 0,
+            c6: 0,
+            c7: 0,
+            c8: 0,
+            c9: 0,
+            p,
+        }
+    }
+
+    fn post() -> OpCode {
+        OpCode::Post {
+            p: Option::None,
+            num: 25_400_000,
+            den: 473_628_672,
+            mag: 1000,
+            l: 0,
+            u: 0,
+            s: 0,
+            t: 2,
+        }
+    }
+}
@@ -1,13 +1,27 @@
 use std::io;
 use std::string::FromUtf8Error;
 
+use crate::fonts::verification::FontWarning;
+
 #[derive(Debug)]
 pub enum DviousError {
+    AsmParseError(String),
+    DviEncodeError(String),
+    DviInterpretError(String),
+    DviParseError(String),
+    EncParseError(String),
+    /// A font warning that was turned into an error in strict mode
+    FontMismatchError(FontWarning),
+    FontNotFoundError(String),
     IoError(io::Error),
     KpsewhichError(String),
     IndexOutOfBoundsError,
+    /// The lig/kern programs of a font never finish for a word
+    LigKernLoopError(String),
+    TfmEncodeError(String),
     TfmParseError(String),
-    UnknownOpcodeError(u8),
+    /// The unknown opcode byte and its offset in the input
+    UnknownOpcodeError(u8, usize),
     Utf8Error(FromUtf8Error),
 }
 
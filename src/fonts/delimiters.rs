@@ -0,0 +1,311 @@
+use crate::fonts::tfm::{TexFontMetric, TfmCharInfoTag};
+use crate::util::dimension::Dimension;
+
+/// The pieces of an extensible character, where absent pieces are `None`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExtensibleRecipe {
+    pub top: Option<u8>,
+    pub mid: Option<u8>,
+    pub bot: Option<u8>,
+    pub rep: u8,
+}
+
+/// The variant of a delimiter that TeX picks for a given size
+#[derive(Clone, Debug, PartialEq)]
+pub enum Delimiter {
+    /// A single character of the charlist
+    Char(u8),
+    /// An extensible character, with `repeats` copies of `rep` above and below `mid`.
+    /// The pieces are listed from top to bottom, and `size` is their total height
+    /// plus depth.
+    Assembled {
+        recipe: ExtensibleRecipe,
+        repeats: usize,
+        pieces: Vec<u8>,
+        size: Dimension,
+    },
+}
+
+impl TexFontMetric {
+    /// The next larger variant of `c`, if `c` has a charlist
+    pub fn next_larger(&self, c: u8) -> Option<u8> {
+        match self.char_info_table.get(&c)?.tag {
+            TfmCharInfoTag::List(next) => Some(next),
+            _ => None,
+        }
+    }
+
+    /// `c` followed by its larger variants, in the order TeX tries them. The chain
+    /// stops at characters that do not exist and before it would revisit one.
+    pub fn charlist(&self, c: u8) -> Vec<u8> {
+        let mut chain = Vec::new();
+        let mut next = Some(c);
+        while let Some(c) = next {
+            if !self.char_exists(c) || chain.contains(&c) {
+                break;
+            }
+            chain.push(c);
+            next = self.next_larger(c);
+        }
+        chain
+    }
+
+    /// The recipe of `c`, if it is an extensible character
+    pub fn extensible_recipe(&self, c: u8) -> Option<ExtensibleRecipe> {
+        let index = match self.char_info_table.get(&c)?.tag {
+            TfmCharInfoTag::Extensible(index) => usize::from(index),
+            _ => return None,
+        };
+        let recipe = self.extension_table.get(index)?;
+        let piece = |c: u8| if c == 0 { None } else { Some(c) };
+        Some(ExtensibleRecipe {
+            top: piece(recipe.top),
+            mid: piece(recipe.mid),
+            bot: piece(recipe.bot),
+            rep: recipe.rep,
+        })
+    }
+
+    /// Picks the variant of the delimiter `c` with a height plus depth of at least
+    /// `size` in a font loaded at `at_size`, like `var_delimiter` of TeX. The charlist
+    /// is walked until a variant is large enough or an extensible character is found,
+    /// which is assembled to the size. Otherwise the largest variant is returned, and
+    /// like in TeX, variants without height and depth are never picked.
+    pub fn delimiter(&self, c: u8, size: Dimension, at_size: Dimension) -> Option<Delimiter> {
+        let mut best = None;
+        let mut largest = Dimension::ZERO;
+        for c in self.charlist(c) {
+            if let Some(recipe) = self.extensible_recipe(c) {
+                return self.assemble(recipe, size, at_size);
+            }
+
+            let height_plus_depth = self.height_plus_depth(c, at_size)?;
+            if height_plus_depth > largest {
+                best = Some(c);
+                largest = height_plus_depth;
+                if height_plus_depth >= size {
+                    break;
+                }
+            }
+        }
+        best.map(Delimiter::Char)
+    }
+
+    fn assemble(
+        &self,
+        recipe: ExtensibleRecipe,
+        size: Dimension,
+        at_size: Dimension,
+    ) -> Option<Delimiter> {
+        let rep = self.height_plus_depth(recipe.rep, at_size)?;
+        let mut total = Dimension::ZERO;
+        for &piece in [recipe.top, recipe.mid, recipe.bot].iter().flatten() {
+            total += self.height_plus_depth(piece, at_size)?;
+        }
+
+        let mut repeats = 0;
+        if rep > Dimension::ZERO {
+            while total < size {
+                total += rep;
+                repeats += 1;
+                if recipe.mid.is_some() {
+                    total += rep;
+                }
+            }
+        }
+
+        let mut pieces: Vec<u8> = recipe.top.into_iter().collect();
+        pieces.extend(vec![recipe.rep; repeats]);
+        if let Some(mid) = recipe.mid {
+            pieces.push(mid);
+            pieces.extend(vec![recipe.rep; repeats]);
+        }
+        pieces.extend(recipe.bot);
+
+        Some(Delimiter::Assembled {
+            recipe,
+            repeats,
+            pieces,
+            size: total,
+        })
+    }
+
+    fn char_exists(&self, c: u8) -> bool {
+        matches!(self.char_info_table.get(&c), Some(info) if info.width_index != 0)
+    }
+
+    fn height_plus_depth(&self, c: u8, at_size: Dimension) -> Option<Dimension> {
+        let metrics = self.char_metrics(c, at_size)?;
+        Some(metrics.height + metrics.depth)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fonts::delimiters::*;
+    use crate::fonts::tfm::builder::TfmBuilder;
+    use crate::fonts::tfm::*;
+    use crate::util::dimension::Dimension;
+    use crate::util::num::FixWord;
+
+    const TEN_POINT: Dimension = Dimension::new(655_360);
+
+    #[test]
+    fn test_next_larger() {
+        let tfm = delimiter_font();
+
+        assert_eq!(tfm.next_larger(0x00), Some(0x10));
+        assert_eq!(tfm.next_larger(0x20), Some(0x30));
+        assert_eq!(tfm.next_larger(0x30), None);
+        assert_eq!(tfm.next_larger(0x99), None);
+    }
+
+    #[test]
+    fn test_charlist() {
+        let tfm = delimiter_font();
+
+        assert_eq!(tfm.charlist(0x00), vec![0x00, 0x10, 0x12, 0x20, 0x30]);
+        assert_eq!(tfm.charlist(0x20), vec![0x20, 0x30]);
+        assert_eq!(tfm.charlist(0x42), vec![0x42]);
+        assert_eq!(tfm.charlist(0x99), vec![]);
+    }
+
+    #[test]
+    fn test_charlist_stops_at_cycles() {
+        let mut tfm = delimiter_font();
+        set_tag(&mut tfm, 0x12, TfmCharInfoTag::List(0x10));
+
+        assert_eq!(tfm.charlist(0x00), vec![0x00, 0x10, 0x12]);
+    }
+
+    #[test]
+    fn test_extensible_recipe() {
+        let tfm = delimiter_font();
+
+        assert_eq!(
+            tfm.extensible_recipe(0x30),
+            Some(ExtensibleRecipe {
+                top: Some(0x30),
+                mid: None,
+                bot: Some(0x40),
+                rep: 0x42,
+            })
+        );
+        assert_eq!(tfm.extensible_recipe(0x20), None);
+    }
+
+    #[test]
+    fn test_delimiter_picks_first_variant_large_enough() {
+        let tfm = delimiter_font();
+
+        let delimiter = |size| tfm.delimiter(0x00, Dimension::new(size), TEN_POINT);
+
+        assert_eq!(delimiter(0), Some(Delimiter::Char(0x00)));
+        assert_eq!(delimiter(491_520), Some(Delimiter::Char(0x00)));
+        assert_eq!(delimiter(491_521), Some(Delimiter::Char(0x10)));
+        assert_eq!(delimiter(1_966_080), Some(Delimiter::Char(0x20)));
+    }
+
+    #[test]
+    fn test_delimiter_assembles_extensible() {
+        let tfm = delimiter_font();
+
+        let delimiter = tfm.delimiter(0x00, Dimension::new(2_000_000), TEN_POINT);
+
+        // Top and bottom are 983040 together, and 7 repeats of 163840 are needed
+        let mut pieces = vec![0x30];
+        pieces.extend(vec![0x42; 7]);
+        pieces.push(0x40);
+        assert_eq!(
+            delimiter,
+            Some(Delimiter::Assembled {
+                recipe: tfm.extensible_recipe(0x30).unwrap(),
+                repeats: 7,
+                pieces,
+                size: Dimension::new(983_040 + 7 * 163_840),
+            })
+        );
+    }
+
+    #[test]
+    fn test_delimiter_assembles_around_middle() {
+        let mut tfm = delimiter_font();
+        tfm.extension_table[0].mid = 0x44;
+
+        let delimiter = tfm.delimiter(0x30, Dimension::new(1_500_000), TEN_POINT);
+
+        // Every repeat adds a piece above and below the middle
+        assert_eq!(
+            delimiter,
+            Some(Delimiter::Assembled {
+                recipe: tfm.extensible_recipe(0x30).unwrap(),
+                repeats: 2,
+                pieces: vec![0x30, 0x42, 0x42, 0x44, 0x42, 0x42, 0x40],
+                size: Dimension::new(1_146_880 + 4 * 163_840),
+            })
+        );
+    }
+
+    #[test]
+    fn test_delimiter_falls_back_to_largest_variant() {
+        let mut tfm = delimiter_font();
+        set_tag(&mut tfm, 0x20, TfmCharInfoTag::None);
+
+        let delimiter = tfm.delimiter(0x00, Dimension::new(10_000_000), TEN_POINT);
+
+        assert_eq!(delimiter, Some(Delimiter::Char(0x20)));
+        assert_eq!(tfm.delimiter(0x99, Dimension::ZERO, TEN_POINT), None);
+    }
+
+    #[test]
+    fn test_delimiter_skips_empty_variants() {
+        let (zero, half) = (FixWord::ZERO, FixWord::from_f64(0.5));
+        let tfm = TfmBuilder::new()
+            .char(0x01, half, zero, zero)
+            .tag(0x01, TfmCharInfoTag::List(0x02))
+            .char(0x02, half, half, zero)
+            .char(0x03, half, zero, zero)
+            .build();
+
+        let delimiter = tfm.delimiter(0x01, Dimension::new(10_000_000), TEN_POINT);
+
+        assert_eq!(delimiter, Some(Delimiter::Char(0x02)));
+        assert_eq!(tfm.delimiter(0x03, Dimension::ZERO, TEN_POINT), None);
+    }
+
+    fn set_tag(tfm: &mut TexFontMetric, c: u8, tag: TfmCharInfoTag) {
+        tfm.char_info_table.get_mut(&c).unwrap().tag = tag;
+    }
+
+    /// A left parenthesis like in cmex10, with four sizes followed by an extensible
+    /// version, and its pieces
+    fn delimiter_font() -> TexFontMetric {
+        let sizes = vec![
+            (0x00, 0.5, 0.25, TfmCharInfoTag::List(0x10)),
+            (0x10, 1.0, 0.5, TfmCharInfoTag::List(0x12)),
+            (0x12, 1.5, 0.75, TfmCharInfoTag::List(0x20)),
+            (0x20, 2.0, 1.0, TfmCharInfoTag::List(0x30)),
+            (0x30, 0.75, 0.0, TfmCharInfoTag::Extensible(0)),
+            (0x40, 0.75, 0.0, TfmCharInfoTag::None),
+            (0x42, 0.25, 0.0, TfmCharInfoTag::None),
+            (0x44, 0.25, 0.0, TfmCharInfoTag::None),
+        ];
+        let mut builder = TfmBuilder::new()
+            .encoding("TeX math extension")
+            .font_identifier("CMEX");
+        for (c, height, depth, tag) in sizes {
+            let (height, depth) = (FixWord::from_f64(height), FixWord::from_f64(depth));
+            builder = builder
+                .char(c, FixWord::from_f64(0.5), height, depth)
+                .tag(c, tag);
+        }
+        builder
+            .extensible(vec![TfmExtensionRecipe {
+                top: 0x30,
+                mid: 0,
+                bot: 0x40,
+                rep: 0x42,
+            }])
+            .build()
+    }
+}
@@ -0,0 +1,1344 @@
+use std::fs;
+
+use crate::errors::{DviousError, DviousResult};
+use crate::fonts::glyph_names::glyph_to_unicode;
+use crate::fonts::tfm::TexFontMetric;
+
+/// Maps the 256 character codes of a font to Unicode text. Ligatures are decomposed,
+/// so that the `fi` of a TeX font is the two letters f and i.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Encoding {
+    name: String,
+    unicode: Vec<Option<String>>,
+}
+
+/// The encodings of the fonts that come with TeX and LaTeX
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BuiltinEncoding {
+    /// OT1, the encoding of Computer Modern text fonts like cmr10
+    TexText,
+    /// The typewriter variant of OT1 used by cmtt10
+    TexTypewriterText,
+    /// T1, the Cork encoding of the EC fonts
+    T1,
+    /// TS1, the text companion symbols of the TC fonts
+    Ts1,
+    /// OML, the math italic of cmmi10
+    Oml,
+    /// OMS, the math symbols of cmsy10
+    Oms,
+    /// OMX, the math extension of cmex10
+    Omx,
+    /// LY1, TeX'n'ANSI
+    Ly1,
+}
+
+impl BuiltinEncoding {
+    /// Recognizes the coding scheme of a TFM header, like `TeX text`, or the name of
+    /// the encoding, like `OT1`. Case and repeated spaces are ignored.
+    pub fn from_coding_scheme(scheme: &str) -> Option<BuiltinEncoding> {
+        let scheme = scheme
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_uppercase();
+        match scheme.as_str() {
+            "TEX TEXT" | "TEX TEXT WITHOUT F-LIGATURES" | "OT1" => Some(BuiltinEncoding::TexText),
+            "TEX TYPEWRITER TEXT" | "OT1TT" => Some(BuiltinEncoding::TexTypewriterText),
+            "EXTENDED TEX FONT ENCODING - LATIN" | "T1" | "CORK" => Some(BuiltinEncoding::T1),
+            "TEX TEXT COMPANION SYMBOLS 1---TS1" | "TS1" => Some(BuiltinEncoding::Ts1),
+            "TEX MATH ITALIC" | "OML" => Some(BuiltinEncoding::Oml),
+            "TEX MATH SYMBOLS" | "OMS" => Some(BuiltinEncoding::Oms),
+            "TEX MATH EXTENSION" | "OMX" => Some(BuiltinEncoding::Omx),
+            "TEX TYPEWRITER AND WINDOWS ANSI" | "LY1" | "TEXNANSI" => Some(BuiltinEncoding::Ly1),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BuiltinEncoding::TexText => "OT1",
+            BuiltinEncoding::TexTypewriterText => "OT1TT",
+            BuiltinEncoding::T1 => "T1",
+            BuiltinEncoding::Ts1 => "TS1",
+            BuiltinEncoding::Oml => "OML",
+            BuiltinEncoding::Oms => "OMS",
+            BuiltinEncoding::Omx => "OMX",
+            BuiltinEncoding::Ly1 => "LY1",
+        }
+    }
+
+    /// The glyph names of the 256 character codes
+    fn glyph_names(self) -> Vec<&'static str> {
+        let mut names = vec![".notdef"; 256];
+        match self {
+            BuiltinEncoding::TexText => {
+                set(&mut names, 0, &GREEK_CAPITALS);
+                set(&mut names, 11, &OT1_LIGATURES);
+                set(&mut names, 16, &OT1_ACCENTS_AND_FOREIGN);
+                set(&mut names, 32, &ASCII);
+                set(&mut names, 32, &["suppress"]);
+                set(&mut names, 34, &["quotedblright"]);
+                set(&mut names, 39, &["quoteright"]);
+                set(&mut names, 60, &["exclamdown"]);
+                set(&mut names, 62, &["questiondown"]);
+                set(&mut names, 92, &["quotedblleft"]);
+                set(&mut names, 94, &["circumflex", "dotaccent", "quoteleft"]);
+                set(
+                    &mut names,
+                    123,
+                    &["endash", "emdash", "hungarumlaut", "tilde", "dieresis"],
+                );
+            }
+            BuiltinEncoding::TexTypewriterText => {
+                set(&mut names, 0, &GREEK_CAPITALS);
+                set(
+                    &mut names,
+                    11,
+                    &[
+                        "arrowup",
+                        "arrowdown",
+                        "quotesingle",
+                        "exclamdown",
+                        "questiondown",
+                    ],
+                );
+                set(&mut names, 16, &OT1_ACCENTS_AND_FOREIGN);
+                set(&mut names, 32, &ASCII);
+                set(&mut names, 32, &["visiblespace"]);
+                set(&mut names, 39, &["quoteright"]);
+                set(&mut names, 96, &["quoteleft"]);
+                set(&mut names, 127, &["dieresis"]);
+            }
+            BuiltinEncoding::T1 => {
+                set(&mut names, 0, &T1_LOW);
+                set(&mut names, 32, &ASCII);
+                set(&mut names, 32, &["visiblespace"]);
+                set(&mut names, 39, &["quoteright"]);
+                set(&mut names, 96, &["quoteleft"]);
+                set(&mut names, 127, &["sfthyphen"]);
+                set(&mut names, 128, &T1_HIGH);
+                set(&mut names, 192, &LATIN1[32..]);
+                set(&mut names, 215, &["OE"]);
+                set(&mut names, 223, &["SS"]);
+                set(&mut names, 247, &["oe"]);
+                set(&mut names, 255, &["germandbls"]);
+            }
+            BuiltinEncoding::Ts1 => {
+                for &(code, name) in TS1.iter() {
+                    names[usize::from(code)] = name;
+                }
+            }
+            BuiltinEncoding::Oml => set(&mut names, 0, &OML),
+            BuiltinEncoding::Oms => set(&mut names, 0, &OMS),
+            BuiltinEncoding::Omx => set(&mut names, 0, &OMX),
+            BuiltinEncoding::Ly1 => {
+                set(&mut names, 0, &LY1_LOW);
+                set(&mut names, 32, &ASCII);
+                set(&mut names, 39, &["quoteright"]);
+                set(&mut names, 96, &["quoteleft"]);
+                set(&mut names, 127, &["dieresis"]);
+                set(&mut names, 128, &LY1_HIGH);
+                set(&mut names, 160, &LATIN1);
+                set(&mut names, 173, &["hyphen"]);
+            }
+        }
+        names
+    }
+}
+
+fn set(names: &mut [&'static str], start: usize, glyphs: &[&'static str]) {
+    names[start..start + glyphs.len()].copy_from_slice(glyphs);
+}
+
+impl Encoding {
+    pub fn builtin(encoding: BuiltinEncoding) -> Encoding {
+        Encoding::from_glyph_names(encoding.name(), &encoding.glyph_names())
+    }
+
+    /// The built-in encoding for the coding scheme of a TFM header
+    pub fn from_coding_scheme(scheme: &str) -> Option<Encoding> {
+        BuiltinEncoding::from_coding_scheme(scheme).map(Encoding::builtin)
+    }
+
+    /// The built-in encoding named in the header of the metrics
+    pub fn for_tfm(tfm: &TexFontMetric) -> Option<Encoding> {
+        tfm.header
+            .encoding
+            .as_ref()
+            .and_then(|scheme| Encoding::from_coding_scheme(scheme))
+    }
+
+    /// Creates an encoding from the glyph names of the character codes, which are
+    /// mapped to Unicode like the Adobe Glyph List does
+    pub fn from_glyph_names<S: AsRef<str>>(name: &str, glyph_names: &[S]) -> Encoding {
+        let mut unicode: Vec<Option<String>> = glyph_names
+            .iter()
+            .take(256)
+            .map(|glyph| glyph_to_unicode(glyph.as_ref()))
+            .collect();
+        unicode.resize(256, None);
+        Encoding {
+            name: name.to_string(),
+            unicode,
+        }
+    }
+
+    /// Parses an encoding vector of dvips and pdfTeX, like
+    /// `/T1Encoding [ /grave /acute ... ] def`
+    pub fn parse_enc(text: &str) -> DviousResult<Encoding> {
+        let tokens = tokenize_enc(text);
+        let open = tokens
+            .iter()
+            .position(|&token| token == "[")
+            .ok_or_else(|| {
+                DviousError::EncParseError("Encoding vector does not start with [[]".to_string())
+            })?;
+        let close = tokens
+            .iter()
+            .position(|&token| token == "]")
+            .ok_or_else(|| {
+                DviousError::EncParseError("Encoding vector does not end with []]".to_string())
+            })?;
+        if close < open {
+            return Err(DviousError::EncParseError(
+                "Encoding vector ends before it starts".to_string(),
+            ));
+        }
+
+        let name = tokens[..open]
+            .iter()
+            .rev()
+            .find_map(|token| token.strip_prefix('/'))
+            .unwrap_or_default();
+        let mut glyph_names = Vec::new();
+        for token in &tokens[open + 1..close] {
+            match token.strip_prefix('/') {
+                Some(glyph) => glyph_names.push(glyph),
+                None => {
+                    return Err(DviousError::EncParseError(format!(
+                        "Expected a glyph name, but found [{}]",
+                        token
+                    )))
+                }
+            }
+        }
+        if glyph_names.len() != 256 {
+            return Err(DviousError::EncParseError(format!(
+                "Encoding vector has [{}] glyph names instead of 256",
+                glyph_names.len()
+            )));
+        }
+
+        Ok(Encoding::from_glyph_names(name, &glyph_names))
+    }
+
+    pub fn read_enc_file(path: &str) -> DviousResult<Encoding> {
+        Encoding::parse_enc(&fs::read_to_string(path)?)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The Unicode text of a character code, or `None` if it is not encoded. Some
+    /// glyphs like the compound word mark of T1 are empty.
+    pub fn to_unicode(&self, c: i32) -> Option<&str> {
+        if !(0..256).contains(&c) {
+            return None;
+        }
+        self.unicode[c as usize].as_deref()
+    }
+}
+
+/// Splits PostScript into names, the brackets of arrays and other words, without
+/// comments
+fn tokenize_enc(text: &str) -> Vec<&str> {
+    let is_delimiter = |c: char| c.is_whitespace() || "[]{}()<>/%".contains(c);
+
+    let mut tokens = Vec::new();
+    for line in text.lines() {
+        let line = line.split('%').next().unwrap_or_default();
+        let mut rest = line.trim_start();
+        while let Some(c) = rest.chars().next() {
+            let length = match c {
+                '[' | ']' | '{' | '}' => 1,
+                '/' => 1 + rest[1..].find(is_delimiter).unwrap_or(rest.len() - 1),
+                _ => rest.find(is_delimiter).unwrap_or(rest.len()).max(1),
+            };
+            tokens.push(&rest[..length]);
+            rest = rest[length..].trim_start();
+        }
+    }
+    tokens
+}
+
+const ASCII: [&str; 95] = [
+    "space",
+    "exclam",
+    "quotedbl",
+    "numbersign",
+    "dollar",
+    "percent",
+    "ampersand",
+    "quotesingle",
+    "parenleft",
+    "parenright",
+    "asterisk",
+    "plus",
+    "comma",
+    "hyphen",
+    "period",
+    "slash",
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "colon",
+    "semicolon",
+    "less",
+    "equal",
+    "greater",
+    "question",
+    "at",
+    "A",
+    "B",
+    "C",
+    "D",
+    "E",
+    "F",
+    "G",
+    "H",
+    "I",
+    "J",
+    "K",
+    "L",
+    "M",
+    "N",
+    "O",
+    "P",
+    "Q",
+    "R",
+    "S",
+    "T",
+    "U",
+    "V",
+    "W",
+    "X",
+    "Y",
+    "Z",
+    "bracketleft",
+    "backslash",
+    "bracketright",
+    "asciicircum",
+    "underscore",
+    "grave",
+    "a",
+    "b",
+    "c",
+    "d",
+    "e",
+    "f",
+    "g",
+    "h",
+    "i",
+    "j",
+    "k",
+    "l",
+    "m",
+    "n",
+    "o",
+    "p",
+    "q",
+    "r",
+    "s",
+    "t",
+    "u",
+    "v",
+    "w",
+    "x",
+    "y",
+    "z",
+    "braceleft",
+    "bar",
+    "braceright",
+    "asciitilde",
+];
+
+/// The codes 160 to 255 of ISO 8859-1
+const LATIN1: [&str; 96] = [
+    "nbspace",
+    "exclamdown",
+    "cent",
+    "sterling",
+    "currency",
+    "yen",
+    "brokenbar",
+    "section",
+    "dieresis",
+    "copyright",
+    "ordfeminine",
+    "guillemotleft",
+    "logicalnot",
+    "sfthyphen",
+    "registered",
+    "macron",
+    "degree",
+    "plusminus",
+    "twosuperior",
+    "threesuperior",
+    "acute",
+    "mu",
+    "paragraph",
+    "periodcentered",
+    "cedilla",
+    "onesuperior",
+    "ordmasculine",
+    "guillemotright",
+    "onequarter",
+    "onehalf",
+    "threequarters",
+    "questiondown",
+    "Agrave",
+    "Aacute",
+    "Acircumflex",
+    "Atilde",
+    "Adieresis",
+    "Aring",
+    "AE",
+    "Ccedilla",
+    "Egrave",
+    "Eacute",
+    "Ecircumflex",
+    "Edieresis",
+    "Igrave",
+    "Iacute",
+    "Icircumflex",
+    "Idieresis",
+    "Eth",
+    "Ntilde",
+    "Ograve",
+    "Oacute",
+    "Ocircumflex",
+    "Otilde",
+    "Odieresis",
+    "multiply",
+    "Oslash",
+    "Ugrave",
+    "Uacute",
+    "Ucircumflex",
+    "Udieresis",
+    "Yacute",
+    "Thorn",
+    "germandbls",
+    "agrave",
+    "aacute",
+    "acircumflex",
+    "atilde",
+    "adieresis",
+    "aring",
+    "ae",
+    "ccedilla",
+    "egrave",
+    "eacute",
+    "ecircumflex",
+    "edieresis",
+    "igrave",
+    "iacute",
+    "icircumflex",
+    "idieresis",
+    "eth",
+    "ntilde",
+    "ograve",
+    "oacute",
+    "ocircumflex",
+    "otilde",
+    "odieresis",
+    "divide",
+    "oslash",
+    "ugrave",
+    "uacute",
+    "ucircumflex",
+    "udieresis",
+    "yacute",
+    "thorn",
+    "ydieresis",
+];
+
+const GREEK_CAPITALS: [&str; 11] = [
+    "Gamma", "Delta", "Theta", "Lambda", "Xi", "Pi", "Sigma", "Upsilon", "Phi", "Psi", "Omega",
+];
+
+const OT1_LIGATURES: [&str; 5] = ["ff", "fi", "fl", "ffi", "ffl"];
+
+/// The codes 16 to 31 of OT1
+const OT1_ACCENTS_AND_FOREIGN: [&str; 16] = [
+    "dotlessi",
+    "dotlessj",
+    "grave",
+    "acute",
+    "caron",
+    "breve",
+    "macron",
+    "ring",
+    "cedilla",
+    "germandbls",
+    "ae",
+    "oe",
+    "oslash",
+    "AE",
+    "OE",
+    "Oslash",
+];
+
+const T1_LOW: [&str; 32] = [
+    "grave",
+    "acute",
+    "circumflex",
+    "tilde",
+    "dieresis",
+    "hungarumlaut",
+    "ring",
+    "caron",
+    "breve",
+    "macron",
+    "dotaccent",
+    "cedilla",
+    "ogonek",
+    "quotesinglbase",
+    "guilsinglleft",
+    "guilsinglright",
+    "quotedblleft",
+    "quotedblright",
+    "quotedblbase",
+    "guillemotleft",
+    "guillemotright",
+    "endash",
+    "emdash",
+    "cwm",
+    "perthousandzero",
+    "dotlessi",
+    "dotlessj",
+    "ff",
+    "fi",
+    "fl",
+    "ffi",
+    "ffl",
+];
+
+/// The codes 128 to 191 of T1
+const T1_HIGH: [&str; 64] = [
+    "Abreve",
+    "Aogonek",
+    "Cacute",
+    "Ccaron",
+    "Dcaron",
+    "Ecaron",
+    "Eogonek",
+    "Gbreve",
+    "Lacute",
+    "Lcaron",
+    "Lslash",
+    "Nacute",
+    "Ncaron",
+    "Eng",
+    "Ohungarumlaut",
+    "Racute",
+    "Rcaron",
+    "Sacute",
+    "Scaron",
+    "Scedilla",
+    "Tcaron",
+    "Tcommaaccent",
+    "Uhungarumlaut",
+    "Uring",
+    "Ydieresis",
+    "Zacute",
+    "Zcaron",
+    "Zdotaccent",
+    "IJ",
+    "Idotaccent",
+    "dcroat",
+    "section",
+    "abreve",
+    "aogonek",
+    "cacute",
+    "ccaron",
+    "dcaron",
+    "ecaron",
+    "eogonek",
+    "gbreve",
+    "lacute",
+    "lcaron",
+    "lslash",
+    "nacute",
+    "ncaron",
+    "eng",
+    "ohungarumlaut",
+    "racute",
+    "rcaron",
+    "sacute",
+    "scaron",
+    "scedilla",
+    "tcaron",
+    "tcommaaccent",
+    "uhungarumlaut",
+    "uring",
+    "ydieresis",
+    "zacute",
+    "zcaron",
+    "zdotaccent",
+    "ij",
+    "exclamdown",
+    "questiondown",
+    "sterling",
+];
+
+/// The codes of TS1 that are defined
+const TS1: &[(u8, &str)] = &[
+    (0, "capitalgrave"),
+    (1, "capitalacute"),
+    (2, "capitalcircumflex"),
+    (3, "capitaltilde"),
+    (4, "capitaldieresis"),
+    (5, "capitalhungarumlaut"),
+    (6, "capitalring"),
+    (7, "capitalcaron"),
+    (8, "capitalbreve"),
+    (9, "capitalmacron"),
+    (10, "capitaldotaccent"),
+    (11, "cedilla"),
+    (12, "ogonek"),
+    (13, "quotestraightbase"),
+    (18, "quotestraightdblbase"),
+    (21, "twelveudash"),
+    (22, "threequartersemdash"),
+    (23, "capitalcompwordmark"),
+    (24, "arrowleft"),
+    (25, "arrowright"),
+    (26, "tie"),
+    (27, "capitaltie"),
+    (28, "newtie"),
+    (29, "capitalnewtie"),
+    (31, "ascendercompwordmark"),
+    (32, "blank"),
+    (36, "dollar"),
+    (39, "quotesingle"),
+    (42, "asteriskcentered"),
+    (44, "comma"),
+    (45, "hyphendbl"),
+    (46, "period"),
+    (47, "fraction"),
+    (48, "zerooldstyle"),
+    (49, "oneoldstyle"),
+    (50, "twooldstyle"),
+    (51, "threeoldstyle"),
+    (52, "fouroldstyle"),
+    (53, "fiveoldstyle"),
+    (54, "sixoldstyle"),
+    (55, "sevenoldstyle"),
+    (56, "eightoldstyle"),
+    (57, "nineoldstyle"),
+    (60, "angleleft"),
+    (61, "minus"),
+    (62, "angleright"),
+    (77, "mho"),
+    (79, "bigcircle"),
+    (87, "ohm"),
+    (91, "dblbracketleft"),
+    (93, "dblbracketright"),
+    (94, "arrowup"),
+    (95, "arrowdown"),
+    (96, "grave"),
+    (98, "born"),
+    (99, "divorced"),
+    (100, "died"),
+    (108, "leaf"),
+    (109, "married"),
+    (110, "musicalnote"),
+    (126, "tildelow"),
+    (127, "hyphendbl"),
+    (128, "breve"),
+    (129, "caron"),
+    (130, "hungarumlaut"),
+    (131, "uni02F5"),
+    (132, "dagger"),
+    (133, "daggerdbl"),
+    (134, "bardbl"),
+    (135, "perthousand"),
+    (136, "bullet"),
+    (137, "celsius"),
+    (138, "dollaroldstyle"),
+    (139, "centoldstyle"),
+    (140, "florin"),
+    (141, "colonmonetary"),
+    (142, "won"),
+    (143, "naira"),
+    (144, "guarani"),
+    (145, "peso"),
+    (146, "lira"),
+    (147, "recipe"),
+    (148, "interrobang"),
+    (149, "interrobangdown"),
+    (150, "dong"),
+    (151, "trademark"),
+    (152, "pertenthousand"),
+    (153, "paragraph"),
+    (154, "baht"),
+    (155, "numero"),
+    (156, "discount"),
+    (157, "estimated"),
+    (158, "openbullet"),
+    (159, "servicemark"),
+    (160, "quillleft"),
+    (161, "quillright"),
+    (162, "cent"),
+    (163, "sterling"),
+    (164, "currency"),
+    (165, "yen"),
+    (166, "brokenbar"),
+    (167, "section"),
+    (168, "dieresis"),
+    (169, "copyright"),
+    (170, "ordfeminine"),
+    (171, "copyleft"),
+    (172, "logicalnot"),
+    (173, "circledP"),
+    (174, "registered"),
+    (175, "macron"),
+    (176, "degree"),
+    (177, "plusminus"),
+    (178, "twosuperior"),
+    (179, "threesuperior"),
+    (180, "acute"),
+    (181, "mu"),
+    (182, "paragraph"),
+    (183, "periodcentered"),
+    (184, "referencemark"),
+    (185, "onesuperior"),
+    (186, "ordmasculine"),
+    (187, "radical"),
+    (188, "onequarter"),
+    (189, "onehalf"),
+    (190, "threequarters"),
+    (191, "Euro"),
+    (214, "multiply"),
+    (246, "divide"),
+];
+
+const OML: [&str; 128] = [
+    "Gamma",
+    "Delta",
+    "Theta",
+    "Lambda",
+    "Xi",
+    "Pi",
+    "Sigma",
+    "Upsilon",
+    "Phi",
+    "Psi",
+    "Omega",
+    "alpha",
+    "beta",
+    "gamma",
+    "delta",
+    "epsilon1",
+    "zeta",
+    "eta",
+    "theta",
+    "iota",
+    "kappa",
+    "lambda",
+    "uni03BC",
+    "nu",
+    "xi",
+    "pi",
+    "rho",
+    "sigma",
+    "tau",
+    "upsilon",
+    "phi",
+    "chi",
+    "psi",
+    "omega",
+    "epsilon",
+    "theta1",
+    "pi1",
+    "rho1",
+    "sigma1",
+    "phi1",
+    "arrowlefttophalf",
+    "arrowleftbothalf",
+    "arrowrighttophalf",
+    "arrowrightbothalf",
+    "arrowhookleft",
+    "arrowhookright",
+    "triangleright",
+    "triangleleft",
+    "zerooldstyle",
+    "oneoldstyle",
+    "twooldstyle",
+    "threeoldstyle",
+    "fouroldstyle",
+    "fiveoldstyle",
+    "sixoldstyle",
+    "sevenoldstyle",
+    "eightoldstyle",
+    "nineoldstyle",
+    "period",
+    "comma",
+    "less",
+    "slash",
+    "greater",
+    "star",
+    "partialdiff",
+    "A",
+    "B",
+    "C",
+    "D",
+    "E",
+    "F",
+    "G",
+    "H",
+    "I",
+    "J",
+    "K",
+    "L",
+    "M",
+    "N",
+    "O",
+    "P",
+    "Q",
+    "R",
+    "S",
+    "T",
+    "U",
+    "V",
+    "W",
+    "X",
+    "Y",
+    "Z",
+    "flat",
+    "natural",
+    "sharp",
+    "slurbelow",
+    "slurabove",
+    "lscript",
+    "a",
+    "b",
+    "c",
+    "d",
+    "e",
+    "f",
+    "g",
+    "h",
+    "i",
+    "j",
+    "k",
+    "l",
+    "m",
+    "n",
+    "o",
+    "p",
+    "q",
+    "r",
+    "s",
+    "t",
+    "u",
+    "v",
+    "w",
+    "x",
+    "y",
+    "z",
+    "dotlessi",
+    "dotlessj",
+    "weierstrass",
+    "vector",
+    "tie",
+];
+
+const OMS: [&str; 128] = [
+    "minus",
+    "periodcentered",
+    "multiply",
+    "asteriskmath",
+    "divide",
+    "diamondmath",
+    "plusminus",
+    "minusplus",
+    "circleplus",
+    "circleminus",
+    "circlemultiply",
+    "circledivide",
+    "circledot",
+    "circlecopyrt",
+    "openbullet",
+    "bullet",
+    "equivasymptotic",
+    "equivalence",
+    "reflexsubset",
+    "reflexsuperset",
+    "lessequal",
+    "greaterequal",
+    "precedesequal",
+    "followsequal",
+    "similar",
+    "approxequal",
+    "propersubset",
+    "propersuperset",
+    "lessmuch",
+    "greatermuch",
+    "precedes",
+    "follows",
+    "arrowleft",
+    "arrowright",
+    "arrowup",
+    "arrowdown",
+    "arrowboth",
+    "arrownortheast",
+    "arrowsoutheast",
+    "similarequal",
+    "arrowdblleft",
+    "arrowdblright",
+    "arrowdblup",
+    "arrowdbldown",
+    "arrowdblboth",
+    "arrownorthwest",
+    "arrowsouthwest",
+    "proportional",
+    "prime",
+    "infinity",
+    "element",
+    "owner",
+    "triangle",
+    "triangleinv",
+    "negationslash",
+    "mapsto",
+    "universal",
+    "existential",
+    "logicalnot",
+    "emptyset",
+    "Rfractur",
+    "Ifractur",
+    "latticetop",
+    "perpendicular",
+    "aleph",
+    "A",
+    "B",
+    "C",
+    "D",
+    "E",
+    "F",
+    "G",
+    "H",
+    "I",
+    "J",
+    "K",
+    "L",
+    "M",
+    "N",
+    "O",
+    "P",
+    "Q",
+    "R",
+    "S",
+    "T",
+    "U",
+    "V",
+    "W",
+    "X",
+    "Y",
+    "Z",
+    "union",
+    "intersection",
+    "unionmulti",
+    "logicaland",
+    "logicalor",
+    "turnstileleft",
+    "turnstileright",
+    "floorleft",
+    "floorright",
+    "ceilingleft",
+    "ceilingright",
+    "braceleft",
+    "braceright",
+    "angbracketleft",
+    "angbracketright",
+    "bar",
+    "bardbl",
+    "arrowbothv",
+    "arrowdblbothv",
+    "backslash",
+    "wreathproduct",
+    "radical",
+    "coproduct",
+    "nabla",
+    "integral",
+    "unionsq",
+    "intersectionsq",
+    "subsetsqequal",
+    "supersetsqequal",
+    "section",
+    "dagger",
+    "daggerdbl",
+    "paragraph",
+    "club",
+    "diamond",
+    "heart",
+    "spade",
+];
+
+/// The delimiters and operators of OMX come in several sizes, which all map to the
+/// same character
+const OMX: [&str; 128] = [
+    "parenleft",
+    "parenright",
+    "bracketleft",
+    "bracketright",
+    "floorleft",
+    "floorright",
+    "ceilingleft",
+    "ceilingright",
+    "braceleft",
+    "braceright",
+    "angbracketleft",
+    "angbracketright",
+    "vextendsingle",
+    "vextenddouble",
+    "slash",
+    "backslash",
+    "parenleft",
+    "parenright",
+    "parenleft",
+    "parenright",
+    "bracketleft",
+    "bracketright",
+    "floorleft",
+    "floorright",
+    "ceilingleft",
+    "ceilingright",
+    "braceleft",
+    "braceright",
+    "angbracketleft",
+    "angbracketright",
+    "slash",
+    "backslash",
+    "parenleft",
+    "parenright",
+    "bracketleft",
+    "bracketright",
+    "floorleft",
+    "floorright",
+    "ceilingleft",
+    "ceilingright",
+    "braceleft",
+    "braceright",
+    "angbracketleft",
+    "angbracketright",
+    "slash",
+    "backslash",
+    "slash",
+    "backslash",
+    "parenlefttp",
+    "parenrighttp",
+    "bracketlefttp",
+    "bracketrighttp",
+    "bracketleftbt",
+    "bracketrightbt",
+    "bracketleftex",
+    "bracketrightex",
+    "bracelefttp",
+    "bracerighttp",
+    "braceleftbt",
+    "bracerightbt",
+    "braceleftmid",
+    "bracerightmid",
+    "braceex",
+    "arrowvertex",
+    "parenleftbt",
+    "parenrightbt",
+    "parenleftex",
+    "parenrightex",
+    "angbracketleft",
+    "angbracketright",
+    "unionsqdisplay",
+    "unionsqdisplay",
+    "contintegral",
+    "contintegral",
+    "circledotdisplay",
+    "circledotdisplay",
+    "circleplusdisplay",
+    "circleplusdisplay",
+    "circlemultiplydisplay",
+    "circlemultiplydisplay",
+    "summation",
+    "product",
+    "integral",
+    "uniondisplay",
+    "intersectiondisplay",
+    "unionmultidisplay",
+    "logicalanddisplay",
+    "logicalordisplay",
+    "summation",
+    "product",
+    "integral",
+    "uniondisplay",
+    "intersectiondisplay",
+    "unionmultidisplay",
+    "logicalanddisplay",
+    "logicalordisplay",
+    "coproductdisplay",
+    "coproductdisplay",
+    "hatwide",
+    "hatwide",
+    "hatwide",
+    "tildewide",
+    "tildewide",
+    "tildewide",
+    "bracketleft",
+    "bracketright",
+    "floorleft",
+    "floorright",
+    "ceilingleft",
+    "ceilingright",
+    "braceleft",
+    "braceright",
+    "radical",
+    "radical",
+    "radical",
+    "radical",
+    "radicalbt",
+    "arrowvertex",
+    ".notdef",
+    "arrowvertexdbl",
+    "arrowtp",
+    "arrowbt",
+    ".notdef",
+    ".notdef",
+    ".notdef",
+    ".notdef",
+    "arrowdbltp",
+    "arrowdblbt",
+];
+
+const LY1_LOW: [&str; 32] = [
+    ".notdef",
+    "dotaccent",
+    "fi",
+    "fl",
+    "fraction",
+    "hungarumlaut",
+    "Lslash",
+    "lslash",
+    "ogonek",
+    "ring",
+    ".notdef",
+    "breve",
+    "minus",
+    ".notdef",
+    "Zcaron",
+    "zcaron",
+    "caron",
+    "dotlessi",
+    "dotlessj",
+    "ff",
+    "ffi",
+    "ffl",
+    ".notdef",
+    ".notdef",
+    ".notdef",
+    ".notdef",
+    ".notdef",
+    ".notdef",
+    ".notdef",
+    ".notdef",
+    "grave",
+    "quotesingle",
+];
+
+/// The codes 128 to 159 of LY1, which follow Windows ANSI
+const LY1_HIGH: [&str; 32] = [
+    "Euro",
+    ".notdef",
+    "quotesinglbase",
+    "florin",
+    "quotedblbase",
+    "ellipsis",
+    "dagger",
+    "daggerdbl",
+    "circumflex",
+    "perthousand",
+    "Scaron",
+    "guilsinglleft",
+    "OE",
+    ".notdef",
+    ".notdef",
+    ".notdef",
+    ".notdef",
+    "quoteleft",
+    "quoteright",
+    "quotedblleft",
+    "quotedblright",
+    "bullet",
+    "endash",
+    "emdash",
+    "tilde",
+    "trademark",
+    "scaron",
+    "guilsinglright",
+    "oe",
+    ".notdef",
+    ".notdef",
+    "Ydieresis",
+];
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::DviousError;
+    use crate::fonts::encoding::*;
+    use crate::fonts::tfm::builder::TfmBuilder;
+
+    #[test]
+    fn test_from_coding_scheme() {
+        assert_eq!(
+            BuiltinEncoding::from_coding_scheme("TeX text"),
+            Some(BuiltinEncoding::TexText)
+        );
+        assert_eq!(
+            BuiltinEncoding::from_coding_scheme("EXTENDED TEX FONT ENCODING - LATIN"),
+            Some(BuiltinEncoding::T1)
+        );
+        assert_eq!(
+            BuiltinEncoding::from_coding_scheme("TeX  math   symbols"),
+            Some(BuiltinEncoding::Oms)
+        );
+        assert_eq!(
+            BuiltinEncoding::from_coding_scheme("ly1"),
+            Some(BuiltinEncoding::Ly1)
+        );
+        assert_eq!(BuiltinEncoding::from_coding_scheme("UNSPECIFIED"), None);
+    }
+
+    #[test]
+    fn test_tex_text() {
+        let encoding = Encoding::builtin(BuiltinEncoding::TexText);
+
+        assert_eq!(encoding.name(), "OT1");
+        assert_eq!(encoding.to_unicode(0x0C), Some("fi"));
+        assert_eq!(encoding.to_unicode(0x0E), Some("ffi"));
+        assert_eq!(encoding.to_unicode(0x19), Some("ß"));
+        assert_eq!(encoding.to_unicode(0x22), Some("\u{201D}"));
+        assert_eq!(encoding.to_unicode(0x5C), Some("\u{201C}"));
+        assert_eq!(encoding.to_unicode(0x41), Some("A"));
+        assert_eq!(encoding.to_unicode(0x7B), Some("\u{2013}"));
+        assert_eq!(encoding.to_unicode(0x80), None);
+    }
+
+    #[test]
+    fn test_typewriter_text() {
+        let encoding = Encoding::builtin(BuiltinEncoding::TexTypewriterText);
+
+        assert_eq!(encoding.to_unicode(0x0C), Some("\u{2193}"));
+        assert_eq!(encoding.to_unicode(0x20), Some("\u{2423}"));
+        assert_eq!(encoding.to_unicode(0x5C), Some("\\"));
+        assert_eq!(encoding.to_unicode(0x7B), Some("{"));
+    }
+
+    #[test]
+    fn test_t1() {
+        let encoding = Encoding::builtin(BuiltinEncoding::T1);
+
+        assert_eq!(encoding.to_unicode(0x17), Some(""));
+        assert_eq!(encoding.to_unicode(0x1C), Some("fi"));
+        assert_eq!(encoding.to_unicode(0x8A), Some("Ł"));
+        assert_eq!(encoding.to_unicode(0xBF), Some("£"));
+        assert_eq!(encoding.to_unicode(0xC9), Some("É"));
+        assert_eq!(encoding.to_unicode(0xD7), Some("Œ"));
+        assert_eq!(encoding.to_unicode(0xDF), Some("SS"));
+        assert_eq!(encoding.to_unicode(0xE9), Some("é"));
+        assert_eq!(encoding.to_unicode(0xFF), Some("ß"));
+    }
+
+    #[test]
+    fn test_ts1() {
+        let encoding = Encoding::builtin(BuiltinEncoding::Ts1);
+
+        assert_eq!(encoding.to_unicode(0x24), Some("$"));
+        assert_eq!(encoding.to_unicode(0xB0), Some("°"));
+        assert_eq!(encoding.to_unicode(0xBF), Some("€"));
+        assert_eq!(encoding.to_unicode(0x41), None);
+    }
+
+    #[test]
+    fn test_math_encodings() {
+        let oml = Encoding::builtin(BuiltinEncoding::Oml);
+        let oms = Encoding::builtin(BuiltinEncoding::Oms);
+        let omx = Encoding::builtin(BuiltinEncoding::Omx);
+
+        assert_eq!(oml.to_unicode(0x0B), Some("α"));
+        assert_eq!(oml.to_unicode(0x16), Some("μ"));
+        assert_eq!(oml.to_unicode(0x78), Some("x"));
+        assert_eq!(oms.to_unicode(0x00), Some("\u{2212}"));
+        assert_eq!(oms.to_unicode(0x31), Some("∞"));
+        assert_eq!(omx.to_unicode(0x50), Some("∑"));
+        assert_eq!(omx.to_unicode(0x58), Some("∑"));
+        assert_eq!(omx.to_unicode(0x12), Some("("));
+    }
+
+    #[test]
+    fn test_ly1() {
+        let encoding = Encoding::builtin(BuiltinEncoding::Ly1);
+
+        assert_eq!(encoding.to_unicode(0x02), Some("fi"));
+        assert_eq!(encoding.to_unicode(0x80), Some("€"));
+        assert_eq!(encoding.to_unicode(0x93), Some("\u{201C}"));
+        assert_eq!(encoding.to_unicode(0xE9), Some("é"));
+    }
+
+    #[test]
+    fn test_for_tfm() {
+        let mut tfm = TfmBuilder::new().encoding("TeX math italic").build();
+
+        assert_eq!(Encoding::for_tfm(&tfm).unwrap().name(), "OML");
+
+        tfm.header.encoding = None;
+        assert_eq!(Encoding::for_tfm(&tfm), None);
+    }
+
+    #[test]
+    fn test_parse_enc() {
+        let mut text = String::from("% A test\n/TestEncoding [ % first\n/fi/A /.notdef\n");
+        for _ in 3..256 {
+            text.push_str("/uni00E9 ");
+        }
+        text.push_str("\n] def\n");
+
+        let encoding = Encoding::parse_enc(&text).unwrap();
+
+        assert_eq!(encoding.name(), "TestEncoding");
+        assert_eq!(encoding.to_unicode(0), Some("fi"));
+        assert_eq!(encoding.to_unicode(1), Some("A"));
+        assert_eq!(encoding.to_unicode(2), None);
+        assert_eq!(encoding.to_unicode(255), Some("é"));
+    }
+
+    #[test]
+    fn test_parse_enc_with_wrong_length() {
+        let result = Encoding::parse_enc("/Short [ /a /b ] def");
+
+        assert!(matches!(result, Err(DviousError::EncParseError(_))));
+    }
+
+    #[test]
+    fn test_parse_enc_without_vector() {
+        let result = Encoding::parse_enc("/Broken /a /b def");
+
+        assert!(matches!(result, Err(DviousError::EncParseError(_))));
+    }
+}
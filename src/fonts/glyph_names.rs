@@ -0,0 +1,616 @@
+/// Finds the Unicode text of a glyph name like the Adobe Glyph List does: known
+/// names, `uniXXXX` and `uXXXX[XX]` code points, ligatures joined with `_`, and
+/// variants like `a.sc` that fall back to their base name. Ligatures of TeX fonts
+/// are decomposed, so `fi` becomes the two letters f and i.
+pub fn glyph_to_unicode(name: &str) -> Option<String> {
+    if name == ".notdef" || name.is_empty() {
+        return None;
+    }
+    if let Some(base) = name.split('.').next().filter(|base| *base != name) {
+        return glyph_to_unicode(base);
+    }
+    if name.contains('_') {
+        return name.split('_').map(glyph_to_unicode).collect();
+    }
+
+    if let Some(&(_, text)) = GLYPHS.iter().find(|&&(glyph, _)| glyph == name) {
+        return Some(text.to_string());
+    }
+    if name.len() == 1 && name.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Some(name.to_string());
+    }
+    if let Some(hex) = name.strip_prefix("uni") {
+        // Slicing by bytes requires ASCII
+        if !hex.is_empty() && hex.is_ascii() && hex.len() % 4 == 0 {
+            return (0..hex.len())
+                .step_by(4)
+                .map(|i| code_point(&hex[i..i + 4]))
+                .collect();
+        }
+    }
+    if let Some(hex) = name.strip_prefix('u') {
+        if (4..=6).contains(&hex.len()) {
+            return code_point(hex).map(String::from);
+        }
+    }
+    None
+}
+
+fn code_point(hex: &str) -> Option<char> {
+    if !hex
+        .chars()
+        .all(|c| c.is_ascii_hexdigit() && !c.is_ascii_lowercase())
+    {
+        return None;
+    }
+    u32::from_str_radix(hex, 16)
+        .ok()
+        .and_then(std::char::from_u32)
+}
+
+/// The glyph names of the encodings of TeX fonts and their Unicode text
+const GLYPHS: &[(&str, &str)] = &[
+    // ASCII
+    ("space", " "),
+    ("exclam", "!"),
+    ("quotedbl", "\""),
+    ("numbersign", "#"),
+    ("dollar", "$"),
+    ("percent", "%"),
+    ("ampersand", "&"),
+    ("quotesingle", "'"),
+    ("parenleft", "("),
+    ("parenright", ")"),
+    ("asterisk", "*"),
+    ("plus", "+"),
+    ("comma", ","),
+    ("hyphen", "-"),
+    ("period", "."),
+    ("slash", "/"),
+    ("zero", "0"),
+    ("one", "1"),
+    ("two", "2"),
+    ("three", "3"),
+    ("four", "4"),
+    ("five", "5"),
+    ("six", "6"),
+    ("seven", "7"),
+    ("eight", "8"),
+    ("nine", "9"),
+    ("colon", ":"),
+    ("semicolon", ";"),
+    ("less", "<"),
+    ("equal", "="),
+    ("greater", ">"),
+    ("question", "?"),
+    ("at", "@"),
+    ("bracketleft", "["),
+    ("backslash", "\\"),
+    ("bracketright", "]"),
+    ("asciicircum", "^"),
+    ("underscore", "_"),
+    ("grave", "`"),
+    ("braceleft", "{"),
+    ("bar", "|"),
+    ("braceright", "}"),
+    ("asciitilde", "~"),
+    // Old style digits
+    ("zerooldstyle", "0"),
+    ("oneoldstyle", "1"),
+    ("twooldstyle", "2"),
+    ("threeoldstyle", "3"),
+    ("fouroldstyle", "4"),
+    ("fiveoldstyle", "5"),
+    ("sixoldstyle", "6"),
+    ("sevenoldstyle", "7"),
+    ("eightoldstyle", "8"),
+    ("nineoldstyle", "9"),
+    // Accents
+    ("acute", "\u{B4}"),
+    ("breve", "\u{2D8}"),
+    ("caron", "\u{2C7}"),
+    ("cedilla", "\u{B8}"),
+    ("circumflex", "\u{2C6}"),
+    ("dieresis", "\u{A8}"),
+    ("dotaccent", "\u{2D9}"),
+    ("hungarumlaut", "\u{2DD}"),
+    ("macron", "\u{AF}"),
+    ("ogonek", "\u{2DB}"),
+    ("ring", "\u{2DA}"),
+    ("tilde", "\u{2DC}"),
+    ("tie", "\u{2040}"),
+    ("capitalgrave", "`"),
+    ("capitalacute", "\u{B4}"),
+    ("capitalcircumflex", "\u{2C6}"),
+    ("capitaltilde", "\u{2DC}"),
+    ("capitaldieresis", "\u{A8}"),
+    ("capitalhungarumlaut", "\u{2DD}"),
+    ("capitalring", "\u{2DA}"),
+    ("capitalcaron", "\u{2C7}"),
+    ("capitalbreve", "\u{2D8}"),
+    ("capitalmacron", "\u{AF}"),
+    ("capitaldotaccent", "\u{2D9}"),
+    ("capitaltie", "\u{2040}"),
+    ("newtie", "\u{2040}"),
+    ("capitalnewtie", "\u{2040}"),
+    ("tildelow", "\u{2DC}"),
+    // Ligatures
+    ("ff", "ff"),
+    ("fi", "fi"),
+    ("fl", "fl"),
+    ("ffi", "ffi"),
+    ("ffl", "ffl"),
+    ("IJ", "IJ"),
+    ("ij", "ij"),
+    ("SS", "SS"),
+    // Quotes and dashes
+    ("quoteleft", "\u{2018}"),
+    ("quoteright", "\u{2019}"),
+    ("quotesinglbase", "\u{201A}"),
+    ("quotedblleft", "\u{201C}"),
+    ("quotedblright", "\u{201D}"),
+    ("quotedblbase", "\u{201E}"),
+    ("quotestraightbase", "\u{201A}"),
+    ("quotestraightdblbase", "\u{201E}"),
+    ("guilsinglleft", "\u{2039}"),
+    ("guilsinglright", "\u{203A}"),
+    ("guillemotleft", "\u{AB}"),
+    ("guillemotright", "\u{BB}"),
+    ("endash", "\u{2013}"),
+    ("emdash", "\u{2014}"),
+    ("twelveudash", "\u{2013}"),
+    ("threequartersemdash", "\u{2014}"),
+    ("hyphendbl", "\u{2E40}"),
+    ("sfthyphen", "-"),
+    ("ellipsis", "\u{2026}"),
+    // Invisible marks
+    ("cwm", ""),
+    ("suppress", ""),
+    ("compwordmark", ""),
+    ("capitalcompwordmark", ""),
+    ("ascendercompwordmark", ""),
+    ("perthousandzero", "0"),
+    ("visiblespace", "\u{2423}"),
+    ("blank", "\u{2422}"),
+    // Latin-1
+    ("nbspace", "\u{A0}"),
+    ("exclamdown", "\u{A1}"),
+    ("cent", "\u{A2}"),
+    ("sterling", "\u{A3}"),
+    ("currency", "\u{A4}"),
+    ("yen", "\u{A5}"),
+    ("brokenbar", "\u{A6}"),
+    ("section", "\u{A7}"),
+    ("copyright", "\u{A9}"),
+    ("ordfeminine", "\u{AA}"),
+    ("logicalnot", "\u{AC}"),
+    ("registered", "\u{AE}"),
+    ("degree", "\u{B0}"),
+    ("plusminus", "\u{B1}"),
+    ("twosuperior", "\u{B2}"),
+    ("threesuperior", "\u{B3}"),
+    ("mu", "\u{B5}"),
+    ("paragraph", "\u{B6}"),
+    ("periodcentered", "\u{B7}"),
+    ("onesuperior", "\u{B9}"),
+    ("ordmasculine", "\u{BA}"),
+    ("onequarter", "\u{BC}"),
+    ("onehalf", "\u{BD}"),
+    ("threequarters", "\u{BE}"),
+    ("questiondown", "\u{BF}"),
+    ("Agrave", "\u{C0}"),
+    ("Aacute", "\u{C1}"),
+    ("Acircumflex", "\u{C2}"),
+    ("Atilde", "\u{C3}"),
+    ("Adieresis", "\u{C4}"),
+    ("Aring", "\u{C5}"),
+    ("AE", "\u{C6}"),
+    ("Ccedilla", "\u{C7}"),
+    ("Egrave", "\u{C8}"),
+    ("Eacute", "\u{C9}"),
+    ("Ecircumflex", "\u{CA}"),
+    ("Edieresis", "\u{CB}"),
+    ("Igrave", "\u{CC}"),
+    ("Iacute", "\u{CD}"),
+    ("Icircumflex", "\u{CE}"),
+    ("Idieresis", "\u{CF}"),
+    ("Eth", "\u{D0}"),
+    ("Ntilde", "\u{D1}"),
+    ("Ograve", "\u{D2}"),
+    ("Oacute", "\u{D3}"),
+    ("Ocircumflex", "\u{D4}"),
+    ("Otilde", "\u{D5}"),
+    ("Odieresis", "\u{D6}"),
+    ("multiply", "\u{D7}"),
+    ("Oslash", "\u{D8}"),
+    ("Ugrave", "\u{D9}"),
+    ("Uacute", "\u{DA}"),
+    ("Ucircumflex", "\u{DB}"),
+    ("Udieresis", "\u{DC}"),
+    ("Yacute", "\u{DD}"),
+    ("Thorn", "\u{DE}"),
+    ("germandbls", "\u{DF}"),
+    ("agrave", "\u{E0}"),
+    ("aacute", "\u{E1}"),
+    ("acircumflex", "\u{E2}"),
+    ("atilde", "\u{E3}"),
+    ("adieresis", "\u{E4}"),
+    ("aring", "\u{E5}"),
+    ("ae", "\u{E6}"),
+    ("ccedilla", "\u{E7}"),
+    ("egrave", "\u{E8}"),
+    ("eacute", "\u{E9}"),
+    ("ecircumflex", "\u{EA}"),
+    ("edieresis", "\u{EB}"),
+    ("igrave", "\u{EC}"),
+    ("iacute", "\u{ED}"),
+    ("icircumflex", "\u{EE}"),
+    ("idieresis", "\u{EF}"),
+    ("eth", "\u{F0}"),
+    ("ntilde", "\u{F1}"),
+    ("ograve", "\u{F2}"),
+    ("oacute", "\u{F3}"),
+    ("ocircumflex", "\u{F4}"),
+    ("otilde", "\u{F5}"),
+    ("odieresis", "\u{F6}"),
+    ("divide", "\u{F7}"),
+    ("oslash", "\u{F8}"),
+    ("ugrave", "\u{F9}"),
+    ("uacute", "\u{FA}"),
+    ("ucircumflex", "\u{FB}"),
+    ("udieresis", "\u{FC}"),
+    ("yacute", "\u{FD}"),
+    ("thorn", "\u{FE}"),
+    ("ydieresis", "\u{FF}"),
+    // Latin Extended-A
+    ("Abreve", "\u{102}"),
+    ("abreve", "\u{103}"),
+    ("Aogonek", "\u{104}"),
+    ("aogonek", "\u{105}"),
+    ("Cacute", "\u{106}"),
+    ("cacute", "\u{107}"),
+    ("Ccaron", "\u{10C}"),
+    ("ccaron", "\u{10D}"),
+    ("Dcaron", "\u{10E}"),
+    ("dcaron", "\u{10F}"),
+    ("Dcroat", "\u{110}"),
+    ("dcroat", "\u{111}"),
+    ("Eogonek", "\u{118}"),
+    ("eogonek", "\u{119}"),
+    ("Ecaron", "\u{11A}"),
+    ("ecaron", "\u{11B}"),
+    ("Gbreve", "\u{11E}"),
+    ("gbreve", "\u{11F}"),
+    ("Idotaccent", "\u{130}"),
+    ("dotlessi", "\u{131}"),
+    ("dotlessj", "\u{237}"),
+    ("Lacute", "\u{139}"),
+    ("lacute", "\u{13A}"),
+    ("Lcaron", "\u{13D}"),
+    ("lcaron", "\u{13E}"),
+    ("Lslash", "\u{141}"),
+    ("lslash", "\u{142}"),
+    ("Nacute", "\u{143}"),
+    ("nacute", "\u{144}"),
+    ("Ncaron", "\u{147}"),
+    ("ncaron", "\u{148}"),
+    ("Eng", "\u{14A}"),
+    ("eng", "\u{14B}"),
+    ("Ohungarumlaut", "\u{150}"),
+    ("ohungarumlaut", "\u{151}"),
+    ("OE", "\u{152}"),
+    ("oe", "\u{153}"),
+    ("Racute", "\u{154}"),
+    ("racute", "\u{155}"),
+    ("Rcaron", "\u{158}"),
+    ("rcaron", "\u{159}"),
+    ("Sacute", "\u{15A}"),
+    ("sacute", "\u{15B}"),
+    ("Scedilla", "\u{15E}"),
+    ("scedilla", "\u{15F}"),
+    ("Scaron", "\u{160}"),
+    ("scaron", "\u{161}"),
+    ("Tcommaaccent", "\u{162}"),
+    ("tcommaaccent", "\u{163}"),
+    ("Tcaron", "\u{164}"),
+    ("tcaron", "\u{165}"),
+    ("Uring", "\u{16E}"),
+    ("uring", "\u{16F}"),
+    ("Uhungarumlaut", "\u{170}"),
+    ("uhungarumlaut", "\u{171}"),
+    ("Ydieresis", "\u{178}"),
+    ("Zacute", "\u{179}"),
+    ("zacute", "\u{17A}"),
+    ("Zdotaccent", "\u{17B}"),
+    ("zdotaccent", "\u{17C}"),
+    ("Zcaron", "\u{17D}"),
+    ("zcaron", "\u{17E}"),
+    ("florin", "\u{192}"),
+    // Greek
+    ("Gamma", "\u{393}"),
+    ("Delta", "\u{394}"),
+    ("Theta", "\u{398}"),
+    ("Lambda", "\u{39B}"),
+    ("Xi", "\u{39E}"),
+    ("Pi", "\u{3A0}"),
+    ("Sigma", "\u{3A3}"),
+    ("Upsilon", "\u{3A5}"),
+    ("Upsilon1", "\u{3D2}"),
+    ("Phi", "\u{3A6}"),
+    ("Psi", "\u{3A8}"),
+    ("Omega", "\u{3A9}"),
+    ("alpha", "\u{3B1}"),
+    ("beta", "\u{3B2}"),
+    ("gamma", "\u{3B3}"),
+    ("delta", "\u{3B4}"),
+    ("epsilon", "\u{3B5}"),
+    ("epsilon1", "\u{3F5}"),
+    ("zeta", "\u{3B6}"),
+    ("eta", "\u{3B7}"),
+    ("theta", "\u{3B8}"),
+    ("theta1", "\u{3D1}"),
+    ("iota", "\u{3B9}"),
+    ("kappa", "\u{3BA}"),
+    ("lambda", "\u{3BB}"),
+    ("nu", "\u{3BD}"),
+    ("xi", "\u{3BE}"),
+    ("pi", "\u{3C0}"),
+    ("pi1", "\u{3D6}"),
+    ("rho", "\u{3C1}"),
+    ("rho1", "\u{3F1}"),
+    ("sigma", "\u{3C3}"),
+    ("sigma1", "\u{3C2}"),
+    ("tau", "\u{3C4}"),
+    ("upsilon", "\u{3C5}"),
+    ("phi", "\u{3D5}"),
+    ("phi1", "\u{3C6}"),
+    ("chi", "\u{3C7}"),
+    ("psi", "\u{3C8}"),
+    ("omega", "\u{3C9}"),
+    // Text symbols
+    ("dagger", "\u{2020}"),
+    ("daggerdbl", "\u{2021}"),
+    ("bullet", "\u{2022}"),
+    ("openbullet", "\u{2218}"),
+    ("perthousand", "\u{2030}"),
+    ("pertenthousand", "\u{2031}"),
+    ("fraction", "\u{2044}"),
+    ("asteriskcentered", "\u{204E}"),
+    ("referencemark", "\u{203B}"),
+    ("interrobang", "\u{203D}"),
+    ("interrobangdown", "\u{2E18}"),
+    ("Euro", "\u{20AC}"),
+    ("colonmonetary", "\u{20A1}"),
+    ("lira", "\u{20A4}"),
+    ("naira", "\u{20A6}"),
+    ("won", "\u{20A9}"),
+    ("dong", "\u{20AB}"),
+    ("peso", "\u{20B1}"),
+    ("guarani", "\u{20B2}"),
+    ("baht", "\u{E3F}"),
+    ("dollaroldstyle", "$"),
+    ("centoldstyle", "\u{A2}"),
+    ("celsius", "\u{2103}"),
+    ("numero", "\u{2116}"),
+    ("circledP", "\u{2117}"),
+    ("recipe", "\u{211E}"),
+    ("servicemark", "\u{2120}"),
+    ("trademark", "\u{2122}"),
+    ("ohm", "\u{2126}"),
+    ("mho", "\u{2127}"),
+    ("estimated", "\u{212E}"),
+    ("discount", "\u{2052}"),
+    ("copyleft", "\u{1F12F}"),
+    ("born", "\u{2B51}"),
+    ("died", "\u{271D}"),
+    ("divorced", "\u{26AE}"),
+    ("married", "\u{26AD}"),
+    ("leaf", "\u{1F670}"),
+    ("musicalnote", "\u{266A}"),
+    ("bigcircle", "\u{25EF}"),
+    ("angleleft", "\u{2329}"),
+    ("angleright", "\u{232A}"),
+    ("quillleft", "\u{2045}"),
+    ("quillright", "\u{2046}"),
+    ("dblbracketleft", "\u{27E6}"),
+    ("dblbracketright", "\u{27E7}"),
+    // Math italic
+    ("arrowlefttophalf", "\u{21BC}"),
+    ("arrowleftbothalf", "\u{21BD}"),
+    ("arrowrighttophalf", "\u{21C0}"),
+    ("arrowrightbothalf", "\u{21C1}"),
+    ("arrowhookleft", "\u{21A9}"),
+    ("arrowhookright", "\u{21AA}"),
+    ("triangleright", "\u{25B9}"),
+    ("triangleleft", "\u{25C3}"),
+    ("star", "\u{22C6}"),
+    ("partialdiff", "\u{2202}"),
+    ("flat", "\u{266D}"),
+    ("natural", "\u{266E}"),
+    ("sharp", "\u{266F}"),
+    ("slurbelow", "\u{2323}"),
+    ("slurabove", "\u{2322}"),
+    ("lscript", "\u{2113}"),
+    ("weierstrass", "\u{2118}"),
+    ("vector", "\u{20D7}"),
+    // Math symbols
+    ("minus", "\u{2212}"),
+    ("asteriskmath", "\u{2217}"),
+    ("diamondmath", "\u{22C4}"),
+    ("minusplus", "\u{2213}"),
+    ("circleplus", "\u{2295}"),
+    ("circleminus", "\u{2296}"),
+    ("circlemultiply", "\u{2297}"),
+    ("circledivide", "\u{2298}"),
+    ("circledot", "\u{2299}"),
+    ("circlecopyrt", "\u{25EF}"),
+    ("equivasymptotic", "\u{224D}"),
+    ("equivalence", "\u{2261}"),
+    ("reflexsubset", "\u{2286}"),
+    ("reflexsuperset", "\u{2287}"),
+    ("lessequal", "\u{2264}"),
+    ("greaterequal", "\u{2265}"),
+    ("precedesequal", "\u{2AAF}"),
+    ("followsequal", "\u{2AB0}"),
+    ("similar", "\u{223C}"),
+    ("approxequal", "\u{2248}"),
+    ("propersubset", "\u{2282}"),
+    ("propersuperset", "\u{2283}"),
+    ("lessmuch", "\u{226A}"),
+    ("greatermuch", "\u{226B}"),
+    ("precedes", "\u{227A}"),
+    ("follows", "\u{227B}"),
+    ("arrowleft", "\u{2190}"),
+    ("arrowup", "\u{2191}"),
+    ("arrowright", "\u{2192}"),
+    ("arrowdown", "\u{2193}"),
+    ("arrowboth", "\u{2194}"),
+    ("arrowbothv", "\u{2195}"),
+    ("arrownorthwest", "\u{2196}"),
+    ("arrownortheast", "\u{2197}"),
+    ("arrowsoutheast", "\u{2198}"),
+    ("arrowsouthwest", "\u{2199}"),
+    ("similarequal", "\u{2243}"),
+    ("arrowdblleft", "\u{21D0}"),
+    ("arrowdblup", "\u{21D1}"),
+    ("arrowdblright", "\u{21D2}"),
+    ("arrowdbldown", "\u{21D3}"),
+    ("arrowdblboth", "\u{21D4}"),
+    ("arrowdblbothv", "\u{21D5}"),
+    ("proportional", "\u{221D}"),
+    ("prime", "\u{2032}"),
+    ("infinity", "\u{221E}"),
+    ("element", "\u{2208}"),
+    ("owner", "\u{220B}"),
+    ("triangle", "\u{25B3}"),
+    ("triangleinv", "\u{25BD}"),
+    ("negationslash", "\u{338}"),
+    ("mapsto", "\u{21A6}"),
+    ("universal", "\u{2200}"),
+    ("existential", "\u{2203}"),
+    ("emptyset", "\u{2205}"),
+    ("Rfractur", "\u{211C}"),
+    ("Ifractur", "\u{2111}"),
+    ("latticetop", "\u{22A4}"),
+    ("perpendicular", "\u{22A5}"),
+    ("aleph", "\u{2135}"),
+    ("union", "\u{222A}"),
+    ("intersection", "\u{2229}"),
+    ("unionmulti", "\u{228E}"),
+    ("logicaland", "\u{2227}"),
+    ("logicalor", "\u{2228}"),
+    ("turnstileleft", "\u{22A2}"),
+    ("turnstileright", "\u{22A3}"),
+    ("floorleft", "\u{230A}"),
+    ("floorright", "\u{230B}"),
+    ("ceilingleft", "\u{2308}"),
+    ("ceilingright", "\u{2309}"),
+    ("angbracketleft", "\u{27E8}"),
+    ("angbracketright", "\u{27E9}"),
+    ("bardbl", "\u{2016}"),
+    ("wreathproduct", "\u{2240}"),
+    ("radical", "\u{221A}"),
+    ("coproduct", "\u{2A3F}"),
+    ("nabla", "\u{2207}"),
+    ("integral", "\u{222B}"),
+    ("unionsq", "\u{2294}"),
+    ("intersectionsq", "\u{2293}"),
+    ("subsetsqequal", "\u{2291}"),
+    ("supersetsqequal", "\u{2292}"),
+    ("club", "\u{2663}"),
+    ("diamond", "\u{2662}"),
+    ("heart", "\u{2661}"),
+    ("spade", "\u{2660}"),
+    // Pieces of delimiters and big operators of math extension fonts
+    ("parenlefttp", "\u{239B}"),
+    ("parenleftex", "\u{239C}"),
+    ("parenleftbt", "\u{239D}"),
+    ("parenrighttp", "\u{239E}"),
+    ("parenrightex", "\u{239F}"),
+    ("parenrightbt", "\u{23A0}"),
+    ("bracketlefttp", "\u{23A1}"),
+    ("bracketleftex", "\u{23A2}"),
+    ("bracketleftbt", "\u{23A3}"),
+    ("bracketrighttp", "\u{23A4}"),
+    ("bracketrightex", "\u{23A5}"),
+    ("bracketrightbt", "\u{23A6}"),
+    ("bracelefttp", "\u{23A7}"),
+    ("braceleftmid", "\u{23A8}"),
+    ("braceleftbt", "\u{23A9}"),
+    ("braceex", "\u{23AA}"),
+    ("bracerighttp", "\u{23AB}"),
+    ("bracerightmid", "\u{23AC}"),
+    ("bracerightbt", "\u{23AD}"),
+    ("radicalbt", "\u{23B7}"),
+    ("arrowvertex", "\u{23D0}"),
+    ("arrowvertexdbl", "\u{2016}"),
+    ("arrowtp", "\u{2191}"),
+    ("arrowbt", "\u{2193}"),
+    ("arrowdbltp", "\u{21D1}"),
+    ("arrowdblbt", "\u{21D3}"),
+    ("vextendsingle", "|"),
+    ("vextenddouble", "\u{2016}"),
+    ("contintegral", "\u{222E}"),
+    ("circledotdisplay", "\u{2A00}"),
+    ("circleplusdisplay", "\u{2A01}"),
+    ("circlemultiplydisplay", "\u{2A02}"),
+    ("summation", "\u{2211}"),
+    ("product", "\u{220F}"),
+    ("uniondisplay", "\u{22C3}"),
+    ("intersectiondisplay", "\u{22C2}"),
+    ("unionmultidisplay", "\u{2A04}"),
+    ("logicalanddisplay", "\u{22C0}"),
+    ("logicalordisplay", "\u{22C1}"),
+    ("unionsqdisplay", "\u{2A06}"),
+    ("coproductdisplay", "\u{2210}"),
+    ("hatwide", "\u{2C6}"),
+    ("tildewide", "\u{2DC}"),
+];
+
+#[cfg(test)]
+mod tests {
+    use crate::fonts::glyph_names::*;
+
+    #[test]
+    fn test_known_names() {
+        assert_eq!(glyph_to_unicode("A"), Some("A".to_string()));
+        assert_eq!(
+            glyph_to_unicode("quotedblleft"),
+            Some("\u{201C}".to_string())
+        );
+        assert_eq!(glyph_to_unicode("Lslash"), Some("Ł".to_string()));
+        assert_eq!(glyph_to_unicode("cwm"), Some(String::new()));
+    }
+
+    #[test]
+    fn test_ligatures_are_decomposed() {
+        assert_eq!(glyph_to_unicode("fi"), Some("fi".to_string()));
+        assert_eq!(glyph_to_unicode("f_f_l"), Some("ffl".to_string()));
+    }
+
+    #[test]
+    fn test_code_points() {
+        assert_eq!(glyph_to_unicode("uni00E9"), Some("é".to_string()));
+        assert_eq!(glyph_to_unicode("uni00660069"), Some("fi".to_string()));
+        assert_eq!(glyph_to_unicode("u1D49C"), Some("\u{1D49C}".to_string()));
+        assert_eq!(glyph_to_unicode("uni00e9"), None);
+    }
+
+    #[test]
+    fn test_non_ascii_code_points() {
+        assert_eq!(glyph_to_unicode("uniaaaéaé"), None);
+        assert_eq!(glyph_to_unicode("uéé"), None);
+    }
+
+    #[test]
+    fn test_variants_fall_back_to_base_name() {
+        assert_eq!(glyph_to_unicode("a.sc"), Some("a".to_string()));
+        assert_eq!(glyph_to_unicode("zero.oldstyle"), Some("0".to_string()));
+    }
+
+    #[test]
+    fn test_unknown_names() {
+        assert_eq!(glyph_to_unicode(".notdef"), None);
+        assert_eq!(glyph_to_unicode("nonexisting"), None);
+    }
+}
@@ -0,0 +1,423 @@
+use crate::errors::{DviousError, DviousResult};
+use crate::fonts::tfm::{TexFontMetric, TfmCharInfoTag};
+use crate::util::num::FixWord;
+
+/// Ligature programs revisit characters, but never this many times per character
+/// unless they loop forever
+const MAX_STEPS_PER_CHARACTER: usize = 1000;
+
+/// The result of the lig/kern program of a pair of characters
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LigKern {
+    /// A kern in units of the design size, to be scaled like a width
+    Kern(FixWord),
+    /// Inserts the character `c` between the pair, keeps the left and right character
+    /// or replaces them, and then passes over `skip` characters. In the notation of
+    /// TFtoPL, `|` marks the kept characters and `>` the skipped ones: `=:` and `=:|`,
+    /// `|=:`, `|=:|`, `=:|>`, `|=:>`, `|=:|>` and `|=:|>>`.
+    Ligature {
+        c: u8,
+        keep_left: bool,
+        keep_right: bool,
+        skip: u8,
+    },
+}
+
+/// A character or a kern in the result of `TexFontMetric::lig_kern_string`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LigKernItem {
+    Char(u8),
+    Kern(FixWord),
+}
+
+/// A character of a word or one of its boundaries
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Slot {
+    Char(u8),
+    Boundary,
+}
+
+impl TexFontMetric {
+    /// Runs the lig/kern program of `left` for the character `right`
+    pub fn lig_kern(&self, left: u8, right: u8) -> Option<LigKern> {
+        let start = match self.char_info_table.get(&left)?.tag {
+            TfmCharInfoTag::Ligature(start) => usize::from(start),
+            _ => return None,
+        };
+        let first = self.lig_kern_table.get(start)?;
+        let start = if first.skip_byte > 128 {
+            256 * usize::from(first.op_byte) + usize::from(first.remainder)
+        } else {
+            start
+        };
+        self.run_lig_kern_program(start, right)
+    }
+
+    /// The character that stands for the end of a word in lig/kern programs, if the
+    /// font has one
+    pub fn right_boundary(&self) -> Option<u8> {
+        self.lig_kern_table
+            .first()
+            .filter(|command| command.skip_byte == 255)
+            .map(|command| command.next_char)
+    }
+
+    /// Runs the lig/kern program for the beginning of a word, if the font has one,
+    /// for the first character `right`
+    pub fn left_boundary_lig_kern(&self, right: u8) -> Option<LigKern> {
+        let start = self.left_boundary_program()?;
+        self.run_lig_kern_program(start, right)
+    }
+
+    /// Applies the ligatures and kerns of the font to a word like TeX does, including
+    /// the programs of the left and right boundary. Programs that never finish are
+    /// reported as `LigKernLoopError`.
+    pub fn lig_kern_string(&self, word: &[u8]) -> DviousResult<Vec<LigKernItem>> {
+        let mut slots: Vec<Slot> = word.iter().map(|&c| Slot::Char(c)).collect();
+        if self.left_boundary_program().is_some() {
+            slots.insert(0, Slot::Boundary);
+        }
+        if self.right_boundary().is_some() {
+            slots.push(Slot::Boundary);
+        }
+
+        let mut items = Vec::new();
+        let emit = |items: &mut Vec<LigKernItem>, slot: Slot| {
+            if let Slot::Char(c) = slot {
+                items.push(LigKernItem::Char(c));
+            }
+        };
+
+        let max_steps = MAX_STEPS_PER_CHARACTER * slots.len();
+        let mut position = 0;
+        let mut steps = 0;
+        while position < slots.len() {
+            steps += 1;
+            if steps > max_steps {
+                return Err(DviousError::LigKernLoopError(format!(
+                    "Lig/kern programs loop infinitely for the word [{}]",
+                    String::from_utf8_lossy(word)
+                )));
+            }
+
+            let left = slots[position];
+            let action = match (left, slots.get(position + 1)) {
+                (Slot::Char(left), Some(&Slot::Char(right))) => self.lig_kern(left, right),
+                (Slot::Char(left), Some(&Slot::Boundary)) => self
+                    .right_boundary()
+                    .and_then(|right| self.lig_kern(left, right)),
+                (Slot::Boundary, Some(&Slot::Char(right))) => self.left_boundary_lig_kern(right),
+                _ => None,
+            };
+
+            match action {
+                None => {
+                    emit(&mut items, left);
+                    position += 1;
+                }
+                Some(LigKern::Kern(kern)) => {
+                    emit(&mut items, left);
+                    items.push(LigKernItem::Kern(kern));
+                    position += 1;
+                }
+                Some(LigKern::Ligature {
+                    c,
+                    keep_left,
+                    keep_right,
+                    skip,
+                }) => {
+                    match (keep_left, keep_right) {
+                        (false, false) => {
+                            slots[position] = Slot::Char(c);
+                            slots.remove(position + 1);
+                        }
+                        (false, true) => slots[position] = Slot::Char(c),
+                        (true, false) => slots[position + 1] = Slot::Char(c),
+                        (true, true) => slots.insert(position + 1, Slot::Char(c)),
+                    }
+                    for _ in 0..skip {
+                        emit(&mut items, slots[position]);
+                        position += 1;
+                    }
+                }
+            }
+        }
+        Ok(items)
+    }
+
+    fn left_boundary_program(&self) -> Option<usize> {
+        self.lig_kern_table
+            .last()
+            .filter(|command| command.skip_byte == 255)
+            .map(|command| 256 * usize::from(command.op_byte) + usize::from(command.remainder))
+    }
+
+    fn run_lig_kern_program(&self, start: usize, right: u8) -> Option<LigKern> {
+        let mut index = start;
+        loop {
+            let command = self.lig_kern_table.get(index)?;
+            if command.next_char == right && command.skip_byte <= 128 {
+                return self.lig_kern_action(command.op_byte, command.remainder);
+            }
+            if command.skip_byte >= 128 {
+                return None;
+            }
+            index += usize::from(command.skip_byte) + 1;
+        }
+    }
+
+    fn lig_kern_action(&self, op_byte: u8, remainder: u8) -> Option<LigKern> {
+        if op_byte >= 128 {
+            let index = 256 * usize::from(op_byte - 128) + usize::from(remainder);
+            return self.kern_table.get(index).map(|&kern| LigKern::Kern(kern));
+        }
+
+        let keep_left = op_byte & 2 != 0;
+        let keep_right = op_byte & 1 != 0;
+        let skip = op_byte >> 2;
+        // Only the kept characters can be passed over
+        if skip > u8::from(keep_left) + u8::from(keep_right) {
+            return None;
+        }
+        Some(LigKern::Ligature {
+            c: remainder,
+            keep_left,
+            keep_right,
+            skip,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::DviousError;
+    use crate::fonts::lig_kern::*;
+    use crate::fonts::tfm::builder::TfmBuilder;
+    use crate::fonts::tfm::*;
+
+    const FF: u8 = 0x0B;
+    const FI: u8 = 0x0C;
+    const FFI: u8 = 0x0E;
+
+    #[test]
+    fn test_lig_kern_ligature() {
+        let tfm = ligature_font();
+
+        assert_eq!(tfm.lig_kern(b'f', b'i'), Some(ligature(FI, 0)));
+        assert_eq!(tfm.lig_kern(b'f', b'f'), Some(ligature(FF, 0)));
+        assert_eq!(tfm.lig_kern(FF, b'i'), Some(ligature(FFI, 0)));
+    }
+
+    #[test]
+    fn test_lig_kern_kern() {
+        let tfm = ligature_font();
+
+        assert_eq!(
+            tfm.lig_kern(b'A', b'V'),
+            Some(LigKern::Kern(FixWord::from_f64(-0.125)))
+        );
+        assert_eq!(tfm.lig_kern(b'A', b'W'), None);
+        assert_eq!(tfm.lig_kern(b'i', b'f'), None);
+    }
+
+    #[test]
+    fn test_lig_kern_stops_at_skip_byte_of_128() {
+        let tfm = ligature_font();
+
+        // The program of `f` ends before the instruction for `l`
+        assert_eq!(tfm.lig_kern(b'f', b'l'), None);
+    }
+
+    #[test]
+    fn test_lig_kern_redirected_program() {
+        let tfm = ligature_font();
+
+        // The program of `V` starts at 256 * 0 + 5, which is the program of `A`
+        assert_eq!(
+            tfm.lig_kern(b'V', b'V'),
+            Some(LigKern::Kern(FixWord::from_f64(-0.125)))
+        );
+    }
+
+    #[test]
+    fn test_lig_kern_ops() {
+        let mut tfm = ligature_font();
+        for &(op_byte, keep_left, keep_right, skip) in &[
+            (0, false, false, 0),
+            (1, false, true, 0),
+            (2, true, false, 0),
+            (3, true, true, 0),
+            (5, false, true, 1),
+            (6, true, false, 1),
+            (7, true, true, 1),
+            (11, true, true, 2),
+        ] {
+            tfm.lig_kern_table[2].op_byte = op_byte;
+
+            assert_eq!(
+                tfm.lig_kern(b'f', b'i'),
+                Some(LigKern::Ligature {
+                    c: FI,
+                    keep_left,
+                    keep_right,
+                    skip
+                })
+            );
+        }
+
+        // `=:>` would pass over a character that was replaced
+        tfm.lig_kern_table[2].op_byte = 4;
+        assert_eq!(tfm.lig_kern(b'f', b'i'), None);
+    }
+
+    #[test]
+    fn test_boundaries() {
+        let mut tfm = ligature_font();
+        assert_eq!(tfm.right_boundary(), None);
+        assert_eq!(tfm.left_boundary_lig_kern(b'A'), None);
+
+        with_boundaries(&mut tfm);
+
+        assert_eq!(tfm.right_boundary(), Some(b'|'));
+        assert_eq!(
+            tfm.left_boundary_lig_kern(b'A'),
+            Some(LigKern::Kern(FixWord::from_f64(0.25)))
+        );
+    }
+
+    #[test]
+    fn test_lig_kern_string() {
+        let tfm = ligature_font();
+
+        let items = tfm.lig_kern_string(b"AVffi").unwrap();
+
+        assert_eq!(
+            items,
+            vec![
+                LigKernItem::Char(b'A'),
+                LigKernItem::Kern(FixWord::from_f64(-0.125)),
+                LigKernItem::Char(b'V'),
+                LigKernItem::Char(FFI),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lig_kern_string_keeps_and_skips() {
+        let mut tfm = ligature_font();
+        // `f` `i` becomes `f` `fi` `i`, and `fi` is passed over, so that `fi` `i`
+        // does not form another ligature
+        tfm.lig_kern_table[2].op_byte = 7;
+
+        let items = tfm.lig_kern_string(b"fi").unwrap();
+
+        assert_eq!(
+            items,
+            vec![
+                LigKernItem::Char(b'f'),
+                LigKernItem::Char(FI),
+                LigKernItem::Char(b'i'),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lig_kern_string_with_boundaries() {
+        let mut tfm = ligature_font();
+        with_boundaries(&mut tfm);
+
+        let items = tfm.lig_kern_string(b"AA").unwrap();
+
+        assert_eq!(
+            items,
+            vec![
+                LigKernItem::Kern(FixWord::from_f64(0.25)),
+                LigKernItem::Char(b'A'),
+                LigKernItem::Char(b'A'),
+                LigKernItem::Kern(FixWord::from_f64(0.5)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lig_kern_string_detects_loops() {
+        let mut tfm = ligature_font();
+        // `f` `i` becomes `f` `f` `i` forever
+        tfm.lig_kern_table[2].op_byte = 2;
+        tfm.lig_kern_table[2].remainder = b'f';
+        tfm.lig_kern_table[1].op_byte = 1;
+        tfm.lig_kern_table[1].remainder = b'f';
+
+        let result = tfm.lig_kern_string(b"fi");
+
+        assert!(matches!(result, Err(DviousError::LigKernLoopError(_))));
+    }
+
+    fn ligature(c: u8, skip: u8) -> LigKern {
+        LigKern::Ligature {
+            c,
+            keep_left: false,
+            keep_right: false,
+            skip,
+        }
+    }
+
+    fn command(skip_byte: u8, next_char: u8, op_byte: u8, remainder: u8) -> TfmLigatureCommand {
+        TfmLigatureCommand {
+            skip_byte,
+            next_char,
+            op_byte,
+            remainder,
+        }
+    }
+
+    /// Makes `|` the right boundary, which is kerned after `A`, and adds a program
+    /// for the left boundary that kerns before `A`
+    fn with_boundaries(tfm: &mut TexFontMetric) {
+        tfm.lig_kern_table[0] = command(255, b'|', 0, 0);
+        tfm.lig_kern_table[6] = command(128, b'|', 128, 2);
+        tfm.lig_kern_table.push(command(128, b'A', 128, 3));
+        let left = tfm.lig_kern_table.len() as u8;
+        tfm.lig_kern_table.push(command(255, 0, 0, left - 1));
+        tfm.kern_table.push(FixWord::from_f64(0.5));
+        tfm.kern_table.push(FixWord::from_f64(0.25));
+    }
+
+    fn ligature_font() -> TexFontMetric {
+        let tags = vec![
+            (b'f', TfmCharInfoTag::Ligature(1)),
+            (FF, TfmCharInfoTag::Ligature(4)),
+            (b'A', TfmCharInfoTag::Ligature(5)),
+            (b'V', TfmCharInfoTag::Ligature(0)),
+            (b'i', TfmCharInfoTag::None),
+            (b'l', TfmCharInfoTag::None),
+            (b'W', TfmCharInfoTag::None),
+            (b'|', TfmCharInfoTag::None),
+            (FI, TfmCharInfoTag::None),
+            (FFI, TfmCharInfoTag::None),
+        ];
+        let mut builder = TfmBuilder::new();
+        for (c, tag) in tags {
+            builder = builder
+                .char(c, FixWord::from_f64(0.5), FixWord::ZERO, FixWord::ZERO)
+                .tag(c, tag);
+        }
+        builder
+            .lig_kern(vec![
+                // `V` is redirected to the program of `A`
+                command(129, 0, 0, 5),
+                // `f`
+                command(0, b'f', 0, FF),
+                command(128, b'i', 0, FI),
+                // Never reached, as the program of `f` stopped before
+                command(128, b'l', 0, 0),
+                // `ff`
+                command(128, b'i', 0, FFI),
+                // `A`
+                command(0, b'V', 128, 0),
+                command(128, b'l', 0, 0),
+            ])
+            .kerns(&[FixWord::from_f64(-0.125), FixWord::ZERO])
+            .build()
+    }
+}
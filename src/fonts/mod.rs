@@ -1,2 +1,9 @@
+pub mod delimiters;
+pub mod encoding;
+pub mod glyph_names;
 pub mod kpsewhich;
+pub mod lig_kern;
+pub mod params;
+pub mod resolver;
 pub mod tfm;
+pub mod verification;
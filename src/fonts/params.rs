@@ -0,0 +1,327 @@
+use crate::fonts::encoding::BuiltinEncoding;
+use crate::fonts::tfm::TexFontMetric;
+use crate::util::num::FixWord;
+
+/// Families of fonts with the parameters of TeX's math symbol fonts, like cmsy10
+const MATH_SYMBOL_FAMILIES: &[&str] = &["CMSY", "CMBSY", "LMSY", "LMBSY"];
+
+/// Families of fonts with the parameters of TeX's math extension fonts, like cmex10
+const MATH_EXTENSION_FAMILIES: &[&str] = &["CMEX", "LMEX", "EUEX"];
+
+/// The meaning of the parameters after the seven that all fonts share
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParameterSet {
+    /// Only `slant` to `extra_space`, possibly followed by parameters TeX ignores
+    Text,
+    /// The 22 parameters of the math symbol font of family 2, from `num1` on
+    MathSymbols,
+    /// The 13 parameters of the math extension font of family 3, from
+    /// `default_rule_thickness` on
+    MathExtension,
+}
+
+impl TexFontMetric {
+    /// Detects the parameter set from the coding scheme of the font, e.g.
+    /// `TeX math symbols`, or from its family, e.g. `CMEX`
+    pub fn parameter_set(&self) -> ParameterSet {
+        let scheme = self
+            .header
+            .encoding
+            .as_deref()
+            .and_then(BuiltinEncoding::from_coding_scheme);
+        match scheme {
+            Some(BuiltinEncoding::Oms) => return ParameterSet::MathSymbols,
+            Some(BuiltinEncoding::Omx) => return ParameterSet::MathExtension,
+            _ => {}
+        }
+
+        let family = self
+            .header
+            .font_identifier
+            .as_deref()
+            .unwrap_or_default()
+            .to_ascii_uppercase();
+        if MATH_SYMBOL_FAMILIES.contains(&family.as_str()) {
+            ParameterSet::MathSymbols
+        } else if MATH_EXTENSION_FAMILIES.contains(&family.as_str()) {
+            ParameterSet::MathExtension
+        } else {
+            ParameterSet::Text
+        }
+    }
+
+    /// The parameter with the given number, counting from 1 like in the TFM format
+    pub fn param(&self, number: usize) -> Option<FixWord> {
+        number
+            .checked_sub(1)
+            .and_then(|index| self.param_table.get(index))
+            .copied()
+    }
+
+    /// The slant per unit of height, which is not scaled by the size of the font
+    pub fn slant(&self) -> Option<FixWord> {
+        self.param(1)
+    }
+
+    pub fn space(&self) -> Option<FixWord> {
+        self.param(2)
+    }
+
+    pub fn space_stretch(&self) -> Option<FixWord> {
+        self.param(3)
+    }
+
+    pub fn space_shrink(&self) -> Option<FixWord> {
+        self.param(4)
+    }
+
+    pub fn x_height(&self) -> Option<FixWord> {
+        self.param(5)
+    }
+
+    pub fn quad(&self) -> Option<FixWord> {
+        self.param(6)
+    }
+
+    pub fn extra_space(&self) -> Option<FixWord> {
+        self.param(7)
+    }
+
+    pub fn num1(&self) -> Option<FixWord> {
+        self.math_symbol_param(8)
+    }
+
+    pub fn num2(&self) -> Option<FixWord> {
+        self.math_symbol_param(9)
+    }
+
+    pub fn num3(&self) -> Option<FixWord> {
+        self.math_symbol_param(10)
+    }
+
+    pub fn denom1(&self) -> Option<FixWord> {
+        self.math_symbol_param(11)
+    }
+
+    pub fn denom2(&self) -> Option<FixWord> {
+        self.math_symbol_param(12)
+    }
+
+    pub fn sup1(&self) -> Option<FixWord> {
+        self.math_symbol_param(13)
+    }
+
+    pub fn sup2(&self) -> Option<FixWord> {
+        self.math_symbol_param(14)
+    }
+
+    pub fn sup3(&self) -> Option<FixWord> {
+        self.math_symbol_param(15)
+    }
+
+    pub fn sub1(&self) -> Option<FixWord> {
+        self.math_symbol_param(16)
+    }
+
+    pub fn sub2(&self) -> Option<FixWord> {
+        self.math_symbol_param(17)
+    }
+
+    pub fn sup_drop(&self) -> Option<FixWord> {
+        self.math_symbol_param(18)
+    }
+
+    pub fn sub_drop(&self) -> Option<FixWord> {
+        self.math_symbol_param(19)
+    }
+
+    pub fn delim1(&self) -> Option<FixWord> {
+        self.math_symbol_param(20)
+    }
+
+    pub fn delim2(&self) -> Option<FixWord> {
+        self.math_symbol_param(21)
+    }
+
+    pub fn axis_height(&self) -> Option<FixWord> {
+        self.math_symbol_param(22)
+    }
+
+    pub fn default_rule_thickness(&self) -> Option<FixWord> {
+        self.math_extension_param(8)
+    }
+
+    pub fn big_op_spacing1(&self) -> Option<FixWord> {
+        self.math_extension_param(9)
+    }
+
+    pub fn big_op_spacing2(&self) -> Option<FixWord> {
+        self.math_extension_param(10)
+    }
+
+    pub fn big_op_spacing3(&self) -> Option<FixWord> {
+        self.math_extension_param(11)
+    }
+
+    pub fn big_op_spacing4(&self) -> Option<FixWord> {
+        self.math_extension_param(12)
+    }
+
+    pub fn big_op_spacing5(&self) -> Option<FixWord> {
+        self.math_extension_param(13)
+    }
+
+    fn math_symbol_param(&self, number: usize) -> Option<FixWord> {
+        match self.parameter_set() {
+            ParameterSet::MathSymbols => self.param(number),
+            _ => None,
+        }
+    }
+
+    fn math_extension_param(&self, number: usize) -> Option<FixWord> {
+        match self.parameter_set() {
+            ParameterSet::MathExtension => self.param(number),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fonts::params::*;
+    use crate::fonts::tfm::builder::TfmBuilder;
+
+    #[test]
+    fn test_parameter_set_from_coding_scheme() {
+        let cmr10 = TfmBuilder::new()
+            .encoding("TeX text")
+            .font_identifier("CMR")
+            .params(&numbers(7))
+            .build();
+        let cmsy10 = TfmBuilder::new()
+            .encoding("TeX math symbols")
+            .params(&numbers(22))
+            .build();
+        let cmex10 = TfmBuilder::new()
+            .encoding("TeX math extension")
+            .params(&numbers(13))
+            .build();
+
+        assert_eq!(cmr10.parameter_set(), ParameterSet::Text);
+        assert_eq!(cmsy10.parameter_set(), ParameterSet::MathSymbols);
+        assert_eq!(cmex10.parameter_set(), ParameterSet::MathExtension);
+    }
+
+    #[test]
+    fn test_parameter_set_from_family() {
+        let cmbsy10 = TfmBuilder::new()
+            .encoding("unspecified")
+            .font_identifier("CMBSY")
+            .params(&numbers(22))
+            .build();
+        let cmex10 = TfmBuilder::new()
+            .font_identifier("cmex")
+            .params(&numbers(13))
+            .build();
+        let unknown = TfmBuilder::new().params(&numbers(22)).build();
+
+        assert_eq!(cmbsy10.parameter_set(), ParameterSet::MathSymbols);
+        assert_eq!(cmex10.parameter_set(), ParameterSet::MathExtension);
+        assert_eq!(unknown.parameter_set(), ParameterSet::Text);
+    }
+
+    #[test]
+    fn test_text_params() {
+        let tfm = TfmBuilder::new()
+            .encoding("TeX text")
+            .font_identifier("CMR")
+            .params(&numbers(7))
+            .build();
+
+        assert_eq!(tfm.param(0), None);
+        assert_eq!(tfm.slant(), Some(number(1)));
+        assert_eq!(tfm.space(), Some(number(2)));
+        assert_eq!(tfm.space_stretch(), Some(number(3)));
+        assert_eq!(tfm.space_shrink(), Some(number(4)));
+        assert_eq!(tfm.x_height(), Some(number(5)));
+        assert_eq!(tfm.quad(), Some(number(6)));
+        assert_eq!(tfm.extra_space(), Some(number(7)));
+        assert_eq!(tfm.param(8), None);
+    }
+
+    #[test]
+    fn test_missing_text_params() {
+        let tfm = TfmBuilder::new().params(&numbers(2)).build();
+
+        assert_eq!(tfm.space(), Some(number(2)));
+        assert_eq!(tfm.space_stretch(), None);
+        assert_eq!(tfm.extra_space(), None);
+    }
+
+    #[test]
+    fn test_math_symbol_params() {
+        let tfm = TfmBuilder::new()
+            .encoding("TeX math symbols")
+            .font_identifier("CMSY")
+            .params(&numbers(22))
+            .build();
+
+        assert_eq!(tfm.quad(), Some(number(6)));
+        assert_eq!(tfm.num1(), Some(number(8)));
+        assert_eq!(tfm.num2(), Some(number(9)));
+        assert_eq!(tfm.num3(), Some(number(10)));
+        assert_eq!(tfm.denom1(), Some(number(11)));
+        assert_eq!(tfm.denom2(), Some(number(12)));
+        assert_eq!(tfm.sup1(), Some(number(13)));
+        assert_eq!(tfm.sup2(), Some(number(14)));
+        assert_eq!(tfm.sup3(), Some(number(15)));
+        assert_eq!(tfm.sub1(), Some(number(16)));
+        assert_eq!(tfm.sub2(), Some(number(17)));
+        assert_eq!(tfm.sup_drop(), Some(number(18)));
+        assert_eq!(tfm.sub_drop(), Some(number(19)));
+        assert_eq!(tfm.delim1(), Some(number(20)));
+        assert_eq!(tfm.delim2(), Some(number(21)));
+        assert_eq!(tfm.axis_height(), Some(number(22)));
+        assert_eq!(tfm.default_rule_thickness(), None);
+    }
+
+    #[test]
+    fn test_math_extension_params() {
+        let tfm = TfmBuilder::new()
+            .encoding("TeX math extension")
+            .font_identifier("CMEX")
+            .params(&numbers(13))
+            .build();
+
+        assert_eq!(tfm.default_rule_thickness(), Some(number(8)));
+        assert_eq!(tfm.big_op_spacing1(), Some(number(9)));
+        assert_eq!(tfm.big_op_spacing2(), Some(number(10)));
+        assert_eq!(tfm.big_op_spacing3(), Some(number(11)));
+        assert_eq!(tfm.big_op_spacing4(), Some(number(12)));
+        assert_eq!(tfm.big_op_spacing5(), Some(number(13)));
+        assert_eq!(tfm.num1(), None);
+    }
+
+    #[test]
+    fn test_math_params_of_text_fonts() {
+        // Text fonts may have more than seven parameters, but they mean nothing to TeX
+        let tfm = TfmBuilder::new()
+            .encoding("TeX text")
+            .font_identifier("CMR")
+            .params(&numbers(22))
+            .build();
+
+        assert_eq!(tfm.param(8), Some(number(8)));
+        assert_eq!(tfm.num1(), None);
+        assert_eq!(tfm.axis_height(), None);
+        assert_eq!(tfm.default_rule_thickness(), None);
+    }
+
+    fn number(n: usize) -> FixWord {
+        FixWord::from_f64(n as f64 / 16.0)
+    }
+
+    fn numbers(n: usize) -> Vec<FixWord> {
+        (1..=n).map(number).collect()
+    }
+}
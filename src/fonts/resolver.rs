@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::errors::{DviousError, DviousResult};
+use crate::fonts::kpsewhich;
+use crate::fonts::tfm::{read_tfm_from_file, TexFontMetric};
+
+/// Finds the metrics of the fonts defined in a DVI file. Besides the name, the
+/// checksum, scale and design size of the `fnt_def` are passed along, so that
+/// resolvers can pick between several versions of a font.
+pub trait FontResolver {
+    fn resolve(
+        &mut self,
+        name: &str,
+        checksum: u32,
+        scale: u32,
+        design_size: u32,
+    ) -> DviousResult<TexFontMetric>;
+}
+
+/// Finds TFM files with kpsewhich, like the TeX tools do
+#[derive(Debug, Default)]
+pub struct KpsewhichResolver;
+
+impl FontResolver for KpsewhichResolver {
+    fn resolve(&mut self, name: &str, _: u32, _: u32, _: u32) -> DviousResult<TexFontMetric> {
+        read_tfm_from_file(kpsewhich::get_path_to_tfm(name)?)
+    }
+}
+
+/// Reads `<name>.tfm` from a single directory
+#[derive(Debug)]
+pub struct DirectoryResolver {
+    directory: PathBuf,
+}
+
+impl DirectoryResolver {
+    pub fn new<P: Into<PathBuf>>(directory: P) -> DirectoryResolver {
+        DirectoryResolver {
+            directory: directory.into(),
+        }
+    }
+}
+
+impl FontResolver for DirectoryResolver {
+    fn resolve(&mut self, name: &str, _: u32, _: u32, _: u32) -> DviousResult<TexFontMetric> {
+        let path = self.directory.join(format!("{}.tfm", name));
+        if !path.is_file() {
+            return Err(DviousError::FontNotFoundError(format!(
+                "No TFM file for [{}] in [{}]",
+                name,
+                self.directory.display()
+            )));
+        }
+        read_tfm_from_file(path.to_string_lossy().into_owned())
+    }
+}
+
+/// Hands out copies of metrics that were added by name
+#[derive(Debug, Default)]
+pub struct MemoryResolver {
+    fonts: HashMap<String, TexFontMetric>,
+}
+
+impl MemoryResolver {
+    pub fn new() -> MemoryResolver {
+        Default::default()
+    }
+
+    pub fn insert<S: Into<String>>(&mut self, name: S, tfm: TexFontMetric) {
+        self.fonts.insert(name.into(), tfm);
+    }
+}
+
+impl FontResolver for MemoryResolver {
+    fn resolve(&mut self, name: &str, _: u32, _: u32, _: u32) -> DviousResult<TexFontMetric> {
+        match self.fonts.get(name) {
+            Some(tfm) => Ok(tfm.clone()),
+            None => Err(DviousError::FontNotFoundError(format!(
+                "No metrics for [{}]",
+                name
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::DviousError;
+    use crate::fonts::resolver::*;
+    use crate::fonts::tfm::builder::TfmBuilder;
+
+    #[test]
+    fn test_memory_resolver() {
+        let cmr10 = TfmBuilder::new().checksum(0xDEAD).build();
+        let mut resolver = MemoryResolver::new();
+        resolver.insert("cmr10", cmr10.clone());
+
+        let tfm = resolver.resolve("cmr10", 0, 655360, 655360).unwrap();
+
+        assert_eq!(tfm, cmr10);
+    }
+
+    #[test]
+    fn test_memory_resolver_missing_font() {
+        let mut resolver = MemoryResolver::new();
+
+        let result = resolver.resolve("cmr10", 0, 655360, 655360);
+
+        assert!(matches!(result, Err(DviousError::FontNotFoundError(_))));
+    }
+
+    #[test]
+    fn test_directory_resolver_missing_font() {
+        let mut resolver = DirectoryResolver::new("tests/data");
+
+        let result = resolver.resolve("nonexisting", 0, 655360, 655360);
+
+        assert!(matches!(result, Err(DviousError::FontNotFoundError(_))));
+    }
+}
@@ -1,36 +1,42 @@
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::fs::File;
 use std::io::prelude::*;
+use std::io::Cursor;
 
 use crate::errors::{DviousError, DviousResult};
 use crate::util::byte_reader::ByteReader;
-use crate::util::num::Fixword;
+use crate::util::dimension::Dimension;
+use crate::util::num::FixWord;
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct TexFontMetric {
     pub header: TfmMetricHeader,
     pub char_info_table: HashMap<u8, TfmCharInfo>,
-    pub width_table: Vec<Fixword>,
-    pub heigth_table: Vec<Fixword>,
-    pub depth_table: Vec<Fixword>,
-    pub italic_table: Vec<Fixword>,
+    pub width_table: Vec<FixWord>,
+    pub heigth_table: Vec<FixWord>,
+    pub depth_table: Vec<FixWord>,
+    pub italic_table: Vec<FixWord>,
     pub lig_kern_table: Vec<TfmLigatureCommand>,
-    pub kern_table: Vec<Fixword>,
+    pub kern_table: Vec<FixWord>,
     pub extension_table: Vec<TfmExtensionRecipe>,
-    pub param_table: Vec<Fixword>,
+    pub param_table: Vec<FixWord>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct TfmMetricHeader {
     pub checksum: u32,
-    pub design_size: Fixword,
+    pub design_size: FixWord,
     pub encoding: Option<String>,
     pub font_identifier: Option<String>,
+    /// Whether the lig/kern programs never produce characters above 127 from ones
+    /// below 128, as flagged in the high bit of the face word
+    pub seven_bit_safe: bool,
     pub face: Option<u8>,
     pub misc: Vec<u8>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct TfmCharInfo {
     pub character: u8,
     pub width_index: u8,
@@ -40,7 +46,7 @@ pub struct TfmCharInfo {
     pub tag: TfmCharInfoTag,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum TfmCharInfoTag {
     None,
     Ligature(u8),
@@ -48,7 +54,7 @@ pub enum TfmCharInfoTag {
     Extensible(u8),
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct TfmLigatureCommand {
     pub skip_byte: u8,
     pub next_char: u8,
@@ -56,7 +62,7 @@ pub struct TfmLigatureCommand {
     pub remainder: u8,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct TfmExtensionRecipe {
     pub top: u8,
     pub mid: u8,
@@ -64,18 +70,222 @@ pub struct TfmExtensionRecipe {
     pub rep: u8,
 }
 
+/// The dimensions of a character scaled to the size of a font
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CharMetrics {
+    pub width: Dimension,
+    pub height: Dimension,
+    pub depth: Dimension,
+    pub italic: Dimension,
+}
+
+impl TexFontMetric {
+    /// The dimensions of the character `c` in a font loaded at `at_size`. Returns
+    /// `None` if the character does not exist, i.e. its width index is 0, or if its
+    /// dimensions are out of the tables or can not be scaled to `at_size`.
+    pub fn char_metrics(&self, c: u8, at_size: Dimension) -> Option<CharMetrics> {
+        let info = self.char_info_table.get(&c)?;
+        if info.width_index == 0 {
+            return None;
+        }
+        let at_size = i32::try_from(at_size.raw()).ok()?;
+
+        let scale = |table: &[FixWord], index: u8| {
+            let value = table.get(usize::from(index))?;
+            value.scale(at_size).map(Dimension::from)
+        };
+        Some(CharMetrics {
+            width: scale(&self.width_table, info.width_index)?,
+            height: scale(&self.heigth_table, info.height_index)?,
+            depth: scale(&self.depth_table, info.depth_index)?,
+            italic: scale(&self.italic_table, info.italic_index)?,
+        })
+    }
+}
+
 struct TfmMetricReader {
-    reader: ByteReader,
+    reader: ByteReader<Cursor<Vec<u8>>>,
 }
 
 pub fn read_tfm_from_file(path: String) -> DviousResult<TexFontMetric> {
     let mut buffer = Vec::new();
     let mut file = File::open(&path)?;
     file.read_to_end(&mut buffer)?;
-    let mut tfm_reader = TfmMetricReader::new(buffer);
+    read_tfm(buffer)
+}
+
+pub fn read_tfm(bytes: Vec<u8>) -> DviousResult<TexFontMetric> {
+    let mut tfm_reader = TfmMetricReader::new(bytes);
     tfm_reader.read()
 }
 
+/// Serializes a font metric into a TFM file. The lengths of the file and its tables
+/// are computed from the tables, and characters between the smallest and largest
+/// code that are missing from `char_info_table` are written as nonexistent.
+///
+/// The header fields are positional, so that an absent encoding or font identifier
+/// followed by a present field is written as an empty string, and an absent face as 0.
+pub fn write_tfm(tfm: &TexFontMetric) -> DviousResult<Vec<u8>> {
+    let header = encode_header(&tfm.header)?;
+    let lh = header.len() / 4;
+    let (bc, ec) = match (
+        tfm.char_info_table.keys().min(),
+        tfm.char_info_table.keys().max(),
+    ) {
+        (Some(&bc), Some(&ec)) => (usize::from(bc), usize::from(ec)),
+        // A font without characters has bc = ec + 1
+        _ => (1, 0),
+    };
+
+    // Indices into the first four tables and the extension table are packed into
+    // the char info words, the other tables are only bounded by the length of the file
+    let lengths = [
+        lh,
+        bc,
+        ec,
+        table_length("width", tfm.width_table.len(), 256)?,
+        table_length("height", tfm.heigth_table.len(), 16)?,
+        table_length("depth", tfm.depth_table.len(), 16)?,
+        table_length("italic", tfm.italic_table.len(), 64)?,
+        tfm.lig_kern_table.len(),
+        tfm.kern_table.len(),
+        table_length("extension", tfm.extension_table.len(), 256)?,
+        tfm.param_table.len(),
+    ];
+    let lf = 6 + lengths[0] + (ec + 1 - bc) + lengths[3..].iter().sum::<usize>();
+    if lf > usize::from(u16::MAX) {
+        return Err(DviousError::TfmEncodeError(format!(
+            "TFM would have [{}] words, which is more than the allowed 65535",
+            lf
+        )));
+    }
+
+    let mut out = Vec::with_capacity(4 * lf);
+    for &length in [lf].iter().chain(lengths.iter()) {
+        out.extend_from_slice(&(length as u16).to_be_bytes());
+    }
+    out.extend_from_slice(&header);
+    for c in bc..=ec {
+        match tfm.char_info_table.get(&(c as u8)) {
+            Some(char_info) => out.extend_from_slice(&encode_char_info(char_info)?),
+            None => out.extend_from_slice(&[0; 4]),
+        }
+    }
+    write_fixword_table(&tfm.width_table, &mut out);
+    write_fixword_table(&tfm.heigth_table, &mut out);
+    write_fixword_table(&tfm.depth_table, &mut out);
+    write_fixword_table(&tfm.italic_table, &mut out);
+    for command in &tfm.lig_kern_table {
+        out.extend_from_slice(&[
+            command.skip_byte,
+            command.next_char,
+            command.op_byte,
+            command.remainder,
+        ]);
+    }
+    write_fixword_table(&tfm.kern_table, &mut out);
+    for recipe in &tfm.extension_table {
+        out.extend_from_slice(&[recipe.top, recipe.mid, recipe.bot, recipe.rep]);
+    }
+    write_fixword_table(&tfm.param_table, &mut out);
+
+    debug_assert_eq!(out.len(), 4 * lf);
+    Ok(out)
+}
+
+fn table_length(table: &str, length: usize, max: usize) -> DviousResult<usize> {
+    if length > max {
+        return Err(DviousError::TfmEncodeError(format!(
+            "TFM {} table has [{}] entries, which is more than the allowed {}",
+            table, length, max
+        )));
+    }
+    Ok(length)
+}
+
+fn encode_header(header: &TfmMetricHeader) -> DviousResult<Vec<u8>> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&header.checksum.to_be_bytes());
+    out.extend_from_slice(&header.design_size.raw().to_be_bytes());
+
+    let has_misc = !header.misc.is_empty();
+    let has_face = header.face.is_some() || header.seven_bit_safe || has_misc;
+    let has_font_identifier = header.font_identifier.is_some() || has_face;
+    if header.encoding.is_some() || has_font_identifier {
+        let encoding = header.encoding.as_deref().unwrap_or_default();
+        encode_pascal_string("encoding", encoding, 40, &mut out)?;
+    }
+    if has_font_identifier {
+        let font_identifier = header.font_identifier.as_deref().unwrap_or_default();
+        encode_pascal_string("font identifier", font_identifier, 20, &mut out)?;
+    }
+    if has_face {
+        let flag = if header.seven_bit_safe { 0x80 } else { 0 };
+        out.extend_from_slice(&[flag, 0, 0, header.face.unwrap_or_default()]);
+    }
+
+    if !header.misc.len().is_multiple_of(4) {
+        return Err(DviousError::TfmEncodeError(format!(
+            "TFM header has [{}] additional bytes, which are not a multiple of 4",
+            header.misc.len()
+        )));
+    }
+    out.extend_from_slice(&header.misc);
+    Ok(out)
+}
+
+/// Writes `s` prefixed by its length and padded with zeros to `field_len` bytes
+fn encode_pascal_string(
+    field: &str,
+    s: &str,
+    field_len: usize,
+    out: &mut Vec<u8>,
+) -> DviousResult<()> {
+    if s.len() >= field_len {
+        return Err(DviousError::TfmEncodeError(format!(
+            "TFM header {} [{}] has more than the allowed {} bytes",
+            field,
+            s,
+            field_len - 1
+        )));
+    }
+    out.push(s.len() as u8);
+    out.extend_from_slice(s.as_bytes());
+    out.resize(out.len() + field_len - 1 - s.len(), 0);
+    Ok(())
+}
+
+fn encode_char_info(char_info: &TfmCharInfo) -> DviousResult<[u8; 4]> {
+    if char_info.height_index > 0x0F
+        || char_info.depth_index > 0x0F
+        || char_info.italic_index > 0x3F
+    {
+        return Err(DviousError::TfmEncodeError(format!(
+            "Indices of character [{}] do not fit into a char info word",
+            char_info.character
+        )));
+    }
+
+    let (tag, remainder) = match char_info.tag {
+        TfmCharInfoTag::None => (0, 0),
+        TfmCharInfoTag::Ligature(remainder) => (1, remainder),
+        TfmCharInfoTag::List(remainder) => (2, remainder),
+        TfmCharInfoTag::Extensible(remainder) => (3, remainder),
+    };
+    Ok([
+        char_info.width_index,
+        char_info.height_index << 4 | char_info.depth_index,
+        char_info.italic_index << 2 | tag,
+        remainder,
+    ])
+}
+
+fn write_fixword_table(table: &[FixWord], out: &mut Vec<u8>) {
+    for fixword in table {
+        out.extend_from_slice(&fixword.raw().to_be_bytes());
+    }
+}
+
 impl TfmMetricReader {
     fn new(bytes: Vec<u8>) -> TfmMetricReader {
         TfmMetricReader {
@@ -205,16 +415,16 @@ impl TfmMetricReader {
             Option::None
         };
 
-        // Face
-        let face = if words_read < header_size {
-            self.reader.read_be::<u8>()?;
+        // Seven bit safe flag and face
+        let (seven_bit_safe, face) = if words_read < header_size {
+            let flag = self.reader.read_be::<u8>()?;
             self.reader.read_be::<u16>()?;
             let face_byte = self.reader.read_be::<u8>()?;
 
             words_read += 1;
-            Option::Some(face_byte)
+            (flag & 0x80 != 0, Option::Some(face_byte))
         } else {
-            Option::None
+            (false, Option::None)
         };
 
         // Misc
@@ -231,6 +441,7 @@ impl TfmMetricReader {
             design_size,
             encoding,
             font_identifier,
+            seven_bit_safe,
             face,
             misc,
         })
@@ -239,16 +450,16 @@ impl TfmMetricReader {
     fn read_char_info_table(&mut self, bc: u8, ec: u8) -> DviousResult<HashMap<u8, TfmCharInfo>> {
         let mut result = HashMap::new();
 
-        for character in bc..ec + 1 {
+        for character in bc..=ec {
             let first_byte = self.reader.read_be::<u8>()?;
             let second_byte = self.reader.read_be::<u8>()?;
             let third_byte = self.reader.read_be::<u8>()?;
             let fourth_byte = self.reader.read_be::<u8>()?;
 
             let width_index = first_byte;
-            let height_index = (second_byte >> 4) * 16;
+            let height_index = second_byte >> 4;
             let depth_index = second_byte & 0x0F;
-            let italic_index = (third_byte >> 2) * 4;
+            let italic_index = third_byte >> 2;
             let tag_value = third_byte & 0b0000_0011;
             let remainder = fourth_byte;
 
@@ -322,13 +533,12 @@ impl TfmMetricReader {
         Ok(result)
     }
 
-    fn read_fixword(&mut self) -> DviousResult<Fixword> {
-        let f64_factor = 2.0_f64.powi(-20);
+    fn read_fixword(&mut self) -> DviousResult<FixWord> {
         let b = self.reader.read_be::<i32>()?;
-        Ok(f64::from(b) * f64_factor)
+        Ok(FixWord::from_raw(b))
     }
 
-    fn read_fixword_table(&mut self, number_of_fixwords: u16) -> DviousResult<Vec<Fixword>> {
+    fn read_fixword_table(&mut self, number_of_fixwords: u16) -> DviousResult<Vec<FixWord>> {
         let n = number_of_fixwords as usize;
         let mut result = Vec::with_capacity(n);
         for _ in 0..n {
@@ -345,14 +555,162 @@ impl TfmMetricReader {
     }
 }
 
+/// Builds font metrics in memory for the tests. Every dimension is stored once in
+/// its table, and the characters get the indices of their dimensions.
+#[cfg(test)]
+pub(crate) mod builder {
+    use std::collections::HashMap;
+
+    use crate::fonts::tfm::*;
+
+    pub(crate) struct TfmBuilder {
+        tfm: TexFontMetric,
+    }
+
+    impl TfmBuilder {
+        /// Starts with a design size of 10pt, no characters and tables that only
+        /// hold the zero entries every TFM file begins with
+        pub(crate) fn new() -> TfmBuilder {
+            TfmBuilder {
+                tfm: TexFontMetric {
+                    header: TfmMetricHeader {
+                        checksum: 0,
+                        design_size: FixWord::from_f64(10.0),
+                        encoding: None,
+                        font_identifier: None,
+                        seven_bit_safe: false,
+                        face: None,
+                        misc: Vec::new(),
+                    },
+                    char_info_table: HashMap::new(),
+                    width_table: vec![FixWord::ZERO],
+                    heigth_table: vec![FixWord::ZERO],
+                    depth_table: vec![FixWord::ZERO],
+                    italic_table: vec![FixWord::ZERO],
+                    lig_kern_table: vec![],
+                    kern_table: vec![],
+                    extension_table: vec![],
+                    param_table: vec![],
+                },
+            }
+        }
+
+        pub(crate) fn checksum(mut self, checksum: u32) -> TfmBuilder {
+            self.tfm.header.checksum = checksum;
+            self
+        }
+
+        pub(crate) fn encoding(mut self, encoding: &str) -> TfmBuilder {
+            self.tfm.header.encoding = Some(encoding.to_string());
+            self
+        }
+
+        pub(crate) fn font_identifier(mut self, font_identifier: &str) -> TfmBuilder {
+            self.tfm.header.font_identifier = Some(font_identifier.to_string());
+            self
+        }
+
+        /// Adds the character `c`. Its width gets an entry other than the first,
+        /// so that characters of width zero exist as well.
+        pub(crate) fn char(
+            mut self,
+            c: u8,
+            width: FixWord,
+            height: FixWord,
+            depth: FixWord,
+        ) -> TfmBuilder {
+            let info = TfmCharInfo {
+                character: c,
+                width_index: table_index(&mut self.tfm.width_table, 1, width),
+                height_index: table_index(&mut self.tfm.heigth_table, 0, height),
+                depth_index: table_index(&mut self.tfm.depth_table, 0, depth),
+                italic_index: 0,
+                tag: TfmCharInfoTag::None,
+            };
+            self.tfm.char_info_table.insert(c, info);
+            self
+        }
+
+        /// Adds `c` as a character that does not exist, like the ones in the gaps
+        /// between `bc` and `ec`
+        pub(crate) fn missing(mut self, c: u8) -> TfmBuilder {
+            let info = TfmCharInfo {
+                character: c,
+                width_index: 0,
+                height_index: 0,
+                depth_index: 0,
+                italic_index: 0,
+                tag: TfmCharInfoTag::None,
+            };
+            self.tfm.char_info_table.insert(c, info);
+            self
+        }
+
+        /// Sets the italic correction of the character `c`, which has to be added before
+        pub(crate) fn italic(mut self, c: u8, italic: FixWord) -> TfmBuilder {
+            let index = table_index(&mut self.tfm.italic_table, 0, italic);
+            self.tfm.char_info_table.get_mut(&c).unwrap().italic_index = index;
+            self
+        }
+
+        /// Sets the tag of the character `c`, which has to be added before
+        pub(crate) fn tag(mut self, c: u8, tag: TfmCharInfoTag) -> TfmBuilder {
+            self.tfm.char_info_table.get_mut(&c).unwrap().tag = tag;
+            self
+        }
+
+        pub(crate) fn lig_kern(mut self, commands: Vec<TfmLigatureCommand>) -> TfmBuilder {
+            self.tfm.lig_kern_table = commands;
+            self
+        }
+
+        pub(crate) fn kerns(mut self, kerns: &[FixWord]) -> TfmBuilder {
+            self.tfm.kern_table = kerns.to_vec();
+            self
+        }
+
+        pub(crate) fn extensible(mut self, recipes: Vec<TfmExtensionRecipe>) -> TfmBuilder {
+            self.tfm.extension_table = recipes;
+            self
+        }
+
+        /// Sets the parameters, starting with `slant`
+        pub(crate) fn params(mut self, params: &[FixWord]) -> TfmBuilder {
+            self.tfm.param_table = params.to_vec();
+            self
+        }
+
+        pub(crate) fn build(self) -> TexFontMetric {
+            self.tfm
+        }
+    }
+
+    /// The index of `value` in `table` from `start` on, which is appended if missing
+    fn table_index(table: &mut Vec<FixWord>, start: usize, value: FixWord) -> u8 {
+        let index = match table.iter().skip(start).position(|&v| v == value) {
+            Some(position) => start + position,
+            None => {
+                table.push(value);
+                table.len() - 1
+            }
+        };
+        assert!(
+            index <= usize::from(u8::MAX),
+            "Table has more than [256] entries"
+        );
+        index as u8
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::fonts::tfm::builder::TfmBuilder;
     use crate::fonts::tfm::*;
 
     // Sanity checks
 
     #[test]
-    #[cfg_attr(rustfmt, rustfmt_skip)]
+    #[rustfmt::skip]
     fn test_read_preamble_invalid_character_code_range() {
         let data = vec![
             0x00, 0x08,
@@ -384,7 +742,7 @@ mod tests {
     // Header
 
     #[test]
-    #[cfg_attr(rustfmt, rustfmt_skip)]
+    #[rustfmt::skip]
     fn test_read_header() {
         let data = vec![
             0xAA, 0xBB, 0xCC, 0xDD,
@@ -401,7 +759,7 @@ mod tests {
             0x43, 0x41, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
             0x00, 0x00, 0x00, 0x00,
 
-            0x00, 0x00, 0x00, 0x12,
+            0x80, 0x00, 0x00, 0x12,
 
             0xAA, 0xBB, 0xCC, 0xDD
         ];
@@ -414,9 +772,10 @@ mod tests {
             header,
             TfmMetricHeader {
                 checksum: 0xAABBCCDD,
-                design_size: 10.0,
+                design_size: FixWord::from_f64(10.0),
                 encoding: Some("Test".to_string()),
                 font_identifier: Some("HELVETICA".to_string()),
+                seven_bit_safe: true,
                 face: Option::Some(0x12),
                 misc: vec![0xAA, 0xBB, 0xCC, 0xDD]
             }
@@ -426,6 +785,7 @@ mod tests {
     // Char info
 
     #[test]
+    #[allow(clippy::unusual_byte_groupings)]
     fn test_read_charinfo() {
         let data = vec![0x42, 0xAB, 0b101010_10, 0xCD, 0x23, 0xCD, 0b010101_01, 0xEF];
         let mut tfm_reader = TfmMetricReader::new(data);
@@ -442,9 +802,9 @@ mod tests {
             TfmCharInfo {
                 character: 0x60,
                 width_index: 0x42,
-                height_index: 0xA * 16,
+                height_index: 0xA,
                 depth_index: 0xB,
-                italic_index: 42 * 4,
+                italic_index: 42,
                 tag: TfmCharInfoTag::List(0xCD),
             }
         );
@@ -453,14 +813,43 @@ mod tests {
             TfmCharInfo {
                 character: 0x61,
                 width_index: 0x23,
-                height_index: 0xC * 16,
+                height_index: 0xC,
                 depth_index: 0xD,
-                italic_index: 21 * 4,
+                italic_index: 21,
                 tag: TfmCharInfoTag::Ligature(0xEF),
             }
         );
     }
 
+    #[test]
+    #[rustfmt::skip]
+    #[allow(clippy::unusual_byte_groupings)]
+    fn test_read_charinfo_indices_into_tables() {
+        let data = vec![
+            0x00, 0x10, 0x00, 0x02, 0x00, 0x41, 0x00, 0x41,
+            0x00, 0x02, 0x00, 0x02, 0x00, 0x01, 0x00, 0x02,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            // Header
+            0x00, 0x00, 0x00, 0x00,
+            0x00, 0xA0, 0x00, 0x00,
+            // Height 1, depth 0 and italic correction 1
+            0x01, 0x10, 0b000001_00, 0x00,
+            // Widths, heights, depths and italic corrections
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00,
+        ];
+        let mut tfm_reader = TfmMetricReader::new(data);
+
+        let tfm = tfm_reader.read().unwrap();
+
+        let info = &tfm.char_info_table[&0x41];
+        assert_eq!(tfm.heigth_table[usize::from(info.height_index)].to_f64(), 0.5);
+        assert_eq!(tfm.depth_table[usize::from(info.depth_index)].to_f64(), 0.0);
+        assert_eq!(tfm.italic_table[usize::from(info.italic_index)].to_f64(), 0.125);
+    }
+
     #[test]
     fn test_read_charinfo_tag_none() {
         let data = vec![];
@@ -565,7 +954,8 @@ mod tests {
 
         let fixword = tfm_reader.read_fixword().unwrap();
 
-        assert_eq!(fixword, -2048.0);
+        assert_eq!(fixword, FixWord::from_raw(i32::MIN));
+        assert_eq!(fixword.to_f64(), -2048.0);
     }
 
     #[test]
@@ -574,11 +964,12 @@ mod tests {
 
         let fixword = tfm_reader.read_fixword().unwrap();
 
-        assert_eq!(fixword, 2048.0 - 2.0_f64.powi(-20));
+        assert_eq!(fixword, FixWord::from_raw(i32::MAX));
+        assert_eq!(fixword.to_f64(), 2048.0 - 2.0_f64.powi(-20));
     }
 
     #[test]
-    #[cfg_attr(rustfmt, rustfmt_skip)]
+    #[rustfmt::skip]
     fn test_read_fixword_table() {
         let data = vec![
             0x80, 0x00, 0x00, 0x00,
@@ -589,7 +980,8 @@ mod tests {
 
         let fixword_table = tfm_reader.read_fixword_table(3).unwrap();
 
-        assert_eq!(fixword_table, vec![-2048.0, 0.0, 0.6708984375]);
+        let values: Vec<f64> = fixword_table.iter().map(|f| f.to_f64()).collect();
+        assert_eq!(values, vec![-2048.0, 0.0, 0.6708984375]);
     }
 
     // Read string
@@ -602,4 +994,253 @@ mod tests {
 
         assert_eq!(result, "Test".to_string());
     }
+
+    // Char metrics
+
+    #[test]
+    fn test_char_metrics() {
+        let tfm = char_metrics_fixture();
+
+        let metrics = tfm.char_metrics(0x41, Dimension::new(655_360)).unwrap();
+
+        assert_eq!(
+            metrics,
+            CharMetrics {
+                width: Dimension::new(655_360),
+                height: Dimension::new(327_680),
+                depth: Dimension::new(163_840),
+                italic: Dimension::new(81_920),
+            }
+        );
+    }
+
+    #[test]
+    fn test_char_metrics_scaled_to_size() {
+        let tfm = char_metrics_fixture();
+
+        let metrics = tfm.char_metrics(0x41, Dimension::new(2 * 655_360)).unwrap();
+
+        assert_eq!(metrics.width, Dimension::new(1_310_720));
+        assert_eq!(metrics.italic, Dimension::new(163_840));
+    }
+
+    #[test]
+    fn test_char_metrics_of_missing_characters() {
+        let mut tfm = char_metrics_fixture();
+        let mut invalid = tfm.char_info_table[&0x41].clone();
+        invalid.character = 0x43;
+        invalid.height_index = 2;
+        tfm.char_info_table.insert(0x43, invalid);
+
+        // Not in the table, without a width and with a height out of the table
+        let at_size = Dimension::new(655_360);
+        assert_eq!(tfm.char_metrics(0x42, at_size), None);
+        assert_eq!(tfm.char_metrics(0x20, at_size), None);
+        assert_eq!(tfm.char_metrics(0x43, at_size), None);
+    }
+
+    #[test]
+    fn test_char_metrics_at_invalid_size() {
+        let tfm = char_metrics_fixture();
+
+        assert_eq!(tfm.char_metrics(0x41, Dimension::new(-1)), None);
+        assert_eq!(tfm.char_metrics(0x41, Dimension::new(0o1000000000)), None);
+        assert_eq!(tfm.char_metrics(0x41, Dimension::new(1 << 40)), None);
+    }
+
+    fn char_metrics_fixture() -> TexFontMetric {
+        TfmBuilder::new()
+            .char(
+                0x41,
+                FixWord::ONE,
+                FixWord::from_f64(0.5),
+                FixWord::from_f64(0.25),
+            )
+            .italic(0x41, FixWord::from_f64(0.125))
+            .missing(0x20)
+            .build()
+    }
+
+    // Writing
+
+    #[test]
+    fn test_write_tfm_round_trip() {
+        let tfm = write_fixture();
+
+        let bytes = write_tfm(&tfm).unwrap();
+
+        assert_eq!(read_tfm(bytes).unwrap(), tfm);
+    }
+
+    #[test]
+    fn test_write_tfm_lengths() {
+        let bytes = write_tfm(&write_fixture()).unwrap();
+
+        let lengths: Vec<u16> = bytes[..24]
+            .chunks(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+            .collect();
+        // lf, lh, bc, ec, nw, nh, nd, ni, nl, nk, ne and np, where lf includes 191
+        // char info words
+        assert_eq!(lengths, vec![237, 19, 0x41, 0xFF, 3, 2, 2, 2, 2, 2, 1, 7]);
+        assert_eq!(bytes.len(), 237 * 4);
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    #[allow(clippy::unusual_byte_groupings)]
+    fn test_write_tfm_char_info() {
+        let bytes = write_tfm(&write_fixture()).unwrap();
+
+        // The char info words follow the 6 words of lengths and 19 of the header
+        let char_info = &bytes[(6 + 19) * 4..];
+        assert_eq!(&char_info[..8], &[
+            0x01, 0x11, 0b000001_01, 0x00,
+            0x00, 0x00, 0x00, 0x00,
+        ]);
+        assert_eq!(&char_info[(0xFF - 0x41) * 4..][..4], &[0x02, 0x10, 0b000000_11, 0x00]);
+    }
+
+    #[test]
+    fn test_write_tfm_minimal_header() {
+        let mut tfm = write_fixture();
+        tfm.header.encoding = None;
+        tfm.header.font_identifier = None;
+        tfm.header.seven_bit_safe = false;
+        tfm.header.face = None;
+        tfm.header.misc = Vec::new();
+
+        let bytes = write_tfm(&tfm).unwrap();
+
+        assert_eq!(&bytes[2..4], &[0, 2]);
+        assert_eq!(read_tfm(bytes).unwrap(), tfm);
+    }
+
+    #[test]
+    fn test_write_tfm_seven_bit_safe_flag() {
+        let mut tfm = write_fixture();
+
+        // The face word is the 18th word of the header, which starts at byte 24
+        assert_eq!(&write_tfm(&tfm).unwrap()[92..96], &[0x80, 0, 0, 0xEA]);
+
+        tfm.header.seven_bit_safe = false;
+        assert_eq!(&write_tfm(&tfm).unwrap()[92..96], &[0, 0, 0, 0xEA]);
+    }
+
+    #[test]
+    fn test_write_tfm_fills_absent_header_fields() {
+        let mut tfm = write_fixture();
+        tfm.header.encoding = None;
+        tfm.header.seven_bit_safe = false;
+        tfm.header.face = None;
+        tfm.header.misc = Vec::new();
+
+        let header = read_tfm(write_tfm(&tfm).unwrap()).unwrap().header;
+
+        assert_eq!(header.encoding, Some(String::new()));
+        assert_eq!(header.font_identifier, Some("TEST".to_string()));
+        assert_eq!(header.face, None);
+    }
+
+    #[test]
+    fn test_write_tfm_patched_kern() {
+        let mut tfm = write_fixture();
+        tfm.kern_table[1] = FixWord::from_f64(-0.25);
+
+        let patched = read_tfm(write_tfm(&tfm).unwrap()).unwrap();
+
+        assert_eq!(patched.kern_table[1], FixWord::from_f64(-0.25));
+    }
+
+    #[test]
+    fn test_write_tfm_without_characters() {
+        let mut tfm = write_fixture();
+        tfm.char_info_table.clear();
+
+        let bytes = write_tfm(&tfm).unwrap();
+
+        assert_eq!(&bytes[4..8], &[0, 1, 0, 0]);
+    }
+
+    #[test]
+    fn test_write_tfm_invalid() {
+        let mut long_encoding = write_fixture();
+        long_encoding.header.encoding = Some("X".repeat(40));
+        let mut long_identifier = write_fixture();
+        long_identifier.header.font_identifier = Some("X".repeat(20));
+        let mut odd_misc = write_fixture();
+        odd_misc.header.misc = vec![1, 2, 3];
+        let mut large_index = write_fixture();
+        large_index
+            .char_info_table
+            .get_mut(&0x41)
+            .unwrap()
+            .height_index = 16;
+        let mut large_table = write_fixture();
+        large_table.italic_table = vec![FixWord::ZERO; 65];
+
+        for tfm in &[
+            long_encoding,
+            long_identifier,
+            odd_misc,
+            large_index,
+            large_table,
+        ] {
+            assert!(matches!(
+                write_tfm(tfm),
+                Err(DviousError::TfmEncodeError(_))
+            ));
+        }
+    }
+
+    fn write_fixture() -> TexFontMetric {
+        let (half, three_quarters) = (FixWord::from_f64(0.5), FixWord::from_f64(0.75));
+        let mut builder = TfmBuilder::new();
+        // Nonexistent, as the reader returns the characters in the gaps
+        for c in 0x42..0xFF {
+            builder = builder.missing(c);
+        }
+        let mut tfm = builder
+            .checksum(0xDEAD_BEEF)
+            .encoding("TeX text")
+            .font_identifier("TEST")
+            .char(0x41, half, three_quarters, FixWord::from_f64(0.25))
+            .italic(0x41, FixWord::from_f64(0.125))
+            .tag(0x41, TfmCharInfoTag::Ligature(0))
+            .char(0x43, FixWord::ONE, three_quarters, FixWord::ZERO)
+            .tag(0x43, TfmCharInfoTag::List(0xFF))
+            .char(0xFF, FixWord::ONE, three_quarters, FixWord::ZERO)
+            .tag(0xFF, TfmCharInfoTag::Extensible(0))
+            .lig_kern(vec![
+                TfmLigatureCommand {
+                    skip_byte: 0,
+                    next_char: 0x43,
+                    op_byte: 0,
+                    remainder: 0xFF,
+                },
+                TfmLigatureCommand {
+                    skip_byte: 128,
+                    next_char: 0x41,
+                    op_byte: 128,
+                    remainder: 1,
+                },
+            ])
+            .kerns(&[FixWord::ZERO, FixWord::from_f64(-0.125)])
+            .extensible(vec![TfmExtensionRecipe {
+                top: 0x43,
+                mid: 0,
+                bot: 0x41,
+                rep: 0xFF,
+            }])
+            .params(
+                &(0..7)
+                    .map(|i| FixWord::from_f64(f64::from(i) / 8.0))
+                    .collect::<Vec<_>>(),
+            )
+            .build();
+        tfm.header.seven_bit_safe = true;
+        tfm.header.face = Some(0xEA);
+        tfm.header.misc = vec![1, 2, 3, 4];
+        tfm
+    }
 }
@@ -0,0 +1,157 @@
+use std::fmt;
+
+use crate::dvi::units::{TEX_DENOMINATOR, TEX_NUMERATOR};
+use crate::fonts::tfm::TexFontMetric;
+
+/// A disagreement between a `fnt_def` and the TFM file found for it
+#[derive(Clone, Debug, PartialEq)]
+pub enum FontWarning {
+    ChecksumMismatch {
+        font: String,
+        dvi: u32,
+        tfm: u32,
+    },
+    /// The design sizes in DVI units
+    DesignSizeMismatch {
+        font: String,
+        dvi: u32,
+        tfm: i64,
+    },
+}
+
+impl fmt::Display for FontWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FontWarning::ChecksumMismatch { ref font, dvi, tfm } => write!(
+                f,
+                "Check sums of [{}] do not agree: [{}] in DVI vs. [{}] in TFM",
+                font, dvi, tfm
+            ),
+            FontWarning::DesignSizeMismatch { ref font, dvi, tfm } => write!(
+                f,
+                "Design sizes of [{}] do not agree: [{}] in DVI vs. [{}] in TFM",
+                font, dvi, tfm
+            ),
+        }
+    }
+}
+
+/// Compares font definitions with their metrics the way dvitype does: check sums
+/// only disagree if both are nonzero, design sizes may differ by two DVI units.
+#[derive(Clone, Debug)]
+pub struct FontVerifier {
+    tfm_conv: f64,
+}
+
+impl Default for FontVerifier {
+    fn default() -> FontVerifier {
+        FontVerifier::new(TEX_NUMERATOR, TEX_DENOMINATOR)
+    }
+}
+
+impl FontVerifier {
+    /// Creates a verifier for the units given by `num` and `den` of the preamble
+    pub fn new(num: u32, den: u32) -> FontVerifier {
+        let tex_num = f64::from(TEX_NUMERATOR);
+        let tex_den = f64::from(TEX_DENOMINATOR);
+        let tfm_conv = (tex_num / f64::from(num)) * (f64::from(den) / tex_den) / 16.0;
+        FontVerifier { tfm_conv }
+    }
+
+    /// The design size of the metrics in DVI units
+    pub fn design_size(&self, tfm: &TexFontMetric) -> i64 {
+        let raw = tfm.header.design_size.raw();
+        (self.tfm_conv * f64::from(raw)).round() as i64
+    }
+
+    pub fn verify(
+        &self,
+        font: &str,
+        checksum: u32,
+        design_size: u32,
+        tfm: &TexFontMetric,
+    ) -> Vec<FontWarning> {
+        let mut warnings = Vec::new();
+
+        let tfm_checksum = tfm.header.checksum;
+        if checksum != 0 && tfm_checksum != 0 && checksum != tfm_checksum {
+            warnings.push(FontWarning::ChecksumMismatch {
+                font: font.to_string(),
+                dvi: checksum,
+                tfm: tfm_checksum,
+            });
+        }
+
+        let tfm_design_size = self.design_size(tfm);
+        if (tfm_design_size - i64::from(design_size)).abs() > 2 {
+            warnings.push(FontWarning::DesignSizeMismatch {
+                font: font.to_string(),
+                dvi: design_size,
+                tfm: tfm_design_size,
+            });
+        }
+
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fonts::tfm::builder::TfmBuilder;
+    use crate::fonts::verification::*;
+
+    #[test]
+    fn test_verify_matching() {
+        let verifier = FontVerifier::default();
+        let tfm = TfmBuilder::new().checksum(0xDEAD).build();
+
+        let warnings = verifier.verify("cmr10", 0xDEAD, 655360, &tfm);
+
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_verify_ignores_zero_checksum() {
+        let verifier = FontVerifier::default();
+        let tfm = TfmBuilder::new().checksum(0xDEAD).build();
+        let tfm_without_checksum = TfmBuilder::new().build();
+
+        assert!(verifier.verify("cmr10", 0, 655360, &tfm).is_empty());
+        assert!(verifier
+            .verify("cmr10", 0xDEAD, 655360, &tfm_without_checksum)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_verify_mismatches() {
+        let verifier = FontVerifier::default();
+        let tfm = TfmBuilder::new().checksum(0xDEAD).build();
+
+        let warnings = verifier.verify("cmr10", 0xBEEF, 655363, &tfm);
+
+        assert_eq!(
+            warnings,
+            vec![
+                FontWarning::ChecksumMismatch {
+                    font: "cmr10".to_string(),
+                    dvi: 0xBEEF,
+                    tfm: 0xDEAD,
+                },
+                FontWarning::DesignSizeMismatch {
+                    font: "cmr10".to_string(),
+                    dvi: 655363,
+                    tfm: 655360,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_verify_design_size_tolerance() {
+        let verifier = FontVerifier::default();
+        let tfm = TfmBuilder::new().build();
+
+        assert!(verifier.verify("cmr10", 0, 655362, &tfm).is_empty());
+        assert!(verifier.verify("cmr10", 0, 655358, &tfm).is_empty());
+    }
+}
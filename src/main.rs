@@ -1,6 +1,5 @@
-use std::error::Error;
-use std::fs::File;
-use std::io::prelude::*;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Write};
 use std::path::Path;
 use std::process;
 
@@ -8,8 +7,18 @@ extern crate clap;
 use clap::{App, AppSettings, Arg, SubCommand};
 
 extern crate dvious;
-use dvious::dvi::disassembler::disassemble;
+use dvious::dvi::asm::{assemble, dump, Unit};
+use dvious::dvi::bbox::bounding_box;
+use dvious::dvi::disassembler::{disassemble, SpannedOpCodeIter};
+use dvious::dvi::dvitype::{dvitype, DvitypeOptions, OutMode};
+use dvious::dvi::interpreter::Interpreter;
 use dvious::dvi::opcodes::OpCode;
+use dvious::dvi::page::Page;
+use dvious::dvi::text::TextExtractor;
+use dvious::dvi::units::DviUnits;
+use dvious::fonts::encoding::Encoding;
+use dvious::fonts::resolver::KpsewhichResolver;
+use dvious::util::dimension::Dimension;
 
 #[allow(unused_variables)]
 fn main() {
@@ -18,6 +27,25 @@ fn main() {
         .author("Jan-Christoph Klie <git@mrklie.com>")
         .about("Toolkit for DVI files")
         .setting(AppSettings::SubcommandRequired)
+        .subcommand(
+            SubCommand::with_name("assemble")
+                .about("Assembles a DVI file from the text format of dviasm")
+                .version("0.1.0")
+                .arg(
+                    Arg::with_name("INPUT")
+                        .help("Sets the input file to use")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Writes the DVI file to FILE instead of stdout")
+                        .takes_value(true),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("disassemble")
                 .about("Disassembles the specified DVI file")
@@ -27,14 +55,164 @@ fn main() {
                         .help("Sets the input file to use")
                         .required(true)
                         .index(1),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .help("Selects the listing of opcodes or the text format of dviasm")
+                        .possible_values(&["opcodes", "dviasm"])
+                        .default_value("opcodes"),
+                )
+                .arg(
+                    Arg::with_name("unit")
+                        .long("unit")
+                        .value_name("UNIT")
+                        .help("Sets the unit of dimensions in the dviasm format")
+                        .possible_values(&["sp", "pt", "bp", "mm", "cm", "in"])
+                        .default_value("pt"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("dvitype")
+                .about("Lists the specified DVI file like dvitype")
+                .version("0.1.0")
+                .arg(
+                    Arg::with_name("INPUT")
+                        .help("Sets the input file to use")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("output-level")
+                        .long("output-level")
+                        .value_name("LEVEL")
+                        .help("Sets the verbosity from 0 (errors only) to 4 (the works)")
+                        .possible_values(&["0", "1", "2", "3", "4"])
+                        .default_value("4"),
+                )
+                .arg(
+                    Arg::with_name("page-start")
+                        .long("page-start")
+                        .value_name("PAGE")
+                        .help("Starts at the first page matching PAGE, e.g. 1.*.-5")
+                        .default_value("*"),
+                )
+                .arg(
+                    Arg::with_name("max-pages")
+                        .long("max-pages")
+                        .value_name("N")
+                        .help("Translates at most N pages")
+                        .default_value("1000000"),
+                )
+                .arg(
+                    Arg::with_name("dpi")
+                        .long("dpi")
+                        .value_name("REAL")
+                        .help("Sets the resolution in pixels per inch")
+                        .default_value("300"),
+                )
+                .arg(
+                    Arg::with_name("magnification")
+                        .long("magnification")
+                        .value_name("MAG")
+                        .help("Overrides the magnification of the DVI file")
+                        .default_value("0"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("pages")
+                .about(
+                    "Lists the glyphs, rules and specials on the pages of the specified DVI file",
+                )
+                .version("0.1.0")
+                .arg(
+                    Arg::with_name("INPUT")
+                        .help("Sets the input file to use")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(unit_arg())
+                .arg(unit_dpi_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("bbox")
+                .about("Prints the bounding box of the ink on every page of the specified DVI file")
+                .version("0.1.0")
+                .arg(
+                    Arg::with_name("INPUT")
+                        .help("Sets the input file to use")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(unit_arg())
+                .arg(unit_dpi_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("text")
+                .about("Extracts the plain text of the specified DVI file")
+                .version("0.1.0")
+                .arg(
+                    Arg::with_name("INPUT")
+                        .help("Sets the input file to use")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("encoding")
+                        .long("encoding")
+                        .value_name("FONT=FILE")
+                        .help(
+                            "Reads the encoding of FONT from an .enc file instead of its TFM file",
+                        )
+                        .multiple(true)
+                        .number_of_values(1),
                 ),
         )
         .get_matches();
 
     let result = match app.subcommand() {
+        ("assemble", Some(sub)) => {
+            let input = sub.value_of("INPUT").unwrap();
+            assemble_file(input, sub.value_of("output"))
+        }
         ("disassemble", Some(sub)) => {
             let input = sub.value_of("INPUT").unwrap();
-            disassemble_file(input)
+            match sub.value_of("format") {
+                Some("dviasm") => {
+                    let unit = sub.value_of("unit").and_then(Unit::from_suffix);
+                    dump_file(input, unit.unwrap_or(Unit::Pt))
+                }
+                _ => disassemble_file(input),
+            }
+        }
+        ("dvitype", Some(sub)) => {
+            let input = sub.value_of("INPUT").unwrap();
+            match dvitype_options(sub) {
+                Ok(options) => dvitype_file(input, &options),
+                Err(why) => Err(why),
+            }
+        }
+        ("pages", Some(sub)) => {
+            let input = sub.value_of("INPUT").unwrap();
+            let unit = sub.value_of("unit").and_then(Unit::from_suffix);
+            match unit_dpi(sub) {
+                Ok(dpi) => pages_file(input, unit, dpi),
+                Err(why) => Err(why),
+            }
+        }
+        ("text", Some(sub)) => {
+            let input = sub.value_of("INPUT").unwrap();
+            let encodings = sub.values_of("encoding").into_iter().flatten().collect();
+            text_file(input, encodings)
+        }
+        ("bbox", Some(sub)) => {
+            let input = sub.value_of("INPUT").unwrap();
+            let unit = sub.value_of("unit").and_then(Unit::from_suffix);
+            match unit_dpi(sub) {
+                Ok(dpi) => bbox_file(input, unit, dpi),
+                Err(why) => Err(why),
+            }
         }
         _ => Ok(()),
     };
@@ -51,28 +229,269 @@ fn main() {
 fn disassemble_file(input: &str) -> Result<(), String> {
     let path = Path::new(input);
     let display = path.display();
-    let mut file = match File::open(&path) {
-        Err(why) => return Err(format!("Could not open {}: {}", display, why.description())),
+    let file = match File::open(path) {
+        Err(why) => return Err(format!("Could not open {}: {}", display, why)),
         Ok(file) => file,
     };
 
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer).unwrap();
+    for result in SpannedOpCodeIter::new(BufReader::new(file)) {
+        let spanned = match result {
+            Err(why) => return Err(format!("{:?}", why)),
+            Ok(spanned) => spanned,
+        };
+
+        println!("{}", spanned);
+    }
+
+    Ok(())
+}
+
+fn dump_file(input: &str, unit: Unit) -> Result<(), String> {
+    let bytes = match fs::read(input) {
+        Err(why) => return Err(format!("Could not open {}: {}", input, why)),
+        Ok(bytes) => bytes,
+    };
 
-    let opcodes = match disassemble(buffer) {
+    let opcodes = match disassemble(bytes) {
         Err(why) => return Err(format!("{:?}", why)),
         Ok(opcodes) => opcodes,
     };
 
-    for opcode in opcodes {
-        match opcode {
-            OpCode::Pre { ref x, .. } | OpCode::Xxx { ref x, .. } => {
-                println!("{} | {}", opcode, String::from_utf8_lossy(x))
-            }
-            OpCode::FntDef { ref n, .. } => println!("{} | {}", opcode, String::from_utf8_lossy(n)),
-            _ => println!("{}", opcode),
+    match dump(&opcodes, unit) {
+        Err(why) => Err(format!("{:?}", why)),
+        Ok(text) => {
+            print!("{}", text);
+            Ok(())
+        }
+    }
+}
+
+fn dvitype_options(sub: &clap::ArgMatches) -> Result<DvitypeOptions, String> {
+    let out_mode = sub
+        .value_of("output-level")
+        .and_then(|level| level.parse::<u8>().ok())
+        .and_then(OutMode::from_level)
+        .ok_or_else(|| "Invalid output level".to_string())?;
+    let start_page = sub
+        .value_of("page-start")
+        .and_then(DvitypeOptions::parse_start_page)
+        .ok_or_else(|| "Invalid starting page".to_string())?;
+    let max_pages = sub
+        .value_of("max-pages")
+        .and_then(|pages| pages.parse::<i32>().ok())
+        .ok_or_else(|| "Invalid maximum number of pages".to_string())?;
+    let resolution = sub
+        .value_of("dpi")
+        .and_then(|dpi| dpi.parse::<f64>().ok())
+        .filter(|&dpi| dpi > 0.0)
+        .ok_or_else(|| "Invalid resolution".to_string())?;
+    let new_mag = sub
+        .value_of("magnification")
+        .and_then(|mag| mag.parse::<i32>().ok())
+        .ok_or_else(|| "Invalid magnification".to_string())?;
+
+    Ok(DvitypeOptions {
+        out_mode,
+        start_page,
+        max_pages,
+        resolution,
+        new_mag,
+    })
+}
+
+fn dvitype_file(input: &str, options: &DvitypeOptions) -> Result<(), String> {
+    let bytes = match fs::read(input) {
+        Err(why) => return Err(format!("Could not open {}: {}", input, why)),
+        Ok(bytes) => bytes,
+    };
+
+    let stdout = io::stdout();
+    let result = dvitype(bytes, options, &mut KpsewhichResolver, &mut stdout.lock());
+    result.map_err(|why| format!("{:?}", why))
+}
+
+/// Lists the pages with dimensions in `unit`, or in pixels at `dpi` if there is none
+fn pages_file(input: &str, unit: Option<Unit>, dpi: f64) -> Result<(), String> {
+    let (interpreter, pages) = interpret(read_opcodes(input)?)?;
+
+    let units = interpreter.units();
+    let format = |dimension: Dimension| format_dimension(dimension, units, unit, dpi);
+    for page in pages {
+        println!("page {}", format_counts(&page.counts));
+        for glyph in &page.glyphs {
+            println!(
+                "glyph {} {} at {} {} width {} height {} depth {}",
+                glyph.font,
+                glyph.c,
+                format(glyph.h),
+                format(glyph.v),
+                format(glyph.width),
+                format(glyph.height),
+                format(glyph.depth)
+            );
+        }
+        for rule in &page.rules {
+            println!(
+                "rule at {} {} width {} height {}",
+                format(rule.h),
+                format(rule.v),
+                format(rule.width),
+                format(rule.height)
+            );
+        }
+        for special in &page.specials {
+            println!(
+                "special at {} {} | {}",
+                format(special.h),
+                format(special.v),
+                String::from_utf8_lossy(&special.x)
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints the text of the pages, separated by form feeds. The encodings are given as
+/// `FONT=FILE`.
+fn text_file(input: &str, encodings: Vec<&str>) -> Result<(), String> {
+    let mut extractor = TextExtractor::new();
+    for encoding in encodings {
+        let (font, path) = match encoding.find('=') {
+            Some(index) => (&encoding[..index], &encoding[index + 1..]),
+            None => return Err(format!("Invalid encoding {}, expected FONT=FILE", encoding)),
+        };
+        match Encoding::read_enc_file(path) {
+            Err(why) => return Err(format!("Could not read {}: {:?}", path, why)),
+            Ok(encoding) => extractor.set_encoding(font, encoding),
+        }
+    }
+
+    let (interpreter, pages) = interpret(read_opcodes(input)?)?;
+
+    let texts: Vec<String> = pages
+        .iter()
+        .map(|page| extractor.page_text(page, &interpreter))
+        .collect();
+    print!("{}", texts.join("\x0c"));
+    Ok(())
+}
+
+/// Prints the bounding box of every page as left, top, right and bottom, followed by
+/// the largest page given in the postamble
+fn bbox_file(input: &str, unit: Option<Unit>, dpi: f64) -> Result<(), String> {
+    let opcodes = read_opcodes(input)?;
+    let post = opcodes.iter().find_map(|opcode| match *opcode {
+        OpCode::Post { l, u, .. } => Some((l, u)),
+        _ => None,
+    });
+    let (interpreter, pages) = interpret(opcodes)?;
+
+    let units = interpreter.units();
+    let format = |dimension: Dimension| format_dimension(dimension, units, unit, dpi);
+    for page in pages {
+        match bounding_box(&page) {
+            Some(bbox) => println!(
+                "page {}: {} {} {} {}",
+                format_counts(&page.counts),
+                format(bbox.left),
+                format(bbox.top),
+                format(bbox.right),
+                format(bbox.bottom)
+            ),
+            None => println!("page {}: empty", format_counts(&page.counts)),
         }
     }
 
+    if let Some((l, u)) = post {
+        println!(
+            "postamble: height+depth {} width {}",
+            format(Dimension::new(i64::from(l))),
+            format(Dimension::new(i64::from(u)))
+        );
+    }
+
     Ok(())
 }
+
+fn read_opcodes(input: &str) -> Result<Vec<OpCode>, String> {
+    let bytes = match fs::read(input) {
+        Err(why) => return Err(format!("Could not open {}: {}", input, why)),
+        Ok(bytes) => bytes,
+    };
+    disassemble(bytes).map_err(|why| format!("{:?}", why))
+}
+
+/// Executes the opcodes with the fonts found by kpsewhich
+fn interpret(opcodes: Vec<OpCode>) -> Result<(Interpreter, Vec<Page>), String> {
+    let mut interpreter = Interpreter::new();
+    let pages = interpreter
+        .execute(opcodes)
+        .map_err(|why| format!("{:?}", why))?;
+    Ok((interpreter, pages))
+}
+
+/// Formats the counters like TeX, without the trailing zeros
+fn format_counts(counts: &[i32; 10]) -> String {
+    let used = counts
+        .iter()
+        .rposition(|&count| count != 0)
+        .map_or(1, |i| i + 1);
+    let counts: Vec<String> = counts[..used].iter().map(i32::to_string).collect();
+    counts.join(".")
+}
+
+/// Formats like the dviasm format: exact values as integers, others with six decimals
+fn format_dimension(dimension: Dimension, units: DviUnits, unit: Option<Unit>, dpi: f64) -> String {
+    let (value, suffix) = match unit {
+        Some(unit) => (units.convert(dimension, unit), unit.suffix()),
+        None => (units.to_pixels(dimension, dpi), "px"),
+    };
+    if unit == Some(Unit::Sp) || value == value.trunc() {
+        format!("{}{}", value.trunc() as i64, suffix)
+    } else {
+        format!("{:.6}{}", value, suffix)
+    }
+}
+
+fn unit_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("unit")
+        .long("unit")
+        .value_name("UNIT")
+        .help("Sets the unit of positions and dimensions")
+        .possible_values(&["sp", "pt", "bp", "mm", "cm", "in", "px"])
+        .default_value("pt")
+}
+
+fn unit_dpi_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("dpi")
+        .long("dpi")
+        .value_name("REAL")
+        .help("Sets the resolution in pixels per inch for the unit px")
+        .default_value("300")
+}
+
+fn unit_dpi(sub: &clap::ArgMatches) -> Result<f64, String> {
+    sub.value_of("dpi")
+        .and_then(|dpi| dpi.parse::<f64>().ok())
+        .filter(|&dpi| dpi > 0.0)
+        .ok_or_else(|| "Invalid resolution".to_string())
+}
+
+fn assemble_file(input: &str, output: Option<&str>) -> Result<(), String> {
+    let text = match fs::read_to_string(input) {
+        Err(why) => return Err(format!("Could not open {}: {}", input, why)),
+        Ok(text) => text,
+    };
+
+    let bytes = match assemble(&text) {
+        Err(why) => return Err(format!("{:?}", why)),
+        Ok(bytes) => bytes,
+    };
+
+    let written = match output {
+        Some(output) => fs::write(output, &bytes),
+        None => io::stdout().write_all(&bytes),
+    };
+    written.map_err(|why| format!("Could not write DVI file: {}", why))
+}
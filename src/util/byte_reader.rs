@@ -1,9 +1,15 @@
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
 use crate::errors::{DviousError, DviousResult};
 use crate::util::num::{i24, u24};
 
-pub struct ByteReader {
+/// Reads big endian numbers from an arbitrary byte source. Bytes that have been
+/// peeked at, but not consumed yet, are kept in a small lookahead buffer.
+pub struct ByteReader<R: Read> {
     position: usize,
-    bytes: Vec<u8>,
+    source: R,
+    lookahead: Vec<u8>,
+    buffer: Vec<u8>,
 }
 
 pub trait Readable {
@@ -11,15 +17,31 @@ pub trait Readable {
     fn size_in_bytes() -> usize;
 }
 
-impl ByteReader {
-    pub fn new(bytes: Vec<u8>) -> ByteReader {
+impl ByteReader<Cursor<Vec<u8>>> {
+    pub fn new(bytes: Vec<u8>) -> ByteReader<Cursor<Vec<u8>>> {
+        ByteReader::from_reader(Cursor::new(bytes))
+    }
+
+    pub fn len(&self) -> usize {
+        self.source.get_ref().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<R: Read> ByteReader<R> {
+    pub fn from_reader(source: R) -> ByteReader<R> {
         ByteReader {
             position: 0,
-            bytes: bytes,
+            source,
+            lookahead: Vec::new(),
+            buffer: Vec::new(),
         }
     }
 
-    pub fn peek_be<T: Readable>(&self) -> DviousResult<T> {
+    pub fn peek_be<T: Readable>(&mut self) -> DviousResult<T> {
         let number_of_bytes = T::size_in_bytes();
         let buffer = self.peek_slice(number_of_bytes)?;
         let result = T::from_u8_be(buffer);
@@ -52,37 +74,40 @@ impl ByteReader {
         Ok(())
     }
 
-    fn peek_slice(&self, n: usize) -> DviousResult<&[u8]> {
-        let start = self.position;
-        let end = self.position + n;
+    /// Makes sure that at least `n` bytes are available in the lookahead buffer.
+    /// Running out of input is reported as `IndexOutOfBoundsError`, every other
+    /// failure of the underlying source as `IoError`.
+    fn fill_lookahead(&mut self, n: usize) -> DviousResult<()> {
+        let missing = n.saturating_sub(self.lookahead.len());
+        if missing > 0 {
+            self.source
+                .by_ref()
+                .take(missing as u64)
+                .read_to_end(&mut self.lookahead)?;
+        }
 
-        if end <= self.bytes.len() {
-            let result = &self.bytes[start..end];
-            Ok(result)
+        if self.lookahead.len() >= n {
+            Ok(())
         } else {
             Err(DviousError::IndexOutOfBoundsError)
         }
     }
 
-    fn read_slice(&mut self, n: usize) -> DviousResult<&[u8]> {
-        let start = self.position;
-        let end = self.position + n;
-
-        if end <= self.bytes.len() {
-            let result = &self.bytes[start..end];
-            self.position += n;
-            Ok(result)
-        } else {
-            Err(DviousError::IndexOutOfBoundsError)
-        }
+    fn peek_slice(&mut self, n: usize) -> DviousResult<&[u8]> {
+        self.fill_lookahead(n)?;
+        Ok(&self.lookahead[..n])
     }
 
-    pub fn has_more(&self) -> bool {
-        self.position < self.bytes.len()
+    fn read_slice(&mut self, n: usize) -> DviousResult<&[u8]> {
+        self.fill_lookahead(n)?;
+        self.buffer.clear();
+        self.buffer.extend(self.lookahead.drain(..n));
+        self.position += n;
+        Ok(&self.buffer)
     }
 
-    pub fn len(&self) -> usize {
-        self.bytes.len()
+    pub fn has_more(&mut self) -> bool {
+        self.fill_lookahead(1).is_ok()
     }
 
     pub fn position(&self) -> usize {
@@ -90,6 +115,21 @@ impl ByteReader {
     }
 }
 
+impl<R: Read + Seek> ByteReader<R> {
+    /// Moves to a new position in the underlying source and returns the resulting
+    /// absolute position. Any bytes that were peeked at are discarded.
+    pub fn seek(&mut self, target: SeekFrom) -> DviousResult<usize> {
+        let target = match target {
+            // The source is ahead of us by the size of the lookahead buffer
+            SeekFrom::Current(offset) => SeekFrom::Start((self.position as i64 + offset) as u64),
+            _ => target,
+        };
+        self.lookahead.clear();
+        self.position = self.source.seek(target)? as usize;
+        Ok(self.position)
+    }
+}
+
 // Unsigned
 
 impl Readable for u8 {
@@ -178,13 +218,15 @@ impl Readable for i32 {
 
 #[cfg(test)]
 mod tests {
-    use crate::util::byte_reader::{ByteReader, i24, u24};
+    use std::io::{Cursor, SeekFrom};
+
+    use crate::util::byte_reader::{i24, u24, ByteReader};
 
     // Peek unsigned
 
     #[test]
     fn test_peek_u8_be() {
-        let reader = get_reader(vec![0x42]);
+        let mut reader = get_reader(vec![0x42]);
         let result = reader.peek_be::<u8>().unwrap();
 
         assert_eq!(result, 0x42);
@@ -192,7 +234,7 @@ mod tests {
 
     #[test]
     fn test_peek_u16_be() {
-        let reader = get_reader(vec![0xDE, 0xAD]);
+        let mut reader = get_reader(vec![0xDE, 0xAD]);
         let result = reader.peek_be::<u16>().unwrap();
 
         assert_eq!(result, 0xDEAD);
@@ -200,7 +242,7 @@ mod tests {
 
     #[test]
     fn test_peek_u24_be() {
-        let reader = get_reader(vec![0xDE, 0xAD, 0xBE]);
+        let mut reader = get_reader(vec![0xDE, 0xAD, 0xBE]);
         let result = reader.peek_be::<u24>().unwrap();
 
         assert_eq!(result, u24::from(0xDEADBE));
@@ -208,7 +250,7 @@ mod tests {
 
     #[test]
     fn test_peek_u32_be() {
-        let reader = get_reader(vec![0xDE, 0xAD, 0xBE, 0xEF]);
+        let mut reader = get_reader(vec![0xDE, 0xAD, 0xBE, 0xEF]);
         let result = reader.peek_be::<u32>().unwrap();
 
         assert_eq!(result, 0xDEADBEEF);
@@ -218,7 +260,7 @@ mod tests {
 
     #[test]
     fn test_peek_i8_be() {
-        let reader = get_reader(vec![0x42]);
+        let mut reader = get_reader(vec![0x42]);
         let result = reader.peek_be::<i8>().unwrap();
 
         assert_eq!(result, 0x42);
@@ -226,7 +268,7 @@ mod tests {
 
     #[test]
     fn test_peek_i16_be() {
-        let reader = get_reader(vec![0x1E, 0xAD]);
+        let mut reader = get_reader(vec![0x1E, 0xAD]);
         let result = reader.peek_be::<i16>().unwrap();
 
         assert_eq!(result, 0x1EAD);
@@ -234,7 +276,7 @@ mod tests {
 
     #[test]
     fn test_peek_i24_be() {
-        let reader = get_reader(vec![0xDE, 0xAD, 0xBE]);
+        let mut reader = get_reader(vec![0xDE, 0xAD, 0xBE]);
         let result = reader.peek_be::<i24>().unwrap();
 
         assert_eq!(result, i24::from(0xDEADBE));
@@ -242,7 +284,7 @@ mod tests {
 
     #[test]
     fn test_peek_i32_be() {
-        let reader = get_reader(vec![0x0E, 0xAD, 0xBE, 0xEF]);
+        let mut reader = get_reader(vec![0x0E, 0xAD, 0xBE, 0xEF]);
         let result = reader.peek_be::<i32>().unwrap();
 
         assert_eq!(result, 0x0EADBEEF);
@@ -368,7 +410,7 @@ mod tests {
 
     #[test]
     fn test_has_more() {
-        let reader = get_reader(vec![0xDE, 0xAD]);
+        let mut reader = get_reader(vec![0xDE, 0xAD]);
 
         assert!(reader.has_more(), "Expected that reader has more");
     }
@@ -381,6 +423,44 @@ mod tests {
         assert!(!reader.has_more(), "Expected that reader has no more");
     }
 
+    // Streaming
+
+    #[test]
+    fn test_read_from_reader() {
+        let source: &[u8] = &[0xAA, 0xBB, 0xBB];
+        let mut reader = ByteReader::from_reader(source);
+
+        assert_eq!(reader.peek_be::<u8>().unwrap(), 0xAA);
+        assert_eq!(reader.read_be::<u8>().unwrap(), 0xAA);
+        assert_eq!(reader.read_be::<u16>().unwrap(), 0xBBBB);
+        assert!(!reader.has_more(), "Expected that reader has no more");
+        assert_eq!(reader.position(), 3_usize);
+    }
+
+    // Seek
+
+    #[test]
+    fn test_seek() {
+        let mut reader = get_reader(vec![0xDE, 0xAD, 0xBE, 0xEF]);
+
+        assert_eq!(reader.peek_be::<u8>().unwrap(), 0xDE);
+        assert_eq!(reader.seek(SeekFrom::End(-1)).unwrap(), 3);
+        assert_eq!(reader.read_be::<u8>().unwrap(), 0xEF);
+        assert_eq!(reader.seek(SeekFrom::Start(1)).unwrap(), 1);
+        assert_eq!(reader.read_be::<u16>().unwrap(), 0xADBE);
+    }
+
+    #[test]
+    fn test_seek_relative_after_peek() {
+        let mut reader = get_reader(vec![0xDE, 0xAD, 0xBE, 0xEF]);
+
+        reader.read_be::<u8>().unwrap();
+        reader.peek_be::<u16>().unwrap();
+
+        assert_eq!(reader.seek(SeekFrom::Current(1)).unwrap(), 2);
+        assert_eq!(reader.read_be::<u8>().unwrap(), 0xBE);
+    }
+
     // Len
 
     #[test]
@@ -401,7 +481,7 @@ mod tests {
 
     // Util
 
-    fn get_reader(bytes: Vec<u8>) -> ByteReader {
+    fn get_reader(bytes: Vec<u8>) -> ByteReader<Cursor<Vec<u8>>> {
         ByteReader::new(bytes)
     }
 }
@@ -0,0 +1,103 @@
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+
+/// A length in DVI units, which are TeX scaled points for files written by TeX.
+/// `DviUnits` in `dvi::units` converts it to physical units.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Dimension(i64);
+
+impl Dimension {
+    pub const ZERO: Dimension = Dimension(0);
+
+    pub const fn new(raw: i64) -> Dimension {
+        Dimension(raw)
+    }
+
+    pub fn raw(self) -> i64 {
+        self.0
+    }
+
+    pub fn abs(self) -> Dimension {
+        Dimension(self.0.abs())
+    }
+}
+
+impl From<i32> for Dimension {
+    fn from(raw: i32) -> Dimension {
+        Dimension(i64::from(raw))
+    }
+}
+
+impl Add for Dimension {
+    type Output = Dimension;
+
+    fn add(self, other: Dimension) -> Dimension {
+        Dimension(self.0 + other.0)
+    }
+}
+
+impl AddAssign for Dimension {
+    fn add_assign(&mut self, other: Dimension) {
+        self.0 += other.0;
+    }
+}
+
+impl Sub for Dimension {
+    type Output = Dimension;
+
+    fn sub(self, other: Dimension) -> Dimension {
+        Dimension(self.0 - other.0)
+    }
+}
+
+impl SubAssign for Dimension {
+    fn sub_assign(&mut self, other: Dimension) {
+        self.0 -= other.0;
+    }
+}
+
+impl Neg for Dimension {
+    type Output = Dimension;
+
+    fn neg(self) -> Dimension {
+        Dimension(-self.0)
+    }
+}
+
+impl Mul<i64> for Dimension {
+    type Output = Dimension;
+
+    fn mul(self, factor: i64) -> Dimension {
+        Dimension(self.0 * factor)
+    }
+}
+
+impl Div<i64> for Dimension {
+    type Output = Dimension;
+
+    fn div(self, divisor: i64) -> Dimension {
+        Dimension(self.0 / divisor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::dimension::*;
+
+    #[test]
+    fn test_dimension_arithmetic() {
+        let a = Dimension::new(10);
+        let b = Dimension::from(-4);
+
+        assert_eq!(a + b, Dimension::new(6));
+        assert_eq!(a - b, Dimension::new(14));
+        assert_eq!(-a, Dimension::new(-10));
+        assert_eq!(b * 3, Dimension::new(-12));
+        assert_eq!(a / 3, Dimension::new(3));
+        assert_eq!(b.abs(), Dimension::new(4));
+
+        let mut c = a;
+        c += b;
+        c -= Dimension::new(1);
+        assert_eq!(c.raw(), 5);
+    }
+}
@@ -1,2 +1,3 @@
 pub mod byte_reader;
+pub mod dimension;
 pub mod num;
@@ -1,6 +1,58 @@
-// Fixword
+// FixWord
 
-pub type Fixword = f64;
+/// A TFM fix_word: a signed 32-bit number with 20 bits after the binary point
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FixWord(i32);
+
+impl FixWord {
+    pub const ZERO: FixWord = FixWord(0);
+    pub const ONE: FixWord = FixWord(1 << 20);
+
+    pub fn from_raw(raw: i32) -> FixWord {
+        FixWord(raw)
+    }
+
+    /// Rounds to the nearest fix_word
+    pub fn from_f64(value: f64) -> FixWord {
+        FixWord((value * f64::from(1 << 20)).round() as i32)
+    }
+
+    pub fn raw(self) -> i32 {
+        self.0
+    }
+
+    pub fn to_f64(self) -> f64 {
+        f64::from(self.0) / f64::from(1 << 20)
+    }
+
+    /// Multiplies with the size `z` of a font in scaled points, with the same
+    /// rounding as TeX and dvitype. Like TeX, this only accepts fix words with an
+    /// absolute value below 16 and sizes below 2048pt.
+    pub fn scale(self, z: i32) -> Option<i32> {
+        if !(0..0o1000000000).contains(&z) {
+            return None;
+        }
+
+        let [b0, b1, b2, b3] = self.0.to_be_bytes();
+        let (b1, b2, b3) = (i32::from(b1), i32::from(b2), i32::from(b3));
+
+        let mut z = z;
+        let mut alpha = 16;
+        while z >= 0o40000000 {
+            z /= 2;
+            alpha += alpha;
+        }
+        let beta = 256 / alpha;
+        let alpha = alpha * z;
+
+        let scaled = (((b3 * z) / 256 + (b2 * z)) / 256 + (b1 * z)) / beta;
+        match b0 {
+            0 => Some(scaled),
+            255 => Some(scaled - alpha),
+            _ => None,
+        }
+    }
+}
 
 // u24
 
@@ -49,3 +101,42 @@ impl From<i32> for i24 {
         i24(num)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::util::num::*;
+
+    #[test]
+    fn test_fix_word_conversions() {
+        assert_eq!(FixWord::from_f64(0.6708984375).raw(), 703_488);
+        assert_eq!(FixWord::from_raw(-(1 << 19)).to_f64(), -0.5);
+        assert_eq!(FixWord::ONE.to_f64(), 1.0);
+    }
+
+    #[test]
+    fn test_fix_word_scale() {
+        // The width of `H` in cmr10 at 10pt and 20pt
+        assert_eq!(FixWord::from_raw(786_434).scale(655_360), Some(491_521));
+        assert_eq!(FixWord::from_raw(786_434).scale(1_310_720), Some(983_042));
+        assert_eq!(FixWord::from_raw(-(1 << 19)).scale(655_360), Some(-327_680));
+        assert_eq!(FixWord::from_raw(16 << 20).scale(655_360), None);
+    }
+
+    #[test]
+    fn test_fix_word_scale_rounds_like_tex() {
+        // Negative values are rounded towards negative infinity, not towards zero
+        assert_eq!(FixWord::from_raw(-1).scale(655_360), Some(-1));
+        assert_eq!(FixWord::from_raw(1).scale(655_360), Some(0));
+        // Large sizes lose the lowest bits of the fix word first
+        assert_eq!(
+            FixWord::from_raw(0xFFFFF).scale(0o777777777),
+            Some(134_217_584)
+        );
+    }
+
+    #[test]
+    fn test_fix_word_scale_rejects_large_sizes() {
+        assert_eq!(FixWord::ONE.scale(0o1000000000), None);
+        assert_eq!(FixWord::ONE.scale(-1), None);
+    }
+}
@@ -0,0 +1,69 @@
+// Not every test uses every fixture
+#![allow(dead_code)]
+
+use dvious::fonts::resolver::MemoryResolver;
+use dvious::fonts::tfm::*;
+use dvious::util::num::FixWord;
+
+use std::collections::HashMap;
+
+pub fn cmr10_resolver() -> MemoryResolver {
+    let mut resolver = MemoryResolver::new();
+    resolver.insert("cmr10", cmr10());
+    resolver
+}
+
+pub fn cmr10() -> TexFontMetric {
+    // The widths of the characters in `Hello world! 1`, as fix words of cmr10
+    let widths: &[(u8, i32)] = &[
+        (b'!', 291_271),
+        (b'1', 524_290),
+        (b'H', 786_434),
+        (b'd', 582_543),
+        (b'e', 466_035),
+        (b'l', 291_271),
+        (b'o', 524_290),
+        (b'r', 410_699),
+        (b'w', 757_306),
+    ];
+
+    let mut width_table = vec![FixWord::ZERO];
+    let mut char_info_table = HashMap::new();
+    for &(c, width) in widths {
+        width_table.push(FixWord::from_raw(width));
+        let info = TfmCharInfo {
+            character: c,
+            width_index: (width_table.len() - 1) as u8,
+            height_index: 0,
+            depth_index: 0,
+            italic_index: 0,
+            tag: TfmCharInfoTag::None,
+        };
+        char_info_table.insert(c, info);
+    }
+
+    TexFontMetric {
+        header: TfmMetricHeader {
+            checksum: 1_274_110_073,
+            design_size: FixWord::from_f64(10.0),
+            encoding: Some("TeX text".to_string()),
+            font_identifier: None,
+            seven_bit_safe: false,
+            face: None,
+            misc: Vec::new(),
+        },
+        char_info_table,
+        width_table,
+        heigth_table: vec![FixWord::ZERO],
+        depth_table: vec![FixWord::ZERO],
+        italic_table: vec![FixWord::ZERO],
+        lig_kern_table: vec![],
+        kern_table: vec![],
+        extension_table: vec![],
+        // slant, space, space_stretch, space_shrink, x_height, quad and extra_space
+        param_table: [0, 349_526, 174_763, 116_509, 451_470, 1_048_579, 116_509]
+            .iter()
+            .map(|&param| FixWord::from_raw(param))
+            .collect(),
+    }
+}
@@ -0,0 +1,32 @@
+extern crate dvious;
+mod common;
+
+use common::cmr10_resolver;
+use dvious::dvi::bbox::{bounding_box, BoundingBox};
+use dvious::dvi::disassembler::disassemble;
+use dvious::dvi::interpreter::Interpreter;
+use dvious::util::dimension::Dimension;
+
+#[test]
+fn test_bounding_box_within_postamble() {
+    let bytes = std::fs::read("tests/data/helloworld.dvi").unwrap();
+    let mut interpreter = Interpreter::with_resolver(cmr10_resolver());
+
+    let pages = interpreter.execute(disassemble(bytes).unwrap()).unwrap();
+
+    // The glyphs of the fixture have no height or depth, so the box runs from the
+    // baseline of `Hello world!` to the baseline of the page number
+    let bbox = bounding_box(&pages[0]).unwrap();
+    assert_eq!(
+        bbox,
+        BoundingBox {
+            left: Dimension::new(5_046_272),
+            top: Dimension::new(4_128_768),
+            right: Dimension::new(15_204_352 + 327_681),
+            bottom: Dimension::new(41_484_288),
+        }
+    );
+    // The postamble gives l = 41484288 and u = 26673152
+    assert!(bbox.bottom <= Dimension::new(41_484_288));
+    assert!(bbox.width() <= Dimension::new(26_673_152));
+}
@@ -0,0 +1,34 @@
+extern crate dvious;
+use dvious::dvi::disassembler::disassemble_spanned;
+
+#[test]
+fn test_disassembly_listing() {
+    let bytes = std::fs::read("tests/data/helloworld.dvi").unwrap();
+
+    let lines: Vec<String> = disassemble_spanned(bytes)
+        .unwrap()
+        .iter()
+        .map(|spanned| spanned.to_string())
+        .collect();
+
+    assert_eq!(
+        lines[0],
+        "0: pre 2 25400000/473628672 1000 ' TeX output 2017.11.10:0013'"
+    );
+    for line in &[
+        "42: bop 1 0 0 0 0 0 0 0 0 0",
+        "87: down4 41484288",
+        "92: push",
+        "93: down4 -39649280",
+        "114: fntdef1 7: cmr10",
+        "135: fntnum7",
+        "136: set_char_72",
+        "156: down3 1966080",
+        "169: eop",
+        "170: post 25400000/473628672 1000 maxv=41484288, maxh=26673152, \
+         maxstackdepth=3, totalpages=1",
+    ] {
+        assert!(lines.iter().any(|l| l == line), "Missing [{}]", line);
+    }
+    assert!(lines.last().unwrap().ends_with(": post_post 2"));
+}
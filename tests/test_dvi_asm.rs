@@ -0,0 +1,25 @@
+extern crate dvious;
+use dvious::dvi::asm::{assemble, dump, Unit};
+use dvious::dvi::disassembler::disassemble;
+
+#[test]
+fn test_dump_like_dviasm() {
+    let bytes = std::fs::read("tests/data/helloworld.dvi").unwrap();
+    let expected = std::fs::read_to_string("tests/data/helloworld.dump").unwrap();
+    let opcodes = disassemble(bytes).unwrap();
+
+    let text = dump(&opcodes, Unit::Pt).unwrap();
+
+    assert_eq!(text, expected);
+}
+
+#[test]
+fn test_dump_reassembles() {
+    let expected = std::fs::read_to_string("tests/data/helloworld.dump").unwrap();
+    let bytes = assemble(&expected).unwrap();
+    let opcodes = disassemble(bytes).unwrap();
+
+    let text = dump(&opcodes, Unit::Pt).unwrap();
+
+    assert_eq!(text, expected);
+}
@@ -0,0 +1,13 @@
+extern crate dvious;
+use dvious::dvi::disassembler::disassemble_spanned;
+use dvious::dvi::encoder::encode_spanned;
+
+#[test]
+fn test_roundtrip_is_byte_identical() {
+    let bytes = std::fs::read("tests/data/helloworld.dvi").unwrap();
+    let opcodes = disassemble_spanned(bytes.clone()).unwrap();
+
+    let encoded = encode_spanned(&opcodes).unwrap();
+
+    assert_eq!(encoded, bytes);
+}
@@ -0,0 +1,35 @@
+extern crate dvious;
+use dvious::dvi::disassembler::disassemble;
+use dvious::dvi::file::DviFile;
+use dvious::dvi::opcodes::OpCode;
+
+const HELLO_WORLD: &str = "tests/data/helloworld.dvi";
+
+#[test]
+fn test_open_dvi_file() {
+    let dvi = DviFile::open(HELLO_WORLD).unwrap();
+
+    assert_eq!(dvi.page_count(), 1);
+    assert_eq!(dvi.page_offsets(), &[42]);
+    assert_eq!(dvi.font_definitions().len(), 1);
+
+    if let OpCode::Post { p, t, .. } = dvi.postamble() {
+        assert_eq!(*p, Some(42));
+        assert_eq!(*t, 1);
+    } else {
+        panic!("Expected postamble, was {:?}", dvi.postamble());
+    }
+}
+
+#[test]
+fn test_page_matches_sequential_disassembly() {
+    let mut dvi = DviFile::open(HELLO_WORLD).unwrap();
+    let bytes = std::fs::read(HELLO_WORLD).unwrap();
+    let opcodes = disassemble(bytes).unwrap();
+
+    let page = dvi.page(0).unwrap();
+
+    assert_eq!(page.first(), Some(&opcodes[1]));
+    assert_eq!(page.last(), Some(&OpCode::Eop));
+    assert_eq!(page, &opcodes[1..page.len() + 1]);
+}
@@ -0,0 +1,49 @@
+extern crate dvious;
+mod common;
+
+use common::cmr10;
+use dvious::dvi::disassembler::disassemble;
+use dvious::dvi::opcodes::OpCode;
+use dvious::dvi::writer::{write_dvi, DviWriter};
+
+#[test]
+fn test_write_roundtrip() {
+    let bytes = std::fs::read("tests/data/helloworld.dvi").unwrap();
+    let opcodes = disassemble(bytes.clone()).unwrap();
+
+    let written = write_dvi(&opcodes).unwrap();
+
+    assert_eq!(written, bytes);
+}
+
+#[test]
+fn test_write_generated_postamble() {
+    let bytes = std::fs::read("tests/data/helloworld.dvi").unwrap();
+    let opcodes = disassemble(bytes).unwrap();
+    let pages: Vec<OpCode> = opcodes
+        .iter()
+        .take_while(|opcode| !matches!(opcode, OpCode::Post { .. }))
+        .cloned()
+        .collect();
+
+    let mut writer = DviWriter::new(Vec::new());
+    writer.set_font_metrics(7, cmr10());
+    for opcode in &pages {
+        writer.write(opcode).unwrap();
+    }
+    writer.write_postamble().unwrap();
+    let written = disassemble(writer.finish().unwrap()).unwrap();
+
+    match written[pages.len()] {
+        OpCode::Post { p, l, u, s, t, .. } => {
+            assert_eq!(p, Some(42));
+            assert_eq!(l, 41_484_288);
+            // The page number `1` is the rightmost glyph on the page
+            assert_eq!(u, 15_204_352 + 327_681);
+            assert_eq!(s, 3);
+            assert_eq!(t, 1);
+        }
+        ref other => panic!("Expected post, was {:?}", other),
+    }
+    assert_eq!(&written[pages.len() + 1..], &opcodes[pages.len() + 1..]);
+}
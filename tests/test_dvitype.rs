@@ -0,0 +1,135 @@
+extern crate dvious;
+mod common;
+
+use common::{cmr10, cmr10_resolver};
+use dvious::dvi::dvitype::{dvitype, DvitypeOptions, OutMode};
+use dvious::fonts::resolver::{KpsewhichResolver, MemoryResolver};
+use dvious::util::num::FixWord;
+
+use std::process::Command;
+
+/// Compares the listings of both output levels with the ones of Knuth's dvitype,
+/// which needs a TeX installation
+#[test]
+#[ignore]
+fn test_dvitype_matches_knuths_dvitype() {
+    for &(out_mode, level) in &[(OutMode::Terse, 1), (OutMode::TheWorks, 4)] {
+        let bytes = std::fs::read("tests/data/helloworld.dvi").unwrap();
+        let output = Command::new("dvitype")
+            .arg(format!("-output-level={}", level))
+            .arg("-dpi=300")
+            .arg("tests/data/helloworld.dvi")
+            .output()
+            .unwrap();
+        let expected = String::from_utf8(output.stdout).unwrap();
+        let options = DvitypeOptions {
+            out_mode,
+            ..DvitypeOptions::default()
+        };
+        let mut out = Vec::new();
+
+        dvitype(bytes, &options, &mut KpsewhichResolver, &mut out).unwrap();
+
+        // Distributions add their name to the version in the first line
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(
+            text.lines().skip(1).collect::<Vec<_>>(),
+            expected.lines().skip(1).collect::<Vec<_>>()
+        );
+    }
+}
+
+/// The listing of this port, which keeps its output from changing unnoticed in
+/// environments without TeX
+#[test]
+fn test_dvitype_terse() {
+    let bytes = std::fs::read("tests/data/helloworld.dvi").unwrap();
+    let expected = std::fs::read_to_string("tests/data/helloworld.dvitype").unwrap();
+    let options = DvitypeOptions {
+        out_mode: OutMode::Terse,
+        ..DvitypeOptions::default()
+    };
+    let mut out = Vec::new();
+
+    dvitype(bytes, &options, &mut cmr10_resolver(), &mut out).unwrap();
+
+    assert_eq!(String::from_utf8(out).unwrap(), expected);
+}
+
+#[test]
+fn test_dvitype_the_works_reports_positions() {
+    let bytes = std::fs::read("tests/data/helloworld.dvi").unwrap();
+    let mut out = Vec::new();
+
+    dvitype(
+        bytes,
+        &DvitypeOptions::default(),
+        &mut cmr10_resolver(),
+        &mut out,
+    )
+    .unwrap();
+
+    let text = String::from_utf8(out).unwrap();
+    assert!(text.contains("Font 7: cmr10---loaded at size 655360 DVI units \n"));
+    assert!(text.contains("135: fntnum7 current font is cmr10 \n"));
+    assert!(text.contains("136: setchar72 h:=5046272+491521=5537793, hh:=351 \n"));
+    assert!(text.contains("[Hello world!]\n"));
+    assert!(text.ends_with("169: eop \n"));
+}
+
+#[test]
+fn test_dvitype_without_tfm() {
+    let bytes = std::fs::read("tests/data/helloworld.dvi").unwrap();
+    let options = DvitypeOptions {
+        out_mode: OutMode::ErrorsOnly,
+        ..DvitypeOptions::default()
+    };
+    let mut out = Vec::new();
+
+    dvitype(bytes, &options, &mut MemoryResolver::new(), &mut out).unwrap();
+
+    let text = String::from_utf8(out).unwrap();
+    assert!(text.contains("Font 7: cmr10---not loaded, TFM file can't be opened! \n"));
+    assert!(text.contains("136: character 72 invalid in font UNDEFINED! \n"));
+}
+
+#[test]
+fn test_dvitype_checks_fonts() {
+    let bytes = std::fs::read("tests/data/helloworld.dvi").unwrap();
+    let options = DvitypeOptions {
+        out_mode: OutMode::ErrorsOnly,
+        ..DvitypeOptions::default()
+    };
+    let mut stale = cmr10();
+    stale.header.checksum = 1;
+    stale.header.design_size = FixWord::from_f64(12.0);
+    let mut resolver = MemoryResolver::new();
+    resolver.insert("cmr10", stale);
+    let mut out = Vec::new();
+
+    dvitype(bytes, &options, &mut resolver, &mut out).unwrap();
+
+    let text = String::from_utf8(out).unwrap();
+    assert!(text.contains(
+        "Font 7: cmr10---beware: check sums do not agree!\n   (1274110073 vs. 1)\n   \
+         ---beware: design sizes do not agree!\n   (655360 vs. 786432)\n   \
+         ---loaded at size 655360 DVI units \n"
+    ));
+}
+
+#[test]
+fn test_dvitype_aborts_on_truncated_file() {
+    let mut bytes = std::fs::read("tests/data/helloworld.dvi").unwrap();
+    bytes.truncate(150);
+    let options = DvitypeOptions {
+        out_mode: OutMode::Terse,
+        ..DvitypeOptions::default()
+    };
+    let mut out = Vec::new();
+
+    let result = dvitype(bytes, &options, &mut cmr10_resolver(), &mut out);
+
+    assert!(result.is_err());
+    let text = String::from_utf8(out).unwrap();
+    assert!(text.ends_with(" Bad DVI file: the file ended prematurely!\n"));
+}
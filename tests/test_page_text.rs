@@ -0,0 +1,17 @@
+extern crate dvious;
+mod common;
+
+use common::cmr10_resolver;
+use dvious::dvi::disassembler::disassemble;
+use dvious::dvi::interpreter::Interpreter;
+use dvious::dvi::text::page_text;
+
+#[test]
+fn test_page_text() {
+    let bytes = std::fs::read("tests/data/helloworld.dvi").unwrap();
+    let mut interpreter = Interpreter::with_resolver(cmr10_resolver());
+
+    let pages = interpreter.execute(disassemble(bytes).unwrap()).unwrap();
+
+    assert_eq!(page_text(&pages[0], &interpreter), "Hello world!\n1\n");
+}
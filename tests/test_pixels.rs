@@ -0,0 +1,28 @@
+extern crate dvious;
+mod common;
+
+use common::cmr10_resolver;
+use dvious::dvi::disassembler::disassemble;
+use dvious::dvi::interpreter::Interpreter;
+use dvious::dvi::page::PixelPosition;
+use dvious::dvi::pixels::PixelRounding;
+
+#[test]
+fn test_interpreter_pixel_positions() {
+    let bytes = std::fs::read("tests/data/helloworld.dvi").unwrap();
+    let mut interpreter = Interpreter::with_resolver(cmr10_resolver());
+    interpreter.set_pixel_rounding(PixelRounding::new(300.0));
+
+    let pages = interpreter.execute(disassemble(bytes).unwrap()).unwrap();
+
+    // The values of hh and vv before every setchar at 300dpi
+    let hh = [320, 351, 369, 381, 393, 427, 456, 477, 493, 505, 528, 963];
+    let vv = [262, 262, 262, 262, 262, 262, 262, 262, 262, 262, 262, 2628];
+    let expected: Vec<_> = hh
+        .iter()
+        .zip(vv.iter())
+        .map(|(&hh, &vv)| Some(PixelPosition { hh, vv }))
+        .collect();
+    let pixels: Vec<_> = pages[0].glyphs.iter().map(|glyph| glyph.pixels).collect();
+    assert_eq!(pixels, expected);
+}
@@ -1,6 +1,8 @@
 extern crate dvious;
 use dvious::fonts::kpsewhich::get_path_to_tfm;
 use dvious::fonts::tfm::*;
+use dvious::util::dimension::Dimension;
+use dvious::util::num::FixWord;
 
 #[test]
 fn test_parse_tfm_file() {
@@ -8,7 +10,41 @@ fn test_parse_tfm_file() {
     let tfm = read_tfm_from_file(path).unwrap();
 
     assert_eq!(tfm.header.checksum, 1_274_110_073);
-    assert_eq!(tfm.header.design_size, 10.);
+    assert_eq!(tfm.header.design_size.to_f64(), 10.);
     assert_eq!(tfm.header.encoding, Some(String::from("TeX text")));
     assert_eq!(tfm.header.font_identifier, Some(String::from("CMR")));
+
+    let a = tfm.char_metrics(b'A', Dimension::new(655_360)).unwrap();
+    assert_eq!(a.width, Dimension::new(491_521));
+    assert!(a.height > Dimension::ZERO);
+    assert_eq!(a.depth, Dimension::ZERO);
+    assert_eq!(a.italic, Dimension::ZERO);
+}
+
+#[test]
+fn test_write_tfm_round_trip() {
+    // Assembled by hand from the description of the TFM format, with a lig/kern
+    // program, an extensible recipe and a character missing between bc and ec
+    let bytes = std::fs::read("tests/data/testfont.tfm").unwrap();
+    let tfm = read_tfm(bytes.clone()).unwrap();
+
+    assert_eq!(tfm.header.font_identifier, Some(String::from("TESTFONT")));
+    assert!(tfm.header.seven_bit_safe);
+    assert_eq!(tfm.header.face, Some(0x04));
+    assert_eq!(write_tfm(&tfm).unwrap(), bytes);
+}
+
+/// Needs the cmr10.tfm of a TeX installation
+#[test]
+#[ignore]
+fn test_write_tfm_round_trip_of_cmr10() {
+    let path = get_path_to_tfm("cmr10").unwrap();
+    let bytes = std::fs::read(path).unwrap();
+    let mut tfm = read_tfm(bytes.clone()).unwrap();
+
+    assert_eq!(write_tfm(&tfm).unwrap(), bytes);
+
+    tfm.kern_table[0] = FixWord::from_f64(-0.125);
+    let patched = read_tfm(write_tfm(&tfm).unwrap()).unwrap();
+    assert_eq!(patched, tfm);
 }
@@ -0,0 +1,23 @@
+extern crate dvious;
+mod common;
+
+use common::cmr10_resolver;
+use dvious::dvi::disassembler::disassemble;
+use dvious::dvi::interpreter::Interpreter;
+use dvious::dvi::units::{DviUnits, Unit};
+use dvious::util::dimension::Dimension;
+
+#[test]
+fn test_interpreter_units_from_preamble() {
+    let bytes = std::fs::read("tests/data/helloworld.dvi").unwrap();
+    let mut interpreter = Interpreter::with_resolver(cmr10_resolver());
+
+    let pages = interpreter.execute(disassemble(bytes).unwrap()).unwrap();
+
+    let units = interpreter.units();
+    assert_eq!(units, DviUnits::default());
+    let h = pages[0].glyphs[0].h;
+    assert_eq!(h, Dimension::new(5_046_272));
+    assert!((units.convert(h, Unit::Pt) - 77.0).abs() < 1e-9);
+    assert_eq!(units.to_pixels(h, 300.0).round(), 320.0);
+}